@@ -0,0 +1,17 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out vec4 out_color;
+
+uniform sampler2D hdr_texture;
+uniform float bloom_threshold;
+
+void main() {
+    vec3 color = texture(hdr_texture, v_frag_texcoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+
+    /* Only the part above the threshold glows */
+    float weight = max(brightness - bloom_threshold, 0.0) / max(brightness, 0.0001);
+    out_color = vec4(color * weight, 1.0);
+}
@@ -0,0 +1,28 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out vec4 out_color;
+
+uniform sampler2D source_texture;
+uniform bool is_horizontal;
+
+/// Gaussian weights of the center and side taps.
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+/// Taps are that many texels apart to widen the blur.
+const float TAP_STEP = 2.0;
+
+void main() {
+    vec2 dir = is_horizontal ? vec2(1.0, 0.0) : vec2(0.0, 1.0);
+    vec2 offset = dir * TAP_STEP / vec2(textureSize(source_texture, 0));
+
+    vec3 color = texture(source_texture, v_frag_texcoord).rgb * weights[0];
+
+    for (int i = 1; i < 5; ++i) {
+        color += texture(source_texture, v_frag_texcoord + offset * i).rgb * weights[i];
+        color += texture(source_texture, v_frag_texcoord - offset * i).rgb * weights[i];
+    }
+
+    out_color = vec4(color, 1.0);
+}
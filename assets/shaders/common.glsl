@@ -0,0 +1,14 @@
+/// Shared by shaders with `#include "common.glsl"`.
+/// `Z_NEAR`, `Z_FAR`, `MAX_CASCADES` and others are injected from cfg, see `graphics::shader`.
+
+const float PI = 3.1415926;
+
+/// Colors are given in sRGB, lighting is done in linear space.
+vec3 to_linear(vec3 color) {
+    return pow(color, vec3(2.2));
+}
+
+/// Converts depth buffer value to distance from the camera.
+float linearize_depth(float d) {
+    return Z_NEAR * Z_FAR / (Z_FAR + d * (Z_NEAR - Z_FAR));
+}
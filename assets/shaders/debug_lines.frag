@@ -8,9 +8,13 @@ in vec3 v_position;
 out vec3 out_albedo;
 out vec3 out_normal;
 out vec3 out_position;
+out vec3 out_material;
+out vec2 out_light;
 
 void main() {
     out_albedo = v_color.rgb;
     out_normal = vec3(0.0);
     out_position = v_position;
+    out_material = vec3(1.0, 0.0, 0.0);
+    out_light = vec2(1.0, 0.0);
 }
@@ -1,23 +1,48 @@
 #version 440
 
 /* Input compound */
-in vec2 v_tex_coords;
+in vec3 v_tex_coords;
 in vec3 v_position;
 in mat3 v_to_world;
+in vec2 v_light;
+flat in uint v_material;
 
 /* Output */
 out vec3 out_albedo;
 out vec3 out_normal;
 out vec3 out_position;
+out vec3 out_material;
+out vec2 out_light;
 
-/* Texture samplter */
-uniform sampler2D texture_atlas;
-uniform sampler2D normal_atlas;
+/* Block texture arrays, `v_tex_coords.z` is the layer */
+uniform sampler2DArray block_textures;
+uniform sampler2DArray block_normals;
 uniform bool is_shadow_pass;
+uniform vec3 cam_pos;
+
+/// Roughness, metalness, emission and parallax depth of each voxel type.
+/// See `graphics::material`.
+uniform vec4 materials[MAX_MATERIALS];
 
 void process_shadow();
 void shade_standart();
 
+/// Shifts texture coordinates along the view direction by the surface depth.
+/// Height of the surface is its albedo brightness.
+vec3 get_parallax_coords(vec3 coords, float depth) {
+    if (depth <= 0.0)
+        return coords;
+
+    /* `v_to_world` is orthonormal, so the transpose brings vectors to the face space */
+    vec3 view_dir = normalize(transpose(v_to_world) * (cam_pos - v_position));
+
+    vec3 color = texture(block_textures, coords).rgb;
+    float height = dot(color, vec3(0.2126, 0.7152, 0.0722));
+
+    vec2 offset = view_dir.xy / max(view_dir.z, 0.2) * (height - 1.0) * depth;
+    return vec3(coords.xy + offset, coords.z);
+}
+
 void main() {
     if (is_shadow_pass) {
         process_shadow();
@@ -30,13 +55,18 @@ void process_shadow() {
     out_albedo = vec3(0.0);
     out_normal = vec3(0.0);
     out_position = v_position;
+    out_material = vec3(0.0);
+    out_light = vec2(0.0);
 }
 
 void shade_standart() {
-    vec4 tex_color = texture(texture_atlas, v_tex_coords);
+    vec4 material = materials[v_material];
+    vec3 tex_coords = get_parallax_coords(v_tex_coords, material.w);
+
+    vec4 tex_color = texture(block_textures, tex_coords);
 
     /* load normal from normal map and unexponentiate it */
-    vec3 local_normal = texture(normal_atlas, v_tex_coords).xyz;
+    vec3 local_normal = texture(block_normals, tex_coords).xyz;
     local_normal = vec3(
         pow(local_normal.x, 1.0 / (0.4545 * 0.4545)),
         pow(local_normal.y, 1.0 / (0.4545 * 0.4545)),
@@ -49,4 +79,6 @@ void shade_standart() {
     out_albedo = tex_color.rgb;
     out_normal = v_to_world * local_normal;
     out_position = v_position;
+    out_material = material.xyz;
+    out_light = v_light;
 }
\ No newline at end of file
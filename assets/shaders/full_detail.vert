@@ -4,14 +4,19 @@
 in vec3 position;
 in vec2 tex_coords;
 in uint face_idx;
+in uint layer;
+in uint material;
+in uint light;
 
 /* Output compound */
-out vec2 v_tex_coords;
+out vec3 v_tex_coords;
 out vec3 v_normal;
 out vec3 v_tangent;
 out vec3 v_bitangent;
 out vec3 v_position;
 out mat3 v_to_world;
+out vec2 v_light;
+flat out uint v_material;
 
 uniform float time;
 uniform mat4 proj;
@@ -19,8 +24,10 @@ uniform mat4 view;
 
 uniform vec3 light_dir0;
 uniform vec3 light_pos0;
-uniform mat4 light_proj0;
-uniform mat4 light_view0;
+
+/// Light space matrices of shadow cascades. See `graphics::shadow`.
+uniform mat4 shadow_matrices[MAX_CASCADES];
+uniform int cascade_idx;
 
 uniform bool is_shadow_pass;
 
@@ -58,16 +65,20 @@ void process_shadow() {
     v_position = position;
 
     /* Writing to gl_Position */
-    gl_Position = light_proj0 * light_view0 * vec4(position, 1.0);
+    gl_Position = shadow_matrices[cascade_idx] * vec4(position, 1.0);
 }
 
 void shade_standart() {
     /* Assembling output compound */
-    v_tex_coords = tex_coords;
+    v_tex_coords = vec3(tex_coords, float(layer));
     v_normal = normals[face_idx];
     v_tangent = tangents[face_idx];
     v_bitangent = cross(v_normal, v_tangent);
     v_position = position;
+    v_material = material;
+
+    /* Sky light is in high bits, block light is in low bits */
+    v_light = vec2(float(light >> 4), float(light & 15u)) / 15.0;
 
     mat3 to_local = mat3(
         v_bitangent.x, v_tangent.x, v_normal.x,
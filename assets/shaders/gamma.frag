@@ -0,0 +1,33 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out vec4 out_color;
+
+uniform sampler2D ldr_texture;
+uniform vec2 screen_resolution;
+
+uniform bool is_gamma_enabled;
+uniform float gamma;
+
+bool is_cross() {
+    vec2 crosshair_sizes = vec2(3.5, 21.5);
+
+    vec2 pos = (v_frag_texcoord.xy * 2.0 - 1.0) * screen_resolution;
+    pos = round(pos);
+
+    return abs(pos.x) <= crosshair_sizes.x && abs(pos.y) <= crosshair_sizes.y ||
+        abs(pos.x) <= crosshair_sizes.y && abs(pos.y) <= crosshair_sizes.x;
+}
+
+void main() {
+    vec3 color = texture(ldr_texture, v_frag_texcoord).rgb;
+
+    if (is_gamma_enabled)
+        color = pow(color, vec3(1.0 / gamma));
+
+    if (is_cross())
+        color = (1.0 - color) * 0.5;
+
+    out_color = vec4(color, 1.0);
+}
@@ -0,0 +1,73 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out vec4 out_color;
+
+#include "common.glsl"
+
+uniform sampler2D albedo_texture;
+uniform sampler2D normal_texture;
+uniform sampler2D position_texture;
+
+/* Each column is one light: position and radius, color and spot flag,
+ * spot direction and outer cone cosine, inner cone cosine */
+uniform sampler2D light_data;
+/* Each row is one tile: light count followed by light indices */
+uniform sampler2D light_grid;
+uniform ivec2 light_grid_size;
+
+/// Gives smooth falloff reaching zero at `radius`.
+float get_attenuation(float dist, float radius) {
+    float ratio = dist / radius;
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+
+    return window * window / (dist * dist + 1.0);
+}
+
+vec3 get_light(int idx, vec3 position, vec3 normal) {
+    vec4 pos_radius = texelFetch(light_data, ivec2(idx, 0), 0);
+    vec4 color_spot = texelFetch(light_data, ivec2(idx, 1), 0);
+
+    vec3 to_light = pos_radius.xyz - position;
+    float dist = length(to_light);
+    if (dist >= pos_radius.w)
+        return vec3(0.0);
+
+    to_light /= max(dist, 0.0001);
+
+    float intensity = get_attenuation(dist, pos_radius.w) * max(dot(normal, to_light), 0.0);
+
+    if (color_spot.w != 0.0) {
+        vec4 dir_cos_outer = texelFetch(light_data, ivec2(idx, 2), 0);
+        float cos_inner = texelFetch(light_data, ivec2(idx, 3), 0).r;
+
+        intensity *= smoothstep(dir_cos_outer.w, cos_inner, dot(-to_light, dir_cos_outer.xyz));
+    }
+
+    return color_spot.rgb * intensity;
+}
+
+void main() {
+    vec3 normal = texture(normal_texture, v_frag_texcoord).xyz;
+    if (normal == vec3(0.0)) {
+        out_color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec3 position = texture(position_texture, v_frag_texcoord).xyz;
+    vec3 albedo = to_linear(texture(albedo_texture, v_frag_texcoord).rgb);
+
+    ivec2 tile = min(ivec2(v_frag_texcoord * vec2(light_grid_size)), light_grid_size - 1);
+    int tile_idx = tile.y * light_grid_size.x + tile.x;
+    int count = int(texelFetch(light_grid, ivec2(0, tile_idx), 0).r);
+
+    vec3 light = vec3(0.0);
+
+    for (int i = 0; i < count; ++i) {
+        int idx = int(texelFetch(light_grid, ivec2(i + 1, tile_idx), 0).r);
+        light += get_light(idx, position, normal);
+    }
+
+    out_color = vec4(albedo * light, 1.0);
+}
@@ -11,9 +11,8 @@ in float v_time;
 out vec3 out_albedo;
 out vec3 out_normal;
 out vec3 out_position;
-
-uniform sampler2D texture_atlas;
-uniform sampler2D normal_atlas;
+out vec3 out_material;
+out vec2 out_light;
 
 uniform vec3 light_pos0;
 uniform vec3 light_dir0;
@@ -41,10 +40,14 @@ void shade_standart() {
     out_albedo = 0.95 * v_color;
     out_normal = v_normal;
     out_position = v_position;
+    out_material = vec3(0.9, 0.0, 0.0);
+    out_light = vec2(1.0, 0.0);
 }
 
 void process_shadow() {
     out_position = v_position;
     out_albedo = vec3(0.0);
     out_normal = vec3(0.0);
+    out_material = vec3(0.0);
+    out_light = vec2(0.0);
 }
\ No newline at end of file
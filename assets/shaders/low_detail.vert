@@ -18,8 +18,10 @@ uniform mat4 view;
 
 uniform vec3 light_dir0;
 uniform vec3 light_pos0;
-uniform mat4 light_proj0;
-uniform mat4 light_view0;
+
+/// Light space matrices of shadow cascades. See `graphics::shadow`.
+uniform mat4 shadow_matrices[MAX_CASCADES];
+uniform int cascade_idx;
 
 uniform bool is_shadow_pass;
 
@@ -38,7 +40,7 @@ void main() {
 
 void process_shadow() {
     v_position = position;
-    gl_Position = light_proj0 * light_view0 * vec4(position, 1.0);
+    gl_Position = shadow_matrices[cascade_idx] * vec4(position, 1.0);
 }
 
 void shade_standart() {
@@ -4,54 +4,133 @@ in vec2 v_frag_texcoord;
 
 out vec4 out_color;
 
+#include "common.glsl"
+
 uniform sampler2D depth_texture;
 uniform sampler2D albedo_texture;
 uniform sampler2D normal_texture;
 uniform sampler2D position_texture;
-uniform sampler2D light_depth_texture;
+uniform sampler2D material_texture;
+uniform sampler2D light_texture;
+uniform sampler2D ao_texture;
+uniform sampler2D local_light_texture;
 uniform float time;
 
 uniform vec3 light_dir0;
 uniform vec3 light_pos0;
-uniform mat4 light_proj0;
-uniform mat4 light_view0;
 
 uniform vec3 light_dir1;
 uniform vec3 light_pos1;
-uniform mat4 light_proj1;
-uniform mat4 light_view1;
+
+/// Sun shadow cascades. See `graphics::shadow` and `cfg::shadow`.
+uniform sampler2D shadow_map0_texture;
+uniform sampler2D shadow_map1_texture;
+uniform sampler2D shadow_map2_texture;
+uniform sampler2D shadow_map3_texture;
+uniform mat4 shadow_matrices[MAX_CASCADES];
+uniform float cascade_splits[MAX_CASCADES];
+uniform float cascade_texel_sizes[MAX_CASCADES];
+uniform int cascade_count;
 
 uniform vec2 screen_resolution;
 uniform vec3 cam_pos;
+
+/// `front + x * right + y * up` points through screen point `(x, y)`. See `Camera::view_ray_basis`.
+uniform vec3 view_ray_front;
+uniform vec3 view_ray_right;
+uniform vec3 view_ray_up;
 uniform mat4 proj;
 uniform mat4 view;
 uniform bool render_shadows;
+uniform bool is_ssao_enabled;
+uniform bool render_local_lights;
 
-/// These constants are shared. See cfg module.
-vec4 default_color = vec4(0.01, 0.01, 0.01, 1.0);
-float z_near = 0.5;
-float z_far = 10000.0;
+/// Height and distance fog. See `graphics::postprocessing`.
+uniform bool is_fog_enabled;
+uniform float fog_density;
+uniform float fog_height;
+uniform float fog_falloff;
 
-vec3 light_color = vec3(0.4, 0.8, 0.2);
-float shadow_brightness = 0.05;
+/// Day/night cycle colors. See `WorldClock`.
+uniform vec3 sun_color;
+uniform vec3 moon_color;
+uniform vec3 sky_color;
+uniform vec3 fog_color;
+uniform vec3 ambient_color;
 
-float linearize_depth(float d, float z_near, float z_far) {
-    return z_near * z_far / (z_far + d * (z_near - z_far));
-}
+/// Sunlight is that many times brighter than `sun_color`.
+const float SUN_INTENSITY = 4.0;
+
+/// Reflectance of dielectrics at normal incidence.
+const vec3 DIELECTRIC_F0 = vec3(0.04);
+
+float fog_start = 256.0;
+float fog_end = 1024.0;
+vec3 block_light_color = vec3(1.0, 0.72, 0.42);
 
 float get_depth() {
     vec4 depth = texture(depth_texture, v_frag_texcoord);
-    return linearize_depth(depth.r, z_near, z_far);
+    return linearize_depth(depth.r);
+}
+
+/// Gradient sky lit by the sun with Rayleigh and Mie phase functions.
+vec3 get_sky_color(vec3 dir) {
+    vec3 to_sun = -light_dir0;
+    vec3 to_moon = -light_dir1;
+    float sun_cos = dot(dir, to_sun);
+
+    /* Air is thicker near the horizon, so it is lighter there */
+    float height = max(dir.y, 0.0);
+    vec3 sky = mix(to_linear(fog_color), to_linear(sky_color), sqrt(height));
+    if (dir.y < 0.0)
+        sky *= mix(1.0, 0.6, min(-4.0 * dir.y, 1.0));
+
+    float rayleigh = 0.75 * (1.0 + sun_cos * sun_cos);
+
+    /* Henyey-Greenstein phase gives the glow around the sun */
+    float g = 0.76;
+    float mie = (1.0 - g * g) / (4.0 * 3.1415926 * pow(1.0 + g * g - 2.0 * g * sun_cos, 1.5));
+
+    vec3 color = sky * rayleigh + sun_color * mie * 0.2 * (1.0 - 0.5 * height);
+
+    /* Discs are bright enough to bloom */
+    color += sun_color * 20.0 * smoothstep(0.9995, 0.9998, sun_cos);
+    color += moon_color * 4.0 * smoothstep(0.9996, 0.9998, dot(dir, to_moon));
+
+    return color;
+}
+
+/// Direction from the camera through the current pixel.
+vec3 get_view_ray() {
+    vec2 screen_pos = v_frag_texcoord * 2.0 - 1.0;
+    return normalize(view_ray_front + screen_pos.x * view_ray_right + screen_pos.y * view_ray_up);
+}
+
+/// Part of the light from `position` scattered by fog on the way to the camera.
+float get_fog(vec3 position) {
+    vec3 ray = position - cam_pos;
+    float dist = length(ray);
+
+    /* Distance fog hides the edge of the world */
+    float fog = clamp((dist - fog_start) / (fog_end - fog_start), 0.0, 1.0);
+
+    if (!is_fog_enabled)
+        return fog;
+
+    /* Exponential height fog integrated along the view ray */
+    float cam_density = fog_density * exp(-fog_falloff * (cam_pos.y - fog_height));
+    float height_change = fog_falloff * ray.y;
+    float thinning = abs(height_change) > 0.0001
+        ? (1.0 - exp(-height_change)) / height_change
+        : 1.0;
+
+    float height_fog = 1.0 - exp(-cam_density * dist * thinning);
+
+    return max(fog, clamp(height_fog, 0.0, 1.0));
 }
 
 vec3 get_albedo() {
-    vec3 albedo = texture(albedo_texture, v_frag_texcoord).rgb;
-    return vec3(
-        pow(albedo.r, 1.0 / (0.4545 * 0.4545)),
-        pow(albedo.g, 1.0 / (0.4545 * 0.4545)),
-        pow(albedo.b, 1.0 / (0.4545 * 0.4545))
-    );
-    return albedo;
+    return to_linear(texture(albedo_texture, v_frag_texcoord).rgb);
 }
 
 vec3 get_normal() {
@@ -63,67 +142,148 @@ vec3 get_position() {
     return texture(position_texture, v_frag_texcoord).xyz;
 }
 
-float get_light_depth() {
-    float depth = texture(light_depth_texture, v_frag_texcoord).r;
-    return linearize_depth(depth, 1.0, 200.0);
+/// Sky light in `x`, block light in `y`, both in `[0, 1]`.
+vec2 get_voxel_light() {
+    return texture(light_texture, v_frag_texcoord).xy;
 }
 
-float get_shadow_small(vec4 frag_pos, float current_depth) {
-    float shadow_glitch_brightness_shift = 0.13;
+/// Averages noisy SSAO over 4x4 pixels.
+float get_ambient_occlusion() {
+    if (!is_ssao_enabled)
+        return 1.0;
+
+    vec2 texel = 1.0 / vec2(textureSize(ao_texture, 0));
+    float ao = 0.0;
+
+    for (int x = -2; x < 2; ++x) {
+        for (int y = -2; y < 2; ++y) {
+            ao += texture(ao_texture, v_frag_texcoord + (vec2(x, y) + 0.5) * texel).r;
+        }
+    }
+
+    return ao / 16.0;
+}
 
-    vec4 frag_pos_light_space = light_proj0 * light_view0 * frag_pos;
-    vec3 proj_coords = frag_pos_light_space.xyz / frag_pos_light_space.w;
-    proj_coords = proj_coords * 0.5 + 0.5;
+/// Each level of light is 0.8 of the brighter one.
+float light_curve(float level) {
+    return pow(0.8, 15.0 * (1.0 - level));
+}
 
-    if (proj_coords.x < 0.0 || 1.0 < proj_coords.x ||
-            proj_coords.y < 0.0 || 1.0 < proj_coords.y ||
-            proj_coords.z < 0.0 || 1.0 < proj_coords.z)
-    {
+float sample_shadow_map(int cascade, vec2 coords) {
+    switch (cascade) {
+        case 0: return texture(shadow_map0_texture, coords).r;
+        case 1: return texture(shadow_map1_texture, coords).r;
+        case 2: return texture(shadow_map2_texture, coords).r;
+        default: return texture(shadow_map3_texture, coords).r;
+    }
+}
+
+/// Picks the first cascade reaching `depth`, `-1` if there is none.
+int get_cascade(float depth) {
+    for (int i = 0; i < cascade_count; ++i) {
+        if (depth <= cascade_splits[i])
+            return i;
+    }
+
+    return -1;
+}
+
+/// Percentage of 3x3 shadow map texels around the fragment lit by the sun.
+float get_shadow(vec3 position, vec3 normal, float depth) {
+    int cascade = get_cascade(depth);
+    if (cascade == -1)
+        return 1.0;
+
+    /* Offset along the normal hides shadow acne on lit faces */
+    vec3 offset = normal * 1.5 * cascade_texel_sizes[cascade];
+    vec4 light_space = shadow_matrices[cascade] * vec4(position + offset, 1.0);
+    vec3 coords = light_space.xyz / light_space.w * 0.5 + 0.5;
+
+    if (any(lessThan(coords, vec3(0.0))) || any(greaterThan(coords, vec3(1.0))))
         return 1.0;
+
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map0_texture, 0));
+    float bias = 0.00005;
+    float lit = 0.0;
+
+    for (int x = -1; x <= 1; ++x) {
+        for (int y = -1; y <= 1; ++y) {
+            float closest_depth = sample_shadow_map(cascade, coords.xy + vec2(x, y) * texel);
+            lit += coords.z - bias > closest_depth ? 0.0 : 1.0;
+        }
     }
 
-    float closest_depth = texture(light_depth_texture, proj_coords.xy).r;
-    closest_depth = linearize_depth(closest_depth, z_near, z_far);
-    current_depth = linearize_depth(proj_coords.z, z_near, z_far);
-    bool is_shadow = current_depth - 0.0003 > closest_depth;
+    return lit / 9.0;
+}
+
+/// Roughness, metalness and emission written by the geometry pass.
+vec3 get_material() {
+    return texture(material_texture, v_frag_texcoord).xyz;
+}
 
-    out_color = vec4(vec3(closest_depth), 1.0);
+/// GGX normal distribution.
+float distribution_ggx(float n_dot_h, float roughness) {
+    float alpha = roughness * roughness;
+    float alpha_sqr = alpha * alpha;
+    float denom = n_dot_h * n_dot_h * (alpha_sqr - 1.0) + 1.0;
 
-    return is_shadow
-    ? shadow_brightness : 1.0 + shadow_glitch_brightness_shift;
+    return alpha_sqr / (PI * denom * denom);
 }
 
-float get_shadow(vec4 frag_pos, float current_depth) {
-    vec2 offset = 0.2 * vec2(1, 0);
-    frag_pos = floor(frag_pos * 8.0) * 0.125;
+/// Smith geometry term with Schlick-GGX approximation for direct light.
+float geometry_smith(float n_dot_v, float n_dot_l, float roughness) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
 
-    float nearby[6];
-    nearby[0] = get_shadow_small(frag_pos + offset.xyyy, current_depth);
-    nearby[1] = get_shadow_small(frag_pos - offset.xyyy, current_depth);
-    nearby[2] = get_shadow_small(frag_pos + offset.yyxy, current_depth);
-    nearby[3] = get_shadow_small(frag_pos - offset.yyxy, current_depth);
-    nearby[4] = get_shadow_small(frag_pos + offset.yxyy, current_depth);
-    nearby[5] = get_shadow_small(frag_pos - offset.yxyy, current_depth);
+    float view = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float light = n_dot_l / (n_dot_l * (1.0 - k) + k);
 
-    float centre_weight = 0.6;
-    float nearby_weight = 0.1;
+    return view * light;
+}
 
-    float shadow_summary = centre_weight * get_shadow_small(frag_pos, current_depth);
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+/// Cook-Torrance BRDF times the cosine term for light coming from `to_light`.
+vec3 get_brdf(vec3 normal, vec3 to_cam, vec3 to_light, vec3 albedo, vec3 material) {
+    float n_dot_l = dot(normal, to_light);
+    if (n_dot_l <= 0.0)
+        return vec3(0.0);
+
+    float roughness = clamp(material.x, 0.04, 1.0);
+    float metallic = material.y;
+
+    vec3 halfway = normalize(to_cam + to_light);
+    float n_dot_v = max(dot(normal, to_cam), 0.0001);
+    float n_dot_h = max(dot(normal, halfway), 0.0);
 
-    for (uint i = uint(0); i < uint(6); ++i)
-        shadow_summary += nearby[i] * nearby_weight;
+    vec3 f0 = mix(DIELECTRIC_F0, albedo, metallic);
+    vec3 fresnel = fresnel_schlick(max(dot(halfway, to_cam), 0.0), f0);
 
-    return shadow_summary / (centre_weight + 6.0 * nearby_weight);
+    vec3 specular = distribution_ggx(n_dot_h, roughness)
+        * geometry_smith(n_dot_v, n_dot_l, roughness) * fresnel
+        / (4.0 * n_dot_v * n_dot_l);
+
+    /* Metals have no diffuse reflection, the refracted part is absorbed */
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * albedo / PI;
+
+    return (diffuse + specular) * n_dot_l;
 }
 
-bool is_cross() {
-    vec2 crosshair_sizes = vec2(3.5, 21.5);
+/// Light reflected from the sky, approximated by the sky color in the reflected direction.
+vec3 get_ambient(vec3 normal, vec3 to_cam, vec3 albedo, vec3 material) {
+    float roughness = material.x;
+    float metallic = material.y;
 
-    vec2 pos = (v_frag_texcoord.xy * 2.0 - 1.0) * screen_resolution;
-    pos = round(pos);
+    vec3 f0 = mix(DIELECTRIC_F0, albedo, metallic);
+    vec3 fresnel = f0 + (max(vec3(1.0 - roughness), f0) - f0)
+        * pow(1.0 - max(dot(normal, to_cam), 0.0), 5.0);
 
-    return abs(pos.x) <= crosshair_sizes.x && abs(pos.y) <= crosshair_sizes.y ||
-        abs(pos.x) <= crosshair_sizes.y && abs(pos.y) <= crosshair_sizes.x;
+    vec3 reflected = get_sky_color(reflect(-to_cam, normal));
+    vec3 specular = fresnel * mix(reflected, ambient_color, roughness);
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * albedo * ambient_color;
+
+    return diffuse + specular;
 }
 
 void main() {
@@ -131,63 +291,44 @@ void main() {
     vec3 albedo = get_albedo();
     vec3 normal = get_normal();
     vec3 position = get_position();
+    vec2 voxel_light = get_voxel_light();
 
-    float light_depth = 0.0;
-    if (render_shadows)
-        light_depth = get_light_depth();
-
-    if (depth > z_far * 0.5) {
-        out_color = default_color;
-        if (is_cross()) {
-            out_color = (1.0 - out_color) * 0.5;
-            out_color.a = 1.0;
-        }
+    if (depth > Z_FAR * 0.5) {
+        out_color = vec4(get_sky_color(get_view_ray()), 1.0);
         return;
     }
 
     if (normal != vec3(0.0)) {
-        vec3 to_light_dir = -light_dir0;
-        float brightness = max(0.05, dot(normal, to_light_dir));
-
+        vec3 material = get_material();
         vec3 to_cam = normalize(cam_pos - position);
-        vec3 reflected_to_cam = reflect(to_cam, normal);
 
-        float specular_power = 12.0;
-        float specular_multiplier = 0.01;
-        float specular = pow(max(dot(reflected_to_cam, -to_light_dir), 0.0), specular_power) * specular_multiplier;
-
-        float fresnel_power = 20.0;
-        float fresnel_multiplier = 0.002;
-        float fresnel = pow(1.0 - max(dot(to_cam, normal), 0.0), fresnel_power) * fresnel_multiplier;
+        vec3 sun_light = get_brdf(normal, to_cam, -light_dir0, albedo, material) * sun_color;
+        vec3 moon_light = get_brdf(normal, to_cam, -light_dir1, albedo, material) * moon_color;
 
         float shadow = 0.25;
         if (render_shadows)
-            shadow = get_shadow(vec4(position, 1.0), depth);
+            shadow = get_shadow(position, normal, depth);
+
+        float sky_light = light_curve(voxel_light.x);
+        float block_light = voxel_light.y == 0.0 ? 0.0 : light_curve(voxel_light.y);
 
-        out_color = vec4(albedo * brightness + fresnel + specular, 1.0) * shadow * 4.0;
+        /* Colors are given for surfaces facing lights, Lambertian BRDF is `albedo / PI` */
+        vec3 direct = (sun_light * shadow * SUN_INTENSITY + moon_light) * PI;
+        vec3 ambient = get_ambient(normal, to_cam, albedo, material) * get_ambient_occlusion();
+
+        out_color = vec4((ambient + direct) * sky_light, 1.0);
+        out_color.rgb += albedo * block_light_color * block_light * (1.0 - material.y);
+        out_color.rgb += albedo * material.z;
+
+        if (render_local_lights)
+            out_color.rgb += texture(local_light_texture, v_frag_texcoord).rgb;
     }
 
     if (normal == vec3(0.0)) {
         out_color = vec4(albedo, 1.0);
     }
 
-    /* Simple gamma-correction */
-    out_color = vec4(
-            pow(out_color.r, 0.4545),
-            pow(out_color.g, 0.4545),
-            pow(out_color.b, 0.4545),
-            1.0
-        );
-
-    out_color = vec4(
-            pow(out_color.r, 0.4545),
-            pow(out_color.g, 0.4545),
-            pow(out_color.b, 0.4545),
-            1.0
-        );
-
-    if (is_cross()) {
-        out_color = (1.0 - out_color) * 0.5;
-        out_color.a = 1.0;
-    }
+    /* Far terrain fades into the sky behind it */
+    vec3 fog_tint = get_sky_color(normalize(position - cam_pos));
+    out_color.rgb = mix(out_color.rgb, fog_tint, get_fog(position));
 }
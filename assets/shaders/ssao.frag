@@ -0,0 +1,84 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out float out_ao;
+
+uniform sampler2D normal_texture;
+uniform sampler2D position_texture;
+
+uniform mat4 proj;
+uniform mat4 view;
+
+uniform float ssao_radius;
+uniform float ssao_strength;
+
+const int N_SAMPLES = 16;
+const float DEPTH_BIAS = 0.025;
+
+/// Pseudo-random number in `[0, 1)`.
+float hash(vec2 seed) {
+    return fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+/// Offset in the unit hemisphere around `z`, samples are denser near the center.
+vec3 get_kernel_sample(int i) {
+    float angle = hash(vec2(i, 0.0)) * 6.2831853;
+    float z = hash(vec2(i, 1.0));
+    float r = sqrt(1.0 - z * z);
+
+    float scale = float(i + 1) / float(N_SAMPLES);
+    return vec3(r * cos(angle), r * sin(angle), z) * mix(0.1, 1.0, scale * scale);
+}
+
+/// Tangent space rotated randomly around the normal for each pixel.
+mat3 get_tangent_space(vec3 normal) {
+    vec3 helper = abs(normal.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(helper, normal));
+    vec3 bitangent = cross(normal, tangent);
+
+    float angle = hash(gl_FragCoord.xy) * 6.2831853;
+    tangent = cos(angle) * tangent + sin(angle) * bitangent;
+    bitangent = cross(normal, tangent);
+
+    return mat3(tangent, bitangent, normal);
+}
+
+float get_view_depth(vec3 position) {
+    return (view * vec4(position, 1.0)).z;
+}
+
+void main() {
+    vec3 normal = texture(normal_texture, v_frag_texcoord).xyz;
+    if (normal == vec3(0.0)) {
+        out_ao = 1.0;
+        return;
+    }
+
+    vec3 position = texture(position_texture, v_frag_texcoord).xyz;
+    float frag_depth = get_view_depth(position);
+    mat3 to_world = get_tangent_space(normal);
+
+    float occlusion = 0.0;
+
+    for (int i = 0; i < N_SAMPLES; ++i) {
+        vec3 sample_pos = position + to_world * get_kernel_sample(i) * ssao_radius;
+
+        vec4 clip = proj * view * vec4(sample_pos, 1.0);
+        vec2 coords = clip.xy / clip.w * 0.5 + 0.5;
+
+        if (any(lessThan(coords, vec2(0.0))) || any(greaterThan(coords, vec2(1.0))))
+            continue;
+
+        if (texture(normal_texture, coords).xyz == vec3(0.0))
+            continue;
+
+        float scene_depth = get_view_depth(texture(position_texture, coords).xyz);
+
+        /* Far occluders do not darken the fragment */
+        float range = smoothstep(0.0, 1.0, ssao_radius / abs(frag_depth - scene_depth));
+        occlusion += (scene_depth <= clip.w - DEPTH_BIAS ? 1.0 : 0.0) * range;
+    }
+
+    out_ao = clamp(1.0 - ssao_strength * occlusion / float(N_SAMPLES), 0.0, 1.0);
+}
@@ -0,0 +1,44 @@
+#version 440
+
+in vec2 v_frag_texcoord;
+
+out vec4 out_color;
+
+uniform sampler2D hdr_texture;
+uniform sampler2D bloom_texture;
+
+uniform bool is_bloom_enabled;
+uniform float bloom_intensity;
+uniform float exposure;
+
+/// See `graphics::postprocessing::Tonemapper`.
+uniform int tonemapper;
+
+vec3 reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+/// Fitted ACES filmic curve by Krzysztof Narkowicz.
+vec3 aces(vec3 color) {
+    return clamp(
+        (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14),
+        0.0, 1.0
+    );
+}
+
+void main() {
+    vec3 color = texture(hdr_texture, v_frag_texcoord).rgb;
+
+    if (is_bloom_enabled)
+        color += texture(bloom_texture, v_frag_texcoord).rgb * bloom_intensity;
+
+    color *= exposure;
+
+    switch (tonemapper) {
+        case 1: color = reinhard(color); break;
+        case 2: color = aces(color); break;
+        default: color = clamp(color, 0.0, 1.0); break;
+    }
+
+    out_color = vec4(color, 1.0);
+}
@@ -1,39 +1,63 @@
 pub mod utils;
 
 use crate::{
+    assets::{AssetManager, Handle},
+    console,
+    entity::render::EntityDrawBundle,
     graphics::{
-        self, Graphics,
+        Graphics,
         camera::Camera,
         debug_visuals::{self, DebugVisualizedStatic},
         light::DirectionalLight,
-        texture::Texture,
+        local_lights::LocalLights,
+        material::Materials,
+        pipeline,
+        postprocessing::PostProcessing,
+        render_graph::{ChainedUniforms, PassKind},
+        shadow::ShadowCascades,
+        texture::{Texture, TextureArray},
     },
+    inventory::Icons,
+    physics::player::{MoveMode, Player, PlayerInput},
     prelude::*,
+    script::ScriptEngine,
     terrain::chunk::{ChunkDrawBundle, chunk_array::ChunkArray},
 };
-use glium::{
-    Surface,
-    winit::{
-        error::EventLoopError,
-        event::{Event, StartCause, WindowEvent},
-        event_loop::ActiveEventLoop,
-    },
+use glium::winit::{
+    error::EventLoopError,
+    event::{Event, StartCause, WindowEvent},
+    event_loop::ActiveEventLoop,
 };
+use std::path::Path;
 
 /// Struct that handles everything.
 pub struct App {
     graphics: Graphics,
     camera: DebugVisualizedStatic<Camera>,
+    player: Player,
+    move_mode: MoveMode,
     lights: [DirectionalLight; 5],
-    render_shadows: bool,
+    shadow_cascades: ShadowCascades,
+    local_lights: LocalLights,
+    materials: Handle<Materials>,
+    post_processing: PostProcessing,
     draw_timer: Timer,
     update_timer: Timer,
 
     chunk_arr: DebugVisualizedStatic<ChunkArray>,
     chunk_draw_bundle: ChunkDrawBundle<'static>,
+    entity_draw_bundle: EntityDrawBundle<'static>,
+
+    /// Shaders, textures and configs reloaded on change.
+    assets: AssetManager,
 
-    texture_atlas: Texture,
-    normal_atlas: Texture,
+    /// Block textures, one layer per texture id.
+    block_textures: Handle<TextureArray>,
+    block_normals: Handle<TextureArray>,
+    /// Texture atlas registered in imgui for hotbar icons.
+    icons: Icons,
+
+    script_engine: ScriptEngine,
 
     imgui_window_builders: Vec<fn(&imgui::Ui)>,
 }
@@ -43,7 +67,8 @@ impl App {
     pub async fn new() -> Self {
         let _work_guard = logger::work("app", "initialize");
 
-        let graphics = Graphics::new().expect("failed to create graphics");
+        let mut assets = AssetManager::new();
+        let mut graphics = Graphics::new(&mut assets).expect("failed to create graphics");
 
         let camera = DebugVisualizedStatic::new_camera(
             Camera::new().with_position(0.0, 16.0, 2.0).with_rotation(
@@ -54,19 +79,26 @@ impl App {
             graphics.display.as_ref().get_ref(),
         );
 
-        let texture_atlas = Texture::from_path(
-            "assets/image/texture_atlas.png",
-            graphics.display.as_ref().get_ref(),
-        )
-        .expect("path should be valid and file is readable");
+        let asset_path = |name| Path::new(cfg::assets::DIRECTORY).join(name);
 
-        let normal_atlas = Texture::from_path(
-            "assets/image/normal_atlas.png",
-            graphics.display.as_ref().get_ref(),
-        )
-        .expect("path should be valid and file is readable");
+        let block_textures = assets.load(asset_path("image/texture_atlas.png"));
+        let block_normals = assets.load(asset_path("image/normal_atlas.png"));
+        let icon_atlas = assets.load::<Texture>(asset_path("image/texture_atlas.png"));
+        let materials = assets.load(asset_path("materials.cfg"));
+
+        let local_lights = LocalLights::new(graphics.display.as_ref().get_ref())
+            .expect("failed to create local lights textures");
+
+        let chunk_draw_bundle = ChunkDrawBundle::new(&mut assets);
+        let entity_draw_bundle = EntityDrawBundle::new(&mut assets);
+
+        assets
+            .finish_loading(graphics.display.as_ref().get_ref())
+            .await
+            .expect("failed to load assets");
+
+        let icons = Icons::new(&mut graphics.imguir.0, icon_atlas);
 
-        let chunk_draw_bundle = ChunkDrawBundle::new(graphics.display.as_ref().get_ref());
         let chunk_arr = DebugVisualizedStatic::new_chunk_array(
             ChunkArray::new_empty(),
             graphics.display.as_ref().get_ref(),
@@ -82,14 +114,23 @@ impl App {
         Self {
             chunk_arr,
             chunk_draw_bundle,
+            entity_draw_bundle,
             graphics,
             camera,
+            player: Player::default(),
+            move_mode: MoveMode::Fly,
             lights: Default::default(),
-            render_shadows: false,
-            texture_atlas,
-            normal_atlas,
+            shadow_cascades: Default::default(),
+            local_lights,
+            materials,
+            post_processing: Default::default(),
+            assets,
+            block_textures,
+            block_normals,
+            icons,
             draw_timer: Timer::new(),
             update_timer: Timer::new(),
+            script_engine: ScriptEngine::new(),
             imgui_window_builders,
         }
     }
@@ -166,7 +207,7 @@ impl App {
             return;
         }
 
-        if keyboard::just_pressed(Key::KeyY) {
+        if keyboard::just_pressed(cfg::key_bindings::DROP_CHUNK_TASKS) {
             self.chunk_arr.drop_tasks();
         }
 
@@ -181,25 +222,47 @@ impl App {
             self.camera.grabbes_cursor = !self.camera.grabbes_cursor;
         }
 
+        if keyboard::just_pressed(cfg::key_bindings::SWITCH_MOVE_MODE) {
+            self.move_mode = match self.move_mode {
+                MoveMode::Fly => {
+                    self.player = Player::from_camera(&self.camera);
+                    MoveMode::Walk
+                }
+                MoveMode::Walk => MoveMode::Fly,
+            };
+
+            logger::log!(
+                Info,
+                from = "app",
+                "switched to {mode} mode",
+                mode = self.move_mode
+            );
+        }
+
         if keyboard::just_pressed(cfg::key_bindings::SWITCH_RENDER_SHADOWS) {
-            self.render_shadows = !self.render_shadows;
+            let graph = &mut self.graphics.render_graph;
+            let is_enabled = !graph.is_enabled(pipeline::SHADOW_PASSES[0]);
+
+            for &pass in pipeline::shadow_passes() {
+                graph.set_enabled(pass, is_enabled);
+            }
         }
 
         if keyboard::just_pressed(cfg::key_bindings::RELOAD_RESOURCES) {
-            self.chunk_draw_bundle = ChunkDrawBundle::new(self.graphics.display.as_ref().get_ref());
+            self.assets.reload_all();
+        }
 
-            self.graphics
-                .refresh_postprocessing_shaders()
-                .log_error("app", "failed to reload postprocessing shaders");
-
-            match Texture::from_path(
-                "src/image/normal_atlas.png",
-                self.graphics.display.as_ref().get_ref(),
-            ) {
-                Ok(normals) => self.normal_atlas = normals,
-                Err(err) => {
-                    logger::log!(Error, from = "app", "failed to reload normal atlas: {err}")
-                }
+        // Reload assets which files have changed.
+        self.assets.update(self.graphics.display.as_ref().get_ref());
+        self.icons.update(&mut self.graphics.imguir.0);
+
+        // Run commands and scripts typed into the console
+        self.script_engine.update(&self.camera);
+        for line in console::take_input() {
+            if console::is_command(&line) {
+                console::execute(&line);
+            } else {
+                self.script_engine.eval(&line);
             }
         }
 
@@ -240,15 +303,37 @@ impl App {
             // Chunk array control window
             self.chunk_arr.spawn_control_window(ui);
 
+            // Brush panel
+            self.chunk_arr.brush_tool.spawn_control_window(ui);
+
+            // Hotbar and inventory
+            self.chunk_arr.inventory.spawn_hotbar(ui, self.icons.id);
+            self.chunk_arr.inventory.spawn_control_window(ui);
+
+            // Schematic panel
+            let cursor = self.chunk_arr.cursor();
+            self.chunk_arr.region_tool.spawn_control_window(ui, cursor);
+
+            // Scripts panel
+            self.script_engine.spawn_control_window(ui);
+
             // Draw all windows by callbacks
             for builder in self.imgui_window_builders.iter() {
                 builder(ui)
             }
 
-            // Light control window
-            for light in self.lights.iter_mut().take(1) {
-                light.spawn_control_window(ui);
-            }
+            // Day/night cycle window
+            self.chunk_arr.clock.spawn_control_window(ui);
+
+            // Render pass switches
+            self.graphics.render_graph.spawn_control_window(ui);
+
+            // Post-processing stages
+            self.post_processing
+                .spawn_control_window(ui, &mut self.graphics.render_graph);
+
+            // Point and spot lights
+            self.local_lights.spawn_control_window(ui);
 
             // Render UI
             self.graphics
@@ -262,53 +347,122 @@ impl App {
             self.graphics.display.get_framebuffer_dimensions(),
         ));
 
-        graphics::draw! {
-            render_shadows: self.render_shadows,
-            self.graphics,
-            self.graphics.display.draw(),
+        let clock = self.chunk_arr.clock;
+        let day_colors = clock.colors();
+        let sun_color = Color::all(clock.sun_intensity());
+        let moon_color = Color::new(0.6, 0.7, 1.0) * clock.moon_intensity();
+
+        let [view_ray_front, view_ray_right, view_ray_up] = self.camera.view_ray_basis();
+
+        let block_textures = self.block_textures.get();
+        let block_normals = self.block_normals.get();
+        let materials = self.materials.get();
+
+        let uniforms = glium::uniform! {
+            render_shadows: pipeline::shadow_passes()
+                .iter()
+                .all(|&pass| self.graphics.render_graph.is_enabled(pass)),
+            render_local_lights: self
+                .graphics
+                .render_graph
+                .is_enabled(pipeline::LOCAL_LIGHTS_PASS),
+            screen_resolution: resolution.as_array(),
+
+            block_textures: block_textures.get_sampler(),
+            block_normals:  block_normals.get_sampler(),
+
+            light_dir0:  self.lights[0].cam.front.as_array(),
+            light_pose0: self.lights[0].cam.pos.as_array(),
+
+            light_dir1:  self.lights[1].cam.front.as_array(),
+            light_pose1: self.lights[1].cam.pos.as_array(),
+
+            sun_color:     sun_color.as_array(),
+            moon_color:    moon_color.as_array(),
+            sky_color:     day_colors.sky.as_array(),
+            fog_color:     day_colors.fog.as_array(),
+            ambient_color: day_colors.ambient.as_array(),
+
+            time: self.draw_timer.time,
+            cam_pos: self.camera.pos.as_array(),
+            view_ray_front: view_ray_front.as_array(),
+            view_ray_right: view_ray_right.as_array(),
+            view_ray_up:    view_ray_up.as_array(),
+            proj: self.camera.get_proj(),
+            view: self.camera.get_view(),
+        };
 
-            let uniforms = {
-                screen_resolution: resolution.as_array(),
+        let uniforms = ChainedUniforms(
+            ChainedUniforms(
+                ChainedUniforms(uniforms, self.shadow_cascades.uniforms()),
+                self.post_processing.uniforms(),
+            ),
+            ChainedUniforms(self.local_lights.uniforms(), materials.uniforms()),
+        );
 
-                texture_atlas: self.texture_atlas.get_sampler(),
-                normal_atlas:  self.normal_atlas.get_sampler(),
+        let display = self.graphics.display.as_ref().get_ref();
 
-                light_proj0: self.lights[0].cam.get_ortho(64.0, 64.0),
-                light_view0: self.lights[0].cam.get_view(),
-                light_dir0:  self.lights[0].cam.front.as_array(),
-                light_pose0: self.lights[0].cam.pos.as_array(),
+        // Per-frame updates shared by all passes.
+        self.chunk_arr
+            .update_meshes(display, &mut self.camera)
+            .await;
+        self.chunk_arr.update_chunk_debug(display).await;
+        self.entity_draw_bundle
+            .update(&self.chunk_arr.entities, display)
+            .log_error("app", "failed to update entities mesh");
 
-                light_proj1: self.lights[1].cam.get_ortho(512.0, 512.0),
-                light_view1: self.lights[1].cam.get_view(),
-                light_dir1:  self.lights[1].cam.front.as_array(),
-                light_pose1: self.lights[1].cam.pos.as_array(),
+        let mut target = self.graphics.display.draw();
 
-                time: self.draw_timer.time,
-                cam_pos: self.camera.pos.as_array(),
-                proj: self.camera.get_proj(),
-                view: self.camera.get_view(),
-            },
+        self.graphics
+            .render_graph
+            .execute(&mut target, &uniforms, |pass, frame_buffer, uniforms| {
+                self.chunk_arr
+                    .render(
+                        frame_buffer,
+                        &self.chunk_draw_bundle,
+                        uniforms,
+                        pass,
+                        &self.shadow_cascades,
+                    )
+                    .log_error("app", "failed to render chunk array");
 
-            |&mut frame_buffer| {
-                let display = self.graphics.display.as_ref().get_ref();
+                self.entity_draw_bundle
+                    .render(frame_buffer, uniforms)
+                    .log_error("app", "failed to render entities");
 
-                self.chunk_arr.render(frame_buffer, &self.chunk_draw_bundle, &uniforms, display, &mut self.camera)
-                    .await
-                    .log_error("app", "failed to render chunk array");
+                // Shadow passes draw shadow casters only.
+                if pass != PassKind::Geometry {
+                    return;
+                }
 
-                self.chunk_arr.render_chunk_debug(display, frame_buffer, &uniforms)
-                    .await
+                self.chunk_arr
+                    .render_chunk_debug(display, frame_buffer, uniforms)
                     .log_error("app", "failed to render chunk array debug visuals");
 
-                self.camera.render_camera_debug_visuals(display, frame_buffer, &uniforms)
+                self.chunk_arr
+                    .render_brush_preview(display, frame_buffer, uniforms)
+                    .log_error("app", "failed to render brush preview");
+
+                self.chunk_arr
+                    .render_selection(display, frame_buffer, uniforms)
+                    .log_error("app", "failed to render region selection");
+
+                self.chunk_arr
+                    .render_cursor(display, frame_buffer, uniforms)
+                    .log_error("app", "failed to render voxel cursor");
+
+                self.camera
+                    .render_camera_debug_visuals(display, frame_buffer, uniforms)
                     .log_error("app", "failed to render camera");
-            },
+            })
+            .log_error("app", "failed to execute render graph");
 
-            |mut target| {
-                self.graphics.imguir.render(&mut target, draw_data)
-                    .log_error("app", "failed to render imgui");
-            },
-        };
+        self.graphics
+            .imguir
+            .render(&mut target, draw_data)
+            .log_error("app", "failed to render imgui");
+
+        target.finish().expect("failed to finish target");
 
         self.draw_timer.update();
         self.graphics
@@ -317,15 +471,59 @@ impl App {
             .update_delta_time(self.draw_timer.duration());
     }
 
+    /// Moves player with physics and puts camera into its eyes.
+    fn update_player(&mut self) {
+        // Camera was moved by a command, e.g. teleported.
+        if self.camera.pos != self.player.eye_pos() {
+            self.player = Player::from_camera(&self.camera);
+        }
+
+        self.camera.update_rotation();
+
+        // Wait for terrain to be generated so that player does not fall through it.
+        let feet_pos = Int3::from(vec3::from(self.player.pos.as_array().map(f32::round)));
+        if self.chunk_arr.is_voxel_ready(feet_pos) {
+            let input = PlayerInput::from_keyboard(&self.camera);
+            self.player.update(self.update_timer.dt, input, |pos| {
+                self.chunk_arr.is_voxel_solid(pos)
+            });
+        }
+
+        let eye_pos = self.player.eye_pos();
+        self.camera.set_position(eye_pos.x, eye_pos.y, eye_pos.z);
+    }
+
     /// Updates things.
     async fn new_events(&mut self, _start_cause: StartCause) {
         self.update_timer.update();
 
         // Rotating camera.
-        self.camera.update(self.update_timer.dt);
+        self.camera.process_commands();
+        match self.move_mode {
+            MoveMode::Fly => self.camera.update(self.update_timer.dt),
+            MoveMode::Walk => self.update_player(),
+        }
+
+        // Sun and moon follow the world clock.
+        self.chunk_arr.clock.update(self.update_timer.dt);
+        self.lights[0].set_direction(self.chunk_arr.clock.sun_dir());
+        self.lights[1].set_direction(self.chunk_arr.clock.moon_dir());
         for light in self.lights.iter_mut() {
             light.update(self.camera.pos);
         }
+        self.shadow_cascades
+            .update(&self.camera, self.lights[0].cam.front);
+
+        self.chunk_arr.tick_block_entities(self.update_timer.dt);
+        self.chunk_arr.tick_entities(self.update_timer.dt);
+        self.chunk_arr
+            .tick_world(self.update_timer.dt, self.camera.pos);
+        self.chunk_arr.collect_items(self.camera.pos);
+
+        // Lights of emissive voxels near the camera.
+        self.local_lights
+            .update(&self.camera, self.chunk_arr.emitter_lights(self.camera.pos));
+
         // Debug visuals switcher.
         if keyboard::just_pressed(cfg::key_bindings::DEBUG_VISUALS_SWITCH) {
             debug_visuals::switch_enable();
@@ -0,0 +1,715 @@
+//!
+//! Asset manager handing out typed [handles][Handle] to shaders, textures and configs.
+//!
+//! Asset files are read on [runtime][RUNTIME] workers and GL objects are created
+//! on the main thread in [`AssetManager::update`]. Files in [`DIRECTORY`] are watched,
+//! assets made of changed files are reloaded in place, so every handle sees the new
+//! version. If reloading fails the last good version is kept.
+//!
+
+use {
+    crate::{
+        graphics::{
+            material::{self, Materials, MaterialsParseError},
+            shader::{Shader, ShaderError, ShaderSources},
+            texture::{Texture, TextureArray},
+        },
+        prelude::*,
+        terrain::voxel::voxel_data::{Id, Material},
+    },
+    cfg::assets::DIRECTORY,
+    glium::backend::Facade,
+    image::RgbaImage,
+    notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
+    std::{
+        any::{Any, type_name},
+        fmt, fs, io,
+        path::{Path, PathBuf},
+        sync::mpsc,
+    },
+    tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+};
+
+/// Asset loaded in two stages: files are read on a worker thread,
+/// then the asset is created from them on the main thread in context `C`.
+pub trait Asset<C: ?Sized = dyn Facade>: Sized + 'static {
+    /// Identifies the asset, e.g. its path.
+    type Key: Clone + fmt::Debug + Send + 'static;
+
+    /// Data read from asset files.
+    type Source: Send + 'static;
+
+    /// Reads asset files, runs on a worker thread.
+    fn read(key: &Self::Key) -> Result<Loaded<Self::Source>, AssetError>;
+
+    /// Creates the asset, runs on the main thread.
+    fn create(key: &Self::Key, source: Self::Source, context: &C) -> Result<Self, AssetError>;
+}
+
+/// Asset source with files it is read from, the asset is reloaded if they change.
+#[derive(Debug)]
+pub struct Loaded<S> {
+    pub source: S,
+    pub files: Vec<PathBuf>,
+}
+
+impl Asset for Shader {
+    /// Vertex and fragment shader names.
+    type Key = (&'static str, &'static str);
+    type Source = ShaderSources;
+
+    fn read(
+        &(vertex_name, fragment_name): &Self::Key,
+    ) -> Result<Loaded<ShaderSources>, AssetError> {
+        let sources = Shader::preprocess(vertex_name, fragment_name)?;
+
+        Ok(Loaded {
+            files: sources.files().collect(),
+            source: sources,
+        })
+    }
+
+    fn create(
+        _: &Self::Key,
+        sources: ShaderSources,
+        facade: &dyn Facade,
+    ) -> Result<Self, AssetError> {
+        Ok(sources.compile(facade)?)
+    }
+}
+
+impl Asset for Texture {
+    /// Path to image.
+    type Key = PathBuf;
+    type Source = RgbaImage;
+
+    fn read(path: &PathBuf) -> Result<Loaded<RgbaImage>, AssetError> {
+        Ok(Loaded {
+            source: Texture::read(path)?,
+            files: vec![path.clone()],
+        })
+    }
+
+    fn create(path: &PathBuf, image: RgbaImage, facade: &dyn Facade) -> Result<Self, AssetError> {
+        Ok(Texture::from_image(path, image, facade))
+    }
+}
+
+impl Asset for TextureArray {
+    /// Path to texture atlas.
+    type Key = PathBuf;
+    type Source = Vec<RgbaImage>;
+
+    fn read(path: &PathBuf) -> Result<Loaded<Vec<RgbaImage>>, AssetError> {
+        Ok(Loaded {
+            source: TextureArray::read_atlas(path)?,
+            files: vec![path.clone()],
+        })
+    }
+
+    fn create(
+        path: &PathBuf,
+        layers: Vec<RgbaImage>,
+        facade: &dyn Facade,
+    ) -> Result<Self, AssetError> {
+        Ok(TextureArray::from_layers(path, layers, facade))
+    }
+}
+
+impl Asset for Materials {
+    /// Path to material overrides, see [`material`].
+    type Key = PathBuf;
+    type Source = Vec<(Id, Material)>;
+
+    fn read(path: &PathBuf) -> Result<Loaded<Self::Source>, AssetError> {
+        let src = fs::read_to_string(path)?;
+
+        Ok(Loaded {
+            source: material::parse_overrides(&src, voxels::VOXEL_DATA)?,
+            files: vec![path.clone()],
+        })
+    }
+
+    fn create(_: &PathBuf, overrides: Self::Source, _: &dyn Facade) -> Result<Self, AssetError> {
+        let mut materials = Materials::default();
+
+        for (id, material) in overrides {
+            materials.set(id, material);
+        }
+
+        Ok(materials)
+    }
+}
+
+/// Shared handle to an asset, gives its latest loaded version.
+/// Versions given before a reload stay alive while they are used.
+pub struct Handle<T: Asset<C>, C: ?Sized + 'static = dyn Facade> {
+    slot: Rc<Slot<T, C>>,
+}
+
+impl<T: Asset<C>, C: ?Sized + 'static> Handle<T, C> {
+    /// Gives the asset.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the asset is not loaded yet, see [`AssetManager::finish_loading`].
+    pub fn get(&self) -> Rc<T> {
+        self.try_get()
+            .unwrap_or_else(|| panic!("asset {:?} is not loaded yet", self.slot.key))
+    }
+
+    /// Gives the asset if it is loaded.
+    pub fn try_get(&self) -> Option<Rc<T>> {
+        self.slot.value.borrow().clone()
+    }
+}
+
+impl<T: Asset<C>, C: ?Sized + 'static> Clone for Handle<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: Rc::clone(&self.slot),
+        }
+    }
+}
+
+impl<T: Asset<C>, C: ?Sized + 'static> fmt::Debug for Handle<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("key", &self.slot.key)
+            .field("is_loaded", &self.slot.value.borrow().is_some())
+            .finish()
+    }
+}
+
+struct Slot<T: Asset<C>, C: ?Sized> {
+    key: T::Key,
+    value: RefCell<Option<Rc<T>>>,
+}
+
+type Source = Box<dyn Any + Send>;
+type ReadTask = Box<dyn FnOnce() -> Result<Loaded<Source>, AssetError> + Send>;
+
+/// [`Slot`] with its asset type erased.
+trait AnySlot<C: ?Sized> {
+    fn read_task(&self) -> ReadTask;
+    fn create(&self, source: Source, context: &C) -> Result<(), AssetError>;
+    fn is_loaded(&self) -> bool;
+    fn name(&self) -> String;
+}
+
+impl<T: Asset<C>, C: ?Sized> AnySlot<C> for Slot<T, C> {
+    fn read_task(&self) -> ReadTask {
+        let key = self.key.clone();
+
+        Box::new(move || {
+            let Loaded { source, files } = T::read(&key)?;
+
+            Ok(Loaded {
+                source: Box::new(source),
+                files,
+            })
+        })
+    }
+
+    fn create(&self, source: Source, context: &C) -> Result<(), AssetError> {
+        let source = source
+            .downcast::<T::Source>()
+            .expect("source should be read by the same asset type");
+
+        let value = T::create(&self.key, *source, context)?;
+        self.value.replace(Some(Rc::new(value)));
+
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.value.borrow().is_some()
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self.key)
+    }
+}
+
+struct Entry<C: ?Sized> {
+    slot: Rc<dyn AnySlot<C>>,
+    /// The same slot to downcast it back to its type.
+    any_slot: Rc<dyn Any>,
+    /// Canonical paths of files the asset is read from.
+    files: Vec<PathBuf>,
+    is_reading: bool,
+    /// Files changed while the asset was being read.
+    is_outdated: bool,
+}
+
+struct ReadResult {
+    idx: usize,
+    result: Result<Loaded<Source>, AssetError>,
+}
+
+/// Loads [assets][Asset] and reloads them when their files change.
+/// Assets are created in context `C`, bookkeeping does not depend on it.
+pub struct AssetManager<C: ?Sized + 'static = dyn Facade> {
+    entries: Vec<Entry<C>>,
+    /// Entry indices by asset type and key.
+    indices: HashMap<String, usize>,
+
+    sender: UnboundedSender<ReadResult>,
+    receiver: UnboundedReceiver<ReadResult>,
+
+    file_events: mpsc::Receiver<notify::Result<notify::Event>>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl<C: ?Sized + 'static> AssetManager<C> {
+    /// Constructs asset manager watching [`DIRECTORY`].
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        let (event_sender, file_events) = mpsc::channel();
+
+        let watcher = notify::recommended_watcher(event_sender)
+            .and_then(|mut watcher| {
+                watcher.watch(Path::new(DIRECTORY), RecursiveMode::Recursive)?;
+                Ok(Some(watcher))
+            })
+            .log_error(
+                "asset manager",
+                "failed to watch assets, they won't be reloaded",
+            );
+
+        Self {
+            entries: vec![],
+            indices: HashMap::new(),
+            sender,
+            receiver,
+            file_events,
+            _watcher: watcher,
+        }
+    }
+
+    /// Starts loading asset by `key`. Assets with the same key share the handle.
+    pub fn load<T: Asset<C>>(&mut self, key: T::Key) -> Handle<T, C> {
+        let id = format!("{}: {key:?}", type_name::<T>());
+
+        if let Some(&idx) = self.indices.get(&id) {
+            let slot = Rc::clone(&self.entries[idx].any_slot)
+                .downcast::<Slot<T, C>>()
+                .expect("assets with the same id should be of the same type");
+
+            return Handle { slot };
+        }
+
+        let slot = Rc::new(Slot::<T, C> {
+            key,
+            value: RefCell::new(None),
+        });
+
+        let idx = self.entries.len();
+        self.entries.push(Entry {
+            slot: slot.clone(),
+            any_slot: slot.clone(),
+            files: vec![],
+            is_reading: false,
+            is_outdated: false,
+        });
+        self.indices.insert(id, idx);
+        self.start_reading(idx);
+
+        Handle { slot }
+    }
+
+    /// Waits for all assets to be loaded.
+    ///
+    /// # Error
+    ///
+    /// Returns error if an asset without a loaded version fails to load.
+    pub async fn finish_loading(&mut self, context: &C) -> Result<(), AssetError> {
+        while self.entries.iter().any(|entry| entry.is_reading) {
+            let Some(result) = self.receiver.recv().await else {
+                break;
+            };
+
+            let idx = result.idx;
+            if let Err(err) = self.finish_reading(result, context)
+                && !self.entries[idx].slot.is_loaded()
+            {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts reloading assets which files have changed and finishes loaded ones.
+    /// Gives paths of changed files in [`DIRECTORY`].
+    pub fn update(&mut self, context: &C) -> HashSet<PathBuf> {
+        let changed_files = self
+            .file_events
+            .try_iter()
+            .filter_map(|event| {
+                event
+                    .map(Some)
+                    .log_error("asset manager", "failed to watch assets")
+            })
+            .filter(|event| matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)))
+            .flat_map(|event| event.paths)
+            .map(|path| canonicalize(&path))
+            .collect::<HashSet<_>>();
+
+        self.reload_changed(&changed_files);
+
+        while let Ok(result) = self.receiver.try_recv() {
+            // Errors are logged, the last good version is kept.
+            _ = self.finish_reading(result, context);
+        }
+
+        changed_files
+    }
+
+    /// Starts reloading assets read from any of canonical `changed_files`.
+    fn reload_changed(&mut self, changed_files: &HashSet<PathBuf>) {
+        if changed_files.is_empty() {
+            return;
+        }
+
+        for idx in 0..self.entries.len() {
+            if self.entries[idx]
+                .files
+                .iter()
+                .any(|file| changed_files.contains(file))
+            {
+                self.start_reading(idx);
+            }
+        }
+    }
+
+    /// Reloads all assets.
+    pub fn reload_all(&mut self) {
+        for idx in 0..self.entries.len() {
+            self.start_reading(idx);
+        }
+    }
+
+    fn start_reading(&mut self, idx: usize) {
+        let entry = &mut self.entries[idx];
+
+        if entry.is_reading {
+            entry.is_outdated = true;
+            return;
+        }
+
+        entry.is_reading = true;
+
+        let task = entry.slot.read_task();
+        let sender = self.sender.clone();
+
+        RUNTIME.spawn_blocking(move || {
+            let result = task().map(|Loaded { source, files }| Loaded {
+                source,
+                files: files.iter().map(|file| canonicalize(file)).collect(),
+            });
+
+            // The manager may be dropped before reading finishes.
+            _ = sender.send(ReadResult { idx, result });
+        });
+    }
+
+    fn finish_reading(
+        &mut self,
+        ReadResult { idx, result }: ReadResult,
+        context: &C,
+    ) -> Result<(), AssetError> {
+        let entry = &mut self.entries[idx];
+        entry.is_reading = false;
+
+        let was_loaded = entry.slot.is_loaded();
+
+        // Files of a failed read are unknown, so the previous ones stay watched.
+        let result = result.and_then(|Loaded { source, files }| {
+            entry.files = files;
+            entry.slot.create(source, context)
+        });
+
+        match &result {
+            Ok(()) if was_loaded => {
+                logger::log!(
+                    Info,
+                    from = "asset manager",
+                    "reloaded {}",
+                    entry.slot.name()
+                )
+            }
+            Ok(()) => {}
+            Err(err) if was_loaded => logger::log!(
+                Error,
+                from = "asset manager",
+                "failed to reload {}, keeping the last version: {err}",
+                entry.slot.name(),
+            ),
+            Err(err) => logger::log!(
+                Error,
+                from = "asset manager",
+                "failed to load {}: {err}",
+                entry.slot.name(),
+            ),
+        }
+
+        if mem::take(&mut entry.is_outdated) {
+            self.start_reading(idx);
+        }
+
+        result
+    }
+}
+
+impl<C: ?Sized + 'static> Default for AssetManager<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ?Sized + 'static> fmt::Debug for AssetManager<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetManager")
+            .field("assets", &self.indices.keys().collect_vec())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Makes paths of watched files comparable with paths of asset files.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+#[derive(Debug, Error)]
+pub enum AssetError {
+    #[error(transparent)]
+    Shader(#[from] ShaderError),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("failed to parse materials: {0}")]
+    Materials(#[from] MaterialsParseError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    /// Reads of [`Text`] assets by path.
+    static READS: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
+
+    /// GL-free asset created in `()`, empty text fails to create.
+    struct Text(String);
+
+    impl Asset<()> for Text {
+        type Key = PathBuf;
+        type Source = String;
+
+        fn read(path: &PathBuf) -> Result<Loaded<String>, AssetError> {
+            READS.lock().unwrap().push(path.clone());
+
+            Ok(Loaded {
+                source: fs::read_to_string(path)?,
+                files: vec![path.clone()],
+            })
+        }
+
+        fn create(_: &PathBuf, source: String, _: &()) -> Result<Self, AssetError> {
+            if source.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "empty text").into());
+            }
+
+            Ok(Self(source))
+        }
+    }
+
+    /// Same keys as [`Text`] but a different asset type.
+    struct Length(usize);
+
+    impl Asset<()> for Length {
+        type Key = PathBuf;
+        type Source = usize;
+
+        fn read(path: &PathBuf) -> Result<Loaded<usize>, AssetError> {
+            Ok(Loaded {
+                source: fs::read_to_string(path)?.len(),
+                files: vec![path.clone()],
+            })
+        }
+
+        fn create(_: &PathBuf, source: usize, _: &()) -> Result<Self, AssetError> {
+            Ok(Self(source))
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("terramine_test_{}_{name}", std::process::id()))
+    }
+
+    fn finish_loading(assets: &mut AssetManager<()>) -> Result<(), AssetError> {
+        RUNTIME.block_on(assets.finish_loading(&()))
+    }
+
+    fn reload(assets: &mut AssetManager<()>, path: &Path) {
+        assets.reload_changed(&HashSet::from([canonicalize(path)]));
+    }
+
+    fn read_count(path: &Path) -> usize {
+        READS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|read| *read == path)
+            .count()
+    }
+
+    #[test]
+    fn load_shares_handles_by_key() {
+        let first = temp_path("shared_first.txt");
+        let second = temp_path("shared_second.txt");
+        fs::write(&first, "first").unwrap();
+        fs::write(&second, "second").unwrap();
+
+        let mut assets = AssetManager::<()>::new();
+        let text = assets.load::<Text>(first.clone());
+        let same_text = assets.load::<Text>(first.clone());
+        let other_text = assets.load::<Text>(second.clone());
+        let length = assets.load::<Length>(first.clone());
+        finish_loading(&mut assets).unwrap();
+
+        assert_eq!(assets.entries.len(), 3);
+        assert!(Rc::ptr_eq(&text.slot, &same_text.slot));
+        assert_eq!(text.get().0, "first");
+        assert_eq!(other_text.get().0, "second");
+        assert_eq!(length.get().0, 5);
+
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+    }
+
+    #[test]
+    fn changed_files_reload_their_assets() {
+        let changed = temp_path("changed.txt");
+        let unchanged = temp_path("unchanged.txt");
+        fs::write(&changed, "old").unwrap();
+        fs::write(&unchanged, "same").unwrap();
+
+        let mut assets = AssetManager::<()>::new();
+        let changed_text = assets.load::<Text>(changed.clone());
+        let unchanged_text = assets.load::<Text>(unchanged.clone());
+        finish_loading(&mut assets).unwrap();
+
+        fs::write(&changed, "new").unwrap();
+        reload(&mut assets, &changed);
+
+        assert!(assets.entries[0].is_reading);
+        assert!(!assets.entries[1].is_reading);
+
+        finish_loading(&mut assets).unwrap();
+        assert_eq!(changed_text.get().0, "new");
+        assert_eq!(unchanged_text.get().0, "same");
+
+        fs::remove_file(&changed).unwrap();
+        fs::remove_file(&unchanged).unwrap();
+    }
+
+    #[test]
+    fn failed_reload_keeps_last_version() {
+        let path = temp_path("last_version.txt");
+        fs::write(&path, "good").unwrap();
+
+        let mut assets = AssetManager::<()>::new();
+        let text = assets.load::<Text>(path.clone());
+        finish_loading(&mut assets).unwrap();
+        let files = assets.entries[0].files.clone();
+
+        // Creation fails.
+        fs::write(&path, "").unwrap();
+        reload(&mut assets, &path);
+        finish_loading(&mut assets).unwrap();
+        assert_eq!(text.get().0, "good");
+
+        // Reading fails, the asset is still reloaded when the file is back.
+        fs::remove_file(&path).unwrap();
+        assets.reload_all();
+        finish_loading(&mut assets).unwrap();
+        assert_eq!(text.get().0, "good");
+        assert_eq!(assets.entries[0].files, files);
+
+        fs::write(&path, "better").unwrap();
+        reload(&mut assets, &path);
+        finish_loading(&mut assets).unwrap();
+        assert_eq!(text.get().0, "better");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_first_load_is_an_error() {
+        let path = temp_path("missing.txt");
+
+        let mut assets = AssetManager::<()>::new();
+        let text = assets.load::<Text>(path);
+
+        assert!(matches!(
+            finish_loading(&mut assets),
+            Err(AssetError::Io(_))
+        ));
+        assert!(text.try_get().is_none());
+    }
+
+    #[test]
+    fn reloads_during_reading_are_coalesced() {
+        let path = temp_path("coalesced.txt");
+        fs::write(&path, "first").unwrap();
+
+        let mut assets = AssetManager::<()>::new();
+        let text = assets.load::<Text>(path.clone());
+        finish_loading(&mut assets).unwrap();
+
+        // Results are handled only by the manager, so the first reload is still pending.
+        fs::write(&path, "second").unwrap();
+        reload(&mut assets, &path);
+        reload(&mut assets, &path);
+        reload(&mut assets, &path);
+        assert!(assets.entries[0].is_outdated);
+
+        finish_loading(&mut assets).unwrap();
+
+        assert_eq!(read_count(&path), 3);
+        assert!(!assets.entries[0].is_outdated);
+        assert_eq!(text.get().0, "second");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn materials_are_read_with_their_file() {
+        let path = temp_path("materials.cfg");
+        fs::write(&path, "Stone roughness=0.25\n").unwrap();
+
+        let loaded = Materials::read(&path).unwrap();
+        let stone = voxels::VOXEL_DATA
+            .iter()
+            .find(|voxel| voxel.name == "Stone")
+            .unwrap();
+
+        assert_eq!(loaded.files, vec![path.clone()]);
+        assert_eq!(loaded.source.len(), 1);
+        assert_eq!(loaded.source[0].0, stone.id);
+        assert_eq!(loaded.source[0].1.roughness, 0.25);
+
+        fs::write(&path, "Stone roughness=\n").unwrap();
+        assert!(matches!(
+            Materials::read(&path),
+            Err(AssetError::Materials(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Materials::read(&path), Err(AssetError::Io(_))));
+    }
+}
@@ -6,16 +6,15 @@ pub mod save {
     pub const META_FILE_NAME: &str = "meta.off";
     pub const STACK_FILE_EXTENSION: &str = "stk";
     pub const HEAP_FILE_EXTENSION: &str = "hp";
+    pub const WORLD_NAME: &str = "world";
 }
 
 pub mod camera {
     pub const FRUSTUM_EDGE_LINE_LENGTH: f32 = 10_000.0;
     pub const VERTICAL_LOOK_EPS: f64 = 0.001;
-    pub const LIGHT_NEAR_PLANE: f32 = 1.0;
-    pub const LIGHT_FAR_PLANE: f32 = 200.0;
 
     pub mod default {
-        /// These constants are shared with shader file. See `postprocessing.frag`.
+        /// These constants are injected into shaders as `Z_NEAR` and `Z_FAR`. See `graphics::shader`.
         pub const NEAR_PLANE: f32 = 0.5;
         pub const FAR_PLANE: f32 = 10_000.0;
 
@@ -72,42 +71,207 @@ pub mod terrain {
     pub const MAX_TASKS: usize = 10_000;
     pub const MAX_CHUNKS: usize = 100_000;
 
+    /// Maximal number of previous voxel ids and block entities kept in edit history.
+    pub const MAX_HISTORY_VOXELS: usize = 16 * 1024 * 1024;
+
     pub mod voxel_types {
         use {
-            crate::app::utils::terrain::voxel::voxel_data::{TextureSides, VoxelData},
+            crate::app::utils::terrain::voxel::{
+                block_entity::BlockEntityKind,
+                fluid::FluidKind,
+                voxel_data::{Material, TextureSides, VoxelData},
+                world_tick::Behavior,
+            },
             math_linear::prelude::Color,
         };
 
-        pub const VOXEL_DATA: [VoxelData; 5] = [
+        pub const VOXEL_DATA: [VoxelData; 17] = [
             VoxelData {
                 name: "Air",
                 id: 0,
                 avarage_color: Color::new(0.00, 0.00, 0.00),
                 textures: TextureSides::all(0),
+                material: Material::DEFAULT,
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: None,
             },
             VoxelData {
                 name: "Log",
                 id: 1,
                 avarage_color: Color::new(0.62, 0.52, 0.30),
                 textures: TextureSides::vertical(3, 1, 1),
+                material: Material::rough(0.8),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: None,
             },
             VoxelData {
                 name: "Stone",
                 id: 2,
                 avarage_color: Color::new(0.45, 0.45, 0.45),
                 textures: TextureSides::all(2),
+                material: Material::rough(0.7).parallax(0.03),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: None,
             },
             VoxelData {
                 name: "Grass",
                 id: 3,
                 avarage_color: Color::new(0.40, 0.64, 0.24),
                 textures: TextureSides::vertical(4, 6, 5),
+                material: Material::DEFAULT,
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Spreading),
             },
             VoxelData {
                 name: "Dirt",
                 id: 4,
                 avarage_color: Color::new(0.59, 0.42, 0.29),
                 textures: TextureSides::all(5),
+                material: Material::rough(1.0),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Chest",
+                id: 5,
+                avarage_color: Color::new(0.58, 0.41, 0.24),
+                textures: TextureSides::vertical(7, 8, 8),
+                material: Material::rough(0.6),
+                block_entity: Some(BlockEntityKind::Container),
+                emission: 0,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Sign",
+                id: 6,
+                avarage_color: Color::new(0.74, 0.62, 0.42),
+                textures: TextureSides::all(9),
+                material: Material::rough(0.7),
+                block_entity: Some(BlockEntityKind::Sign),
+                emission: 0,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Spawner",
+                id: 7,
+                avarage_color: Color::new(0.11, 0.12, 0.16),
+                textures: TextureSides::all(10),
+                material: Material::rough(0.35).metallic(1.0),
+                block_entity: Some(BlockEntityKind::Spawner),
+                emission: 0,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Torch",
+                id: 8,
+                avarage_color: Color::new(0.85, 0.62, 0.25),
+                textures: TextureSides::all(11),
+                material: Material::DEFAULT.emissive(3.0),
+                block_entity: None,
+                emission: 14,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Lava",
+                id: 9,
+                avarage_color: Color::new(0.90, 0.38, 0.08),
+                textures: TextureSides::all(12),
+                material: Material::rough(0.6).emissive(4.0),
+                block_entity: Some(BlockEntityKind::Fluid),
+                emission: 15,
+                fluid: Some(FluidKind::Lava),
+                behavior: None,
+            },
+            VoxelData {
+                name: "Water",
+                id: 10,
+                avarage_color: Color::new(0.12, 0.35, 0.78),
+                textures: TextureSides::all(13),
+                material: Material::rough(0.05),
+                block_entity: Some(BlockEntityKind::Fluid),
+                emission: 0,
+                fluid: Some(FluidKind::Water),
+                behavior: None,
+            },
+            VoxelData {
+                name: "Obsidian",
+                id: 11,
+                avarage_color: Color::new(0.10, 0.07, 0.15),
+                textures: TextureSides::all(14),
+                material: Material::rough(0.15),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: None,
+            },
+            VoxelData {
+                name: "Sand",
+                id: 12,
+                avarage_color: Color::new(0.86, 0.80, 0.55),
+                textures: TextureSides::all(15),
+                material: Material::rough(1.0),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Falling),
+            },
+            VoxelData {
+                name: "Gravel",
+                id: 13,
+                avarage_color: Color::new(0.52, 0.49, 0.47),
+                textures: TextureSides::all(16),
+                material: Material::rough(1.0).parallax(0.05),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Falling),
+            },
+            VoxelData {
+                name: "Leaves",
+                id: 14,
+                avarage_color: Color::new(0.22, 0.50, 0.16),
+                textures: TextureSides::all(17),
+                material: Material::rough(0.6),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Decaying),
+            },
+            VoxelData {
+                name: "Crop",
+                id: 15,
+                avarage_color: Color::new(0.35, 0.62, 0.20),
+                textures: TextureSides::all(18),
+                material: Material::rough(0.7),
+                block_entity: Some(BlockEntityKind::Crop),
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Growing),
+            },
+            VoxelData {
+                name: "Wheat",
+                id: 16,
+                avarage_color: Color::new(0.85, 0.72, 0.30),
+                textures: TextureSides::all(19),
+                material: Material::rough(0.7),
+                block_entity: None,
+                emission: 0,
+                fluid: None,
+                behavior: Some(Behavior::Growing),
             },
         ];
     }
@@ -133,6 +297,14 @@ pub mod texture {
         pub const ITEMS_COUNT_IN_ROW: usize = 32;
         pub const BIAS: f32 = 0.0;
     }
+
+    /// Anisotropic filtering level of block textures.
+    pub const ANISOTROPY: u16 = 16;
+}
+
+pub mod assets {
+    /// Files in this directory are watched and reloaded on change.
+    pub const DIRECTORY: &str = "assets/";
 }
 
 pub mod shader {
@@ -142,7 +314,7 @@ pub mod shader {
     pub const CLEAR_DEPTH: f32 = 1.0;
     pub const CLEAR_STENCIL: i32 = 0;
 
-    /// That constant is shared with shader. See `postprocessing.frag`.
+    /// Only seen through unlit pixels, the sky is drawn by `postprocessing.frag`.
     pub const CLEAR_COLOR: (f32, f32, f32, f32) = (0.01, 0.01, 0.01, 1.0);
 
     pub mod voxel {
@@ -157,6 +329,53 @@ pub mod shader {
     }
 }
 
+pub mod shadow {
+    /// Shader arrays are this long, injected into shaders. See `graphics::shader`.
+    pub const MAX_CASCADES: usize = 4;
+
+    /// Number of shadow cascades, not more than [`MAX_CASCADES`].
+    pub const N_CASCADES: usize = 4;
+
+    /// Width and height of each cascade shadow map.
+    pub const RESOLUTION: u32 = 2048;
+
+    /// Shadows end that far from the camera.
+    pub const DISTANCE: f32 = 512.0;
+
+    /// Blend of logarithmic (`1.0`) and uniform (`0.0`) cascade splits.
+    pub const SPLIT_LAMBDA: f32 = 0.8;
+
+    /// Voxels that far towards the sun from a cascade still cast shadows into it.
+    pub const CASTER_DISTANCE: f32 = 256.0;
+}
+
+pub mod local_light {
+    /// Lights drawn at most, the nearest to the camera are taken.
+    pub const MAX_LIGHTS: usize = 512;
+
+    /// Lights lighting one screen tile at most.
+    pub const MAX_LIGHTS_PER_TILE: usize = 64;
+
+    /// Screen is split into that many tiles horizontally and vertically.
+    pub const TILES_X: usize = 32;
+    pub const TILES_Y: usize = 18;
+
+    /// Emissive voxels further from the camera do not spawn lights.
+    pub const EMITTER_DISTANCE: f32 = 96.0;
+
+    /// Intensity of light spawned by a voxel with the brightest emission.
+    pub const EMITTER_INTENSITY: f32 = 2.0;
+}
+
+pub mod script {
+    pub const DIRECTORY: &str = "scripts/";
+    pub const FILE_EXTENSION: &str = "rhai";
+
+    /// Limits runaway scripts, e.g. infinite loops. Scripts run on the frame,
+    /// so this keeps a stuck script to a hitch of a fraction of a second.
+    pub const MAX_OPERATIONS: u64 = 1_000_000;
+}
+
 pub mod key_bindings {
     use crate::app::utils::user_io::Key;
 
@@ -167,6 +386,63 @@ pub mod key_bindings {
     pub const ENABLE_PROFILER_WINDOW: Key = Key::KeyE;
     pub const SWITCH_RENDER_SHADOWS: Key = Key::KeyU;
     pub const RELOAD_RESOURCES: Key = Key::KeyR;
+    pub const UNDO: [Key; 2] = [Key::ControlLeft, Key::KeyZ];
+    pub const REDO: [Key; 2] = [Key::ControlLeft, Key::KeyY];
+    pub const SELECT_FIRST_CORNER: Key = Key::BracketLeft;
+    pub const SELECT_SECOND_CORNER: Key = Key::BracketRight;
+    pub const COPY: [Key; 2] = [Key::ControlLeft, Key::KeyC];
+    pub const PASTE: [Key; 2] = [Key::ControlLeft, Key::KeyV];
+    pub const SWITCH_MOVE_MODE: Key = Key::KeyF;
+    pub const THROW_PROJECTILE: Key = Key::KeyG;
+    pub const SAVE: [Key; 2] = [Key::ControlLeft, Key::KeyS];
+    pub const LOAD: [Key; 2] = [Key::ControlLeft, Key::KeyO];
+    pub const DROP_CHUNK_TASKS: Key = Key::KeyK;
+
+    /// Every binding above, checked not to collide.
+    pub const ALL: &[&[Key]] = &[
+        &[DEBUG_VISUALS_SWITCH],
+        &[APP_EXIT],
+        &[MOUSE_CAPTURE],
+        &[ENABLE_DRAG_AND_RESIZE_WINDOWS],
+        &[ENABLE_PROFILER_WINDOW],
+        &[SWITCH_RENDER_SHADOWS],
+        &[RELOAD_RESOURCES],
+        &UNDO,
+        &REDO,
+        &[SELECT_FIRST_CORNER],
+        &[SELECT_SECOND_CORNER],
+        &COPY,
+        &PASTE,
+        &[SWITCH_MOVE_MODE],
+        &[THROW_PROJECTILE],
+        &SAVE,
+        &LOAD,
+        &[DROP_CHUNK_TASKS],
+    ];
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn key_bindings_do_not_collide() {
+            for (i, first) in ALL.iter().enumerate() {
+                for second in &ALL[i + 1..] {
+                    assert_ne!(first, second, "two actions are bound to {first:?}");
+
+                    // A single key fires together with a combo ending with it.
+                    for (single, combo) in [(first, second), (second, first)] {
+                        assert!(
+                            !(single.len() == 1
+                                && combo.len() > 1
+                                && combo.last() == single.last()),
+                            "{single:?} is triggered by {combo:?}",
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub mod timer {
@@ -0,0 +1,913 @@
+//!
+//! In-game console with slash-commands. Lines starting with `/` are parsed into
+//! [console commands][ConsoleCommand], other lines are evaluated as scripts.
+//!
+
+use {
+    crate::{
+        entity::components::EntityKind,
+        graphics::camera::{self, CameraCommand},
+        inventory::GameMode,
+        prelude::*,
+        terrain::{
+            chunk::commands::{self, Command},
+            voxel::{
+                self, generator,
+                voxel_data::{Id, data::VOXEL_DATA},
+            },
+        },
+    },
+    std::sync::Mutex,
+};
+
+lazy_static! {
+    static ref CONSOLE: Mutex<Console> = Mutex::new(Console::new());
+}
+
+/// Prefix of console commands.
+pub const PREFIX: char = '/';
+
+/// Type of a command parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum ArgKind {
+    #[display("int")]
+    Int,
+    #[display("uint")]
+    UInt,
+    #[display("number")]
+    Float,
+    #[display("x y z")]
+    Int3,
+    #[display("x y z")]
+    Float3,
+    #[display("voxel")]
+    Voxel,
+    #[display("name")]
+    Name,
+}
+
+impl ArgKind {
+    /// Number of words taken by the argument.
+    pub const fn n_words(self) -> usize {
+        match self {
+            Self::Int3 | Self::Float3 => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// Parsed command argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Int3(Int3),
+    Float3(vec3),
+    Voxel(Id),
+    Name(String),
+}
+
+/// Command parameter description.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    /// Optional parameters can only be the last ones.
+    pub is_optional: bool,
+}
+
+impl Param {
+    pub const fn new(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            is_optional: false,
+        }
+    }
+
+    pub const fn optional(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            is_optional: true,
+        }
+    }
+}
+
+/// Parsed arguments. Their types match [parameters][Param] of the command.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Args(Vec<Arg>);
+
+impl Args {
+    pub fn get(&self, idx: usize) -> Option<&Arg> {
+        self.0.get(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Int`].
+    pub fn int(&self, idx: usize) -> Option<i32> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Int(value) => *value,
+            _ => panic!("argument {idx} should be an int"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::UInt`].
+    pub fn uint(&self, idx: usize) -> Option<u32> {
+        self.get(idx).map(|arg| match arg {
+            Arg::UInt(value) => *value,
+            _ => panic!("argument {idx} should be an unsigned int"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Float`].
+    pub fn float(&self, idx: usize) -> Option<f32> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Float(value) => *value,
+            _ => panic!("argument {idx} should be a number"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Int3`].
+    pub fn int3(&self, idx: usize) -> Option<Int3> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Int3(value) => *value,
+            _ => panic!("argument {idx} should be a position"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Float3`].
+    pub fn float3(&self, idx: usize) -> Option<vec3> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Float3(value) => *value,
+            _ => panic!("argument {idx} should be a point"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Voxel`].
+    pub fn voxel(&self, idx: usize) -> Option<Id> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Voxel(value) => *value,
+            _ => panic!("argument {idx} should be a voxel"),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// Panics if argument is not [`Arg::Name`].
+    pub fn name(&self, idx: usize) -> Option<&str> {
+        self.get(idx).map(|arg| match arg {
+            Arg::Name(value) => value.as_str(),
+            _ => panic!("argument {idx} should be a name"),
+        })
+    }
+}
+
+/// Registered console command.
+#[derive(Clone, Copy, Debug)]
+pub struct ConsoleCommand {
+    /// Name without the [prefix][PREFIX].
+    pub name: &'static str,
+    pub params: &'static [Param],
+    pub help: &'static str,
+    pub run: fn(&Args) -> Result<(), ConsoleError>,
+}
+
+impl ConsoleCommand {
+    /// Gives usage line, e.g. `/tp <pos: x y z>`.
+    pub fn usage(&self) -> String {
+        let mut usage = format!("{PREFIX}{name}", name = self.name);
+
+        for param in self.params {
+            let (open, close) = match param.is_optional {
+                true => ('[', ']'),
+                false => ('<', '>'),
+            };
+
+            usage += &format!(
+                " {open}{name}: {kind}{close}",
+                name = param.name,
+                kind = param.kind
+            );
+        }
+
+        usage
+    }
+
+    /// Parses argument words.
+    pub fn parse_args(&self, words: &[&str]) -> Result<Args, ConsoleError> {
+        let wrong_count = || ConsoleError::WrongArgCount {
+            usage: self.usage(),
+        };
+
+        let n_required_words: usize = self
+            .params
+            .iter()
+            .filter(|param| !param.is_optional)
+            .map(|param| param.kind.n_words())
+            .sum();
+
+        if words.len() < n_required_words {
+            return Err(wrong_count());
+        }
+
+        let mut args = vec![];
+        let mut words = words.iter().copied();
+
+        for param in self.params {
+            let param_words: Vec<_> = words.by_ref().take(param.kind.n_words()).collect();
+
+            if param_words.is_empty() && param.is_optional {
+                break;
+            }
+
+            if param_words.len() != param.kind.n_words() {
+                return Err(wrong_count());
+            }
+
+            args.push(Self::parse_arg(param.kind, &param_words)?);
+        }
+
+        match words.next() {
+            Some(_) => Err(wrong_count()),
+            None => Ok(Args(args)),
+        }
+    }
+
+    fn parse_arg(kind: ArgKind, words: &[&str]) -> Result<Arg, ConsoleError> {
+        let int = |word: &str| {
+            word.parse::<i32>()
+                .map_err(|_| ConsoleError::InvalidInt(word.to_owned()))
+        };
+
+        let float = |word: &str| {
+            word.parse::<f32>()
+                .map_err(|_| ConsoleError::InvalidFloat(word.to_owned()))
+        };
+
+        Ok(match kind {
+            ArgKind::Int => Arg::Int(int(words[0])?),
+            ArgKind::UInt => Arg::UInt(
+                words[0]
+                    .parse()
+                    .map_err(|_| ConsoleError::InvalidUInt(words[0].to_owned()))?,
+            ),
+            ArgKind::Float => Arg::Float(float(words[0])?),
+            ArgKind::Float3 => Arg::Float3(vec3::new(
+                float(words[0])?,
+                float(words[1])?,
+                float(words[2])?,
+            )),
+            ArgKind::Int3 => Arg::Int3(Int3::new(int(words[0])?, int(words[1])?, int(words[2])?)),
+            ArgKind::Voxel => Arg::Voxel(Self::parse_voxel(words[0])?),
+            ArgKind::Name => Arg::Name(words[0].to_owned()),
+        })
+    }
+
+    /// Parses voxel name or numeric id.
+    fn parse_voxel(word: &str) -> Result<Id, ConsoleError> {
+        let id = match word.parse::<Id>() {
+            Ok(id) => voxel::is_id_valid(id).then_some(id),
+            Err(_) => voxel::id_by_name(word),
+        };
+
+        id.ok_or_else(|| ConsoleError::UnknownVoxel(word.to_owned()))
+    }
+}
+
+/// Command registry with input history.
+#[derive(Clone, Debug, Default)]
+pub struct Console {
+    commands: Vec<ConsoleCommand>,
+    history: Vec<String>,
+    /// Position in history while browsing it with arrow keys.
+    history_pos: Option<usize>,
+    /// Lines entered since last [`take_input`].
+    input: Vec<String>,
+}
+
+impl Console {
+    /// Constructs console with [built-in commands][BUILTIN_COMMANDS].
+    pub fn new() -> Self {
+        let mut console = Self::default();
+
+        for command in BUILTIN_COMMANDS {
+            console.register(command);
+        }
+
+        console
+    }
+
+    /// Registers new command replacing one with the same name.
+    pub fn register(&mut self, command: ConsoleCommand) {
+        self.commands.retain(|other| other.name != command.name);
+        self.commands.push(command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    /// Parses `line` into command and its arguments.
+    pub fn parse(&self, line: &str) -> Result<(&ConsoleCommand, Args), ConsoleError> {
+        let words: Vec<_> = line.split_whitespace().collect();
+
+        let Some(name) = words.first().and_then(|word| word.strip_prefix(PREFIX)) else {
+            return Err(ConsoleError::NotACommand(line.to_owned()));
+        };
+
+        let command = self
+            .get(name)
+            .ok_or_else(|| ConsoleError::UnknownCommand(name.to_owned()))?;
+
+        Ok((command, command.parse_args(&words[1..])?))
+    }
+
+    /// Parses and runs `line`.
+    pub fn execute(&self, line: &str) -> Result<(), ConsoleError> {
+        if let Some(name) = line.trim().strip_prefix(PREFIX)
+            && let Some(topic) = name.strip_prefix("help")
+            && (topic.is_empty() || topic.starts_with(char::is_whitespace))
+        {
+            self.help(topic.trim());
+            return Ok(());
+        }
+
+        let (command, args) = self.parse(line)?;
+        (command.run)(&args)
+    }
+
+    /// Logs usage of `name` command or of all commands if `name` is empty.
+    pub fn help(&self, name: &str) {
+        if name.is_empty() {
+            logger::log!(Info, from = "console", "{PREFIX}help [command]: shows help");
+
+            for command in self.commands.iter() {
+                logger::log!(
+                    Info,
+                    from = "console",
+                    "{usage}: {help}",
+                    usage = command.usage(),
+                    help = command.help,
+                );
+            }
+
+            return;
+        }
+
+        match self.get(name.trim_start_matches(PREFIX)) {
+            Some(command) => logger::log!(
+                Info,
+                from = "console",
+                "{usage}: {help}",
+                usage = command.usage(),
+                help = command.help,
+            ),
+            None => logger::log!(Error, from = "console", "unknown command '{name}'"),
+        }
+    }
+
+    /// Gives all possible completions of the last word of `line`.
+    pub fn completions(&self, line: &str) -> Vec<String> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let is_new_word = line.is_empty() || line.ends_with(char::is_whitespace);
+
+        let last_word = match is_new_word {
+            true => "",
+            false => words.last().copied().unwrap_or_default(),
+        };
+
+        let n_complete_words = words.len() - !is_new_word as usize;
+
+        // Complete command name.
+        if n_complete_words == 0 {
+            return match last_word.strip_prefix(PREFIX) {
+                Some(prefix) => self
+                    .commands
+                    .iter()
+                    .map(|command| command.name)
+                    .chain(["help"])
+                    .filter(|name| name.starts_with(prefix))
+                    .map(|name| format!("{PREFIX}{name}"))
+                    .sorted()
+                    .collect(),
+                None => vec![],
+            };
+        }
+
+        let Some(command) = words[0]
+            .strip_prefix(PREFIX)
+            .and_then(|name| self.get(name))
+        else {
+            return vec![];
+        };
+
+        // Find parameter which the last word belongs to.
+        let mut n_words = n_complete_words - 1;
+        let param = command.params.iter().find(|param| {
+            if n_words < param.kind.n_words() {
+                return true;
+            }
+
+            n_words -= param.kind.n_words();
+            false
+        });
+
+        match param {
+            Some(param) if param.kind == ArgKind::Voxel => VOXEL_DATA
+                .iter()
+                .map(|data| data.name.to_lowercase())
+                .filter(|name| name.starts_with(&last_word.to_lowercase()))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Completes last word of `line` as far as possible.
+    pub fn complete(&self, line: &str) -> Option<String> {
+        let completions = self.completions(line);
+
+        let completion = match completions.as_slice() {
+            [] => return None,
+            [single] => format!("{single} "),
+            [first, rest @ ..] => {
+                let prefix_len = rest.iter().fold(first.len(), |len, other| {
+                    first
+                        .char_indices()
+                        .zip(other.chars())
+                        .find(|((_, a), b)| a != b)
+                        .map_or(first.len().min(other.len()), |((idx, _), _)| idx)
+                        .min(len)
+                });
+
+                first[..prefix_len].to_owned()
+            }
+        };
+
+        let last_word_start = line.rfind(char::is_whitespace).map_or(0, |idx| idx + 1);
+
+        Some(format!("{}{completion}", &line[..last_word_start]))
+    }
+
+    /// Adds entered line into history.
+    pub fn push_history(&mut self, line: String) {
+        self.history_pos = None;
+
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+    /// Gives previous history line.
+    pub fn history_prev(&mut self) -> Option<&str> {
+        let pos = match self.history_pos {
+            Some(pos) => pos.checked_sub(1)?,
+            None => self.history.len().checked_sub(1)?,
+        };
+
+        self.history_pos = Some(pos);
+        self.history.get(pos).map(String::as_str)
+    }
+
+    /// Gives next history line. Gives empty line after the last one.
+    pub fn history_next(&mut self) -> Option<&str> {
+        let pos = self.history_pos? + 1;
+
+        if pos == self.history.len() {
+            self.history_pos = None;
+            return Some("");
+        }
+
+        self.history_pos = Some(pos);
+        self.history.get(pos).map(String::as_str)
+    }
+}
+
+/// Handles tab completion and history of console input.
+struct InputHandler<'c> {
+    console: &'c mut Console,
+}
+
+impl imgui::InputTextCallbackHandler for InputHandler<'_> {
+    fn on_completion(&mut self, mut data: imgui::TextCallbackData) {
+        let line = data.str().to_owned();
+        let completions = self.console.completions(&line);
+
+        if 1 < completions.len() {
+            logger::log!(Info, from = "console", "{}", completions.join(" "));
+        }
+
+        if let Some(completed) = self.console.complete(&line) {
+            data.clear();
+            data.push_str(&completed);
+        }
+    }
+
+    fn on_history(
+        &mut self,
+        direction: imgui::HistoryDirection,
+        mut data: imgui::TextCallbackData,
+    ) {
+        let line = match direction {
+            imgui::HistoryDirection::Up => self.console.history_prev(),
+            imgui::HistoryDirection::Down => self.console.history_next(),
+        };
+
+        if let Some(line) = line {
+            data.clear();
+            data.push_str(line);
+        }
+    }
+}
+
+/// Spawns console input line. Entered lines are given by [`take_input`].
+pub fn spawn_input(ui: &imgui::Ui) {
+    static INPUT: Mutex<String> = Mutex::new(String::new());
+
+    let mut input = INPUT.lock().unwrap();
+    let mut console = CONSOLE.lock().unwrap();
+
+    let is_enter_pressed = ui
+        .input_text("Console", &mut input)
+        .enter_returns_true(true)
+        .callback(
+            imgui::InputTextCallback::COMPLETION | imgui::InputTextCallback::HISTORY,
+            InputHandler {
+                console: &mut console,
+            },
+        )
+        .build();
+
+    if is_enter_pressed && !input.trim().is_empty() {
+        let line = mem::take(&mut *input);
+
+        console.push_history(line.clone());
+        console.input.push(line.replace("^;", "\n"));
+    }
+}
+
+/// Takes all lines entered into the console since last call.
+pub fn take_input() -> Vec<String> {
+    mem::take(&mut CONSOLE.lock().unwrap().input)
+}
+
+/// Checks if `line` is a console command rather than a script.
+pub fn is_command(line: &str) -> bool {
+    line.trim_start().starts_with(PREFIX)
+}
+
+/// Runs console command and logs the error if any.
+pub fn execute(line: &str) {
+    logger::log!(Info, from = "console", "> {line}");
+
+    CONSOLE
+        .lock()
+        .unwrap()
+        .execute(line)
+        .log_error("console", "failed to execute command");
+}
+
+/// Registers new command in the global console.
+pub fn register(command: ConsoleCommand) {
+    CONSOLE.lock().unwrap().register(command);
+}
+
+/// Commands available by default.
+pub const BUILTIN_COMMANDS: [ConsoleCommand; 10] = [
+    ConsoleCommand {
+        name: "tp",
+        params: &[Param::new("pos", ArgKind::Float3)],
+        help: "teleports camera to the position",
+        run: |args| {
+            camera::command(CameraCommand::SetPosition(args.float3(0).unwrap()));
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "set",
+        params: &[
+            Param::new("pos", ArgKind::Int3),
+            Param::new("voxel", ArgKind::Voxel),
+        ],
+        help: "sets voxel at the position",
+        run: |args| {
+            commands::command(Command::SetVoxel {
+                pos: args.int3(0).unwrap(),
+                new_id: args.voxel(1).unwrap(),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "fill",
+        params: &[
+            Param::new("from", ArgKind::Int3),
+            Param::new("to", ArgKind::Int3),
+            Param::new("voxel", ArgKind::Voxel),
+        ],
+        help: "fills region between two corners inclusively",
+        run: |args| {
+            let (from, to) = (args.int3(0).unwrap(), args.int3(1).unwrap());
+
+            commands::command(Command::FillVoxels {
+                pos_from: Int3::new(from.x.min(to.x), from.y.min(to.y), from.z.min(to.z)),
+                pos_to: Int3::new(from.x.max(to.x), from.y.max(to.y), from.z.max(to.z)) + Int3::ONE,
+                new_id: args.voxel(2).unwrap(),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "seed",
+        params: &[Param::optional("seed", ArgKind::UInt)],
+        help: "shows or sets generator seed and regenerates the world",
+        run: |args| {
+            match args.uint(0) {
+                Some(seed) => {
+                    generator::set_seed(seed);
+                    generator::rebuild();
+                    logger::log!(Info, from = "console", "seed is set to {seed}");
+                }
+                None => logger::log!(
+                    Info,
+                    from = "console",
+                    "seed is {seed}",
+                    seed = generator::seed(),
+                ),
+            }
+
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "save",
+        params: &[Param::optional("name", ArgKind::Name)],
+        help: "saves the world",
+        run: |args| {
+            commands::command(Command::Save {
+                name: args.name(0).unwrap_or(cfg::save::WORLD_NAME).to_owned(),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "load",
+        params: &[Param::optional("name", ArgKind::Name)],
+        help: "loads the world",
+        run: |args| {
+            commands::command(Command::Load {
+                name: args.name(0).unwrap_or(cfg::save::WORLD_NAME).to_owned(),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "lod",
+        params: &[Param::new("threshold", ArgKind::Float)],
+        help: "sets distance threshold between chunk LODs",
+        run: |args| {
+            let threshold = args.float(0).unwrap();
+
+            if threshold <= 0.0 {
+                return Err(ConsoleError::InvalidFloat(threshold.to_string()));
+            }
+
+            commands::command(Command::SetLodThreshold(threshold));
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "gen",
+        params: &[Param::new("sizes", ArgKind::Int3)],
+        help: "generates new world of given size in chunks",
+        run: |args| {
+            let sizes = args.int3(0).unwrap();
+
+            if sizes.x <= 0 || sizes.y <= 0 || sizes.z <= 0 {
+                return Err(ConsoleError::InvalidSizes(sizes));
+            }
+
+            commands::command(Command::Generate {
+                sizes: USize3::from(sizes),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "summon",
+        params: &[
+            Param::new("kind", ArgKind::Name),
+            Param::new("pos", ArgKind::Float3),
+        ],
+        help: "spawns mob, projectile or item of voxel name at the position",
+        run: |args| {
+            let name = args.name(0).unwrap();
+            let kind = EntityKind::from_name(name)
+                .ok_or_else(|| ConsoleError::UnknownEntity(name.to_owned()))?;
+
+            commands::command(Command::SpawnEntity {
+                kind,
+                pos: args.float3(1).unwrap(),
+                velocity: vec3::zero(),
+            });
+            Ok(())
+        },
+    },
+    ConsoleCommand {
+        name: "gamemode",
+        params: &[Param::new("mode", ArgKind::Name)],
+        help: "switches between creative and survival modes",
+        run: |args| {
+            let name = args.name(0).unwrap();
+            let mode = GameMode::from_name(name)
+                .ok_or_else(|| ConsoleError::UnknownGameMode(name.to_owned()))?;
+
+            commands::command(Command::SetGameMode(mode));
+            Ok(())
+        },
+    },
+];
+
+#[derive(Debug, Error)]
+pub enum ConsoleError {
+    #[error("'{0}' is not a command, commands start with '/'")]
+    NotACommand(String),
+
+    #[error("unknown command '{0}', see /help")]
+    UnknownCommand(String),
+
+    #[error("wrong number of arguments, usage: {usage}")]
+    WrongArgCount { usage: String },
+
+    #[error("'{0}' is not an integer")]
+    InvalidInt(String),
+
+    #[error("'{0}' is not an integer in range 0..=4294967295")]
+    InvalidUInt(String),
+
+    #[error("'{0}' is not a valid number")]
+    InvalidFloat(String),
+
+    #[error("unknown voxel '{0}'")]
+    UnknownVoxel(String),
+
+    #[error("invalid world sizes {0}")]
+    InvalidSizes(Int3),
+
+    #[error("unknown entity '{0}'")]
+    UnknownEntity(String),
+
+    #[error("unknown game mode '{0}', expected creative or survival")]
+    UnknownGameMode(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::terrain::voxel::voxel_data::data::STONE_VOXEL_DATA};
+
+    #[test]
+    fn parse_typed_args() {
+        let console = Console::new();
+
+        let (command, args) = console.parse("/fill 1 2 3  -4 5 6 Stone").unwrap();
+        assert_eq!(command.name, "fill");
+        assert_eq!(args.int3(0), Some(Int3::new(1, 2, 3)));
+        assert_eq!(args.int3(1), Some(Int3::new(-4, 5, 6)));
+        assert_eq!(args.voxel(2), Some(STONE_VOXEL_DATA.id));
+
+        let (_, args) = console.parse("/set 0 0 0 2").unwrap();
+        assert_eq!(args.voxel(1), Some(2));
+
+        let (_, args) = console.parse("/tp 0.5 10 -3").unwrap();
+        assert_eq!(args.float3(0), Some(vec3::new(0.5, 10.0, -3.0)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let console = Console::new();
+
+        assert!(matches!(
+            console.parse("/set 0 0 stone"),
+            Err(ConsoleError::WrongArgCount { .. }),
+        ));
+        assert!(matches!(
+            console.parse("/set 0 0 0 stone extra"),
+            Err(ConsoleError::WrongArgCount { .. }),
+        ));
+        assert!(matches!(
+            console.parse("/set 0 x 0 stone"),
+            Err(ConsoleError::InvalidInt(_)),
+        ));
+        assert!(matches!(
+            console.parse("/seed -1"),
+            Err(ConsoleError::InvalidUInt(_)),
+        ));
+        assert!(matches!(
+            console.parse("/seed 4294967296"),
+            Err(ConsoleError::InvalidUInt(_)),
+        ));
+        assert!(matches!(
+            console.parse("/set 0 0 0 cheese"),
+            Err(ConsoleError::UnknownVoxel(_)),
+        ));
+        assert!(matches!(
+            console.parse("/fly"),
+            Err(ConsoleError::UnknownCommand(_)),
+        ));
+        assert!(matches!(
+            console.parse("print(1)"),
+            Err(ConsoleError::NotACommand(_)),
+        ));
+    }
+
+    #[test]
+    fn optional_args() {
+        let console = Console::new();
+
+        assert!(console.parse("/save").unwrap().1.is_empty());
+        assert_eq!(console.parse("/save test").unwrap().1.name(0), Some("test"));
+    }
+
+    #[test]
+    fn usage_line() {
+        let console = Console::new();
+
+        assert_eq!(
+            console.get("set").unwrap().usage(),
+            "/set <pos: x y z> <voxel: voxel>",
+        );
+        assert_eq!(console.get("seed").unwrap().usage(), "/seed [seed: uint]");
+    }
+
+    #[test]
+    fn tab_completion() {
+        let console = Console::new();
+
+        assert_eq!(console.complete("/ti"), None);
+        assert_eq!(console.complete("/t").as_deref(), Some("/tp "));
+        assert_eq!(console.complete("/se").as_deref(), Some("/se"));
+        assert_eq!(console.complete("/sa").as_deref(), Some("/save "));
+        assert_eq!(
+            console.complete("/set 1 2 3 st").as_deref(),
+            Some("/set 1 2 3 stone ")
+        );
+        assert_eq!(console.complete("/set 1 2 st"), None);
+        assert_eq!(
+            console.completions("/fill 0 0 0 1 1 1 ").len(),
+            VOXEL_DATA.len()
+        );
+    }
+
+    #[test]
+    fn tab_completion_of_non_ascii_names() {
+        let mut console = Console::new();
+
+        for name in ["über", "übung"] {
+            console.register(ConsoleCommand {
+                name,
+                params: &[],
+                help: "",
+                run: |_| Ok(()),
+            });
+        }
+
+        assert_eq!(console.complete("/ü").as_deref(), Some("/üb"));
+        assert_eq!(console.complete("/übe").as_deref(), Some("/über "));
+    }
+
+    #[test]
+    fn history_navigation() {
+        let mut console = Console::new();
+
+        console.push_history("/a".into());
+        console.push_history("/b".into());
+        console.push_history("/b".into());
+
+        assert_eq!(console.history_prev(), Some("/b"));
+        assert_eq!(console.history_prev(), Some("/a"));
+        assert_eq!(console.history_prev(), None);
+        assert_eq!(console.history_next(), Some("/b"));
+        assert_eq!(console.history_next(), Some(""));
+        assert_eq!(console.history_next(), None);
+    }
+}
@@ -0,0 +1,256 @@
+//!
+//! Components attached to [entities][super::Entity].
+//!
+
+use crate::{
+    prelude::*,
+    terrain::voxel::{
+        self,
+        voxel_data::{Id, VoxelData, data::*},
+    },
+};
+
+/// Position of the entity center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub pos: vec3,
+}
+
+/// Velocity in units per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Velocity(pub vec3);
+
+/// Bounding box colliding with voxels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub half_size: vec3,
+    /// Downwards acceleration in units per second squared.
+    pub gravity: f32,
+    /// Horizontal velocity lost per second while standing on the ground.
+    pub friction: f32,
+    pub despawn_on_hit: bool,
+    pub is_on_ground: bool,
+    /// Set if the last move was blocked by a voxel.
+    pub has_hit: bool,
+}
+
+impl Collider {
+    pub fn new(half_size: vec3) -> Self {
+        Self {
+            half_size,
+            gravity: 0.0,
+            friction: 0.0,
+            despawn_on_hit: false,
+            is_on_ground: false,
+            has_hit: false,
+        }
+    }
+
+    /// Gives bounding box of the collider centered at `pos`.
+    pub fn aabb(&self, pos: vec3) -> Aabb {
+        Aabb::from_float3(pos - self.half_size, pos + self.half_size)
+    }
+}
+
+/// Time left until despawn in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lifetime(pub f32);
+
+/// What the entity is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum EntityKind {
+    #[display("mob")]
+    Mob,
+    #[display("item of {id}")]
+    Item { id: Id },
+    #[display("projectile")]
+    Projectile,
+}
+
+impl EntityKind {
+    pub const GRAVITY: f32 = 28.0;
+    pub const MAX_FALL_SPEED: f32 = 60.0;
+
+    pub const MOB_HALF_SIZE: vec3 = vecf!(0.3, 0.9, 0.3);
+    pub const ITEM_HALF_SIZE: vec3 = vecf!(0.125, 0.125, 0.125);
+    pub const PROJECTILE_HALF_SIZE: vec3 = vecf!(0.1, 0.1, 0.1);
+
+    pub const ITEM_LIFETIME: f32 = 300.0;
+    pub const PROJECTILE_LIFETIME: f32 = 30.0;
+
+    /// Start speed of thrown projectiles.
+    pub const PROJECTILE_SPEED: f32 = 30.0;
+
+    /// Gives kind by name: `mob`, `projectile` or a voxel name for an item.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "mob" => Some(Self::Mob),
+            "projectile" => Some(Self::Projectile),
+            _ => voxel::id_by_name(name).map(|id| Self::Item { id }),
+        }
+    }
+
+    /// Gives default collider of this kind.
+    pub fn collider(self) -> Collider {
+        match self {
+            Self::Mob => Collider {
+                gravity: Self::GRAVITY,
+                friction: 10.0,
+                ..Collider::new(Self::MOB_HALF_SIZE)
+            },
+
+            Self::Item { .. } => Collider {
+                gravity: Self::GRAVITY,
+                friction: 5.0,
+                ..Collider::new(Self::ITEM_HALF_SIZE)
+            },
+
+            Self::Projectile => Collider {
+                gravity: 0.5 * Self::GRAVITY,
+                despawn_on_hit: true,
+                ..Collider::new(Self::PROJECTILE_HALF_SIZE)
+            },
+        }
+    }
+
+    /// Gives lifetime of this kind. Entities without it live forever.
+    pub fn lifetime(self) -> Option<f32> {
+        match self {
+            Self::Mob => None,
+            Self::Item { .. } => Some(Self::ITEM_LIFETIME),
+            Self::Projectile => Some(Self::PROJECTILE_LIFETIME),
+        }
+    }
+
+    /// Gives voxel which textures are used to draw this kind.
+    pub fn look(self) -> &'static VoxelData {
+        match self {
+            Self::Mob => LOG_VOXEL_DATA,
+            Self::Item { id } => &VOXEL_DATA[id as usize],
+            Self::Projectile => STONE_VOXEL_DATA,
+        }
+    }
+}
+
+impl AsBytes for EntityKind {
+    fn as_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Mob => compose! { std::iter::once(0), Id::default().as_bytes() }.collect(),
+            Self::Item { id } => compose! { std::iter::once(1), id.as_bytes() }.collect(),
+            Self::Projectile => compose! { std::iter::once(2), Id::default().as_bytes() }.collect(),
+        }
+    }
+}
+
+impl FromBytes for EntityKind {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        read! {
+            source,
+            let variant: u8,
+            let id: Id,
+        }
+
+        match variant {
+            0 => Ok(Self::Mob),
+            1 if voxel::is_id_valid(id) => Ok(Self::Item { id }),
+            2 => Ok(Self::Projectile),
+            _ => Err(ReinterpretError::Conversion(format!(
+                "conversion of byte {variant} with id {id} to EntityKind"
+            ))),
+        }
+    }
+}
+
+impl StaticSize for EntityKind {
+    fn static_size() -> usize {
+        u8::static_size() + Id::static_size()
+    }
+}
+
+/// Saved state of an entity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityRecord {
+    pub kind: EntityKind,
+    pub pos: vec3,
+    pub velocity: vec3,
+    pub lifetime: Option<f32>,
+}
+
+impl AsBytes for EntityRecord {
+    fn as_bytes(&self) -> Vec<u8> {
+        compose! {
+            self.kind.as_bytes(),
+            self.pos.as_bytes(),
+            self.velocity.as_bytes(),
+            self.lifetime.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for EntityRecord {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        read! {
+            source,
+            let kind,
+            let pos,
+            let velocity,
+            let lifetime,
+        }
+
+        Ok(Self {
+            kind,
+            pos,
+            velocity,
+            lifetime,
+        })
+    }
+}
+
+impl DynamicSize for EntityRecord {
+    fn dynamic_size(&self) -> usize {
+        EntityKind::static_size() + 2 * vec3::static_size() + self.lifetime.dynamic_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reinterpret_records() {
+        let before = vec![
+            EntityRecord {
+                kind: EntityKind::Mob,
+                pos: vec3::new(1.0, -2.0, 3.5),
+                velocity: vec3::zero(),
+                lifetime: None,
+            },
+            EntityRecord {
+                kind: EntityKind::Item {
+                    id: DIRT_VOXEL_DATA.id,
+                },
+                pos: vec3::all(7.0),
+                velocity: vec3::new(0.0, 3.0, 0.0),
+                lifetime: Some(12.5),
+            },
+        ];
+
+        let after = Vec::<EntityRecord>::from_bytes(&before.as_bytes()).unwrap();
+
+        assert_eq!(before, after);
+        assert_eq!(before[1].dynamic_size(), before[1].as_bytes().len());
+    }
+
+    #[test]
+    fn kinds_by_name() {
+        assert_eq!(EntityKind::from_name("Mob"), Some(EntityKind::Mob));
+        assert_eq!(
+            EntityKind::from_name("stone"),
+            Some(EntityKind::Item {
+                id: STONE_VOXEL_DATA.id
+            })
+        );
+        assert_eq!(EntityKind::from_name("dragon"), None);
+    }
+}
@@ -0,0 +1,346 @@
+//!
+//! Lightweight entity-component storage for mobs, dropped items and projectiles.
+//!
+
+pub mod components;
+pub mod render;
+pub mod systems;
+
+use {
+    crate::{
+        physics::timestep::FixedTimestep,
+        prelude::*,
+        terrain::{chunk::Chunk, voxel::Voxel},
+    },
+    components::{Collider, EntityKind, EntityRecord, Lifetime, Transform, Velocity},
+};
+
+/// Handle to an entity. Handles of despawned entities stay invalid
+/// even after their slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    idx: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Gives index of the slot of this entity.
+    pub const fn idx(self) -> usize {
+        self.idx as usize
+    }
+}
+
+/// Storage of one component type indexed by entity slot.
+#[derive(Clone, Debug)]
+pub struct Components<T> {
+    items: Vec<Option<T>>,
+}
+
+impl<T> Default for Components<T> {
+    fn default() -> Self {
+        Self { items: vec![] }
+    }
+}
+
+impl<T> Components<T> {
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.items.get(entity.idx())?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.items.get_mut(entity.idx())?.as_mut()
+    }
+
+    /// Attaches `value` to `entity` and returns the old one.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if self.items.len() <= entity.idx() {
+            self.items.resize_with(entity.idx() + 1, || None);
+        }
+
+        self.items[entity.idx()].replace(value)
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.items.get_mut(entity.idx())?.take()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+}
+
+/// All entities of the world with their components.
+#[derive(Clone, Debug, Default)]
+pub struct EntityWorld {
+    generations: Vec<u32>,
+    is_alive: Vec<bool>,
+    free_slots: Vec<u32>,
+
+    pub transforms: Components<Transform>,
+    pub velocities: Components<Velocity>,
+    pub colliders: Components<Collider>,
+    pub kinds: Components<EntityKind>,
+    pub lifetimes: Components<Lifetime>,
+
+    /// Entities by chunk position, see [`systems::bucket`].
+    pub buckets: HashMap<Int3, Vec<Entity>>,
+
+    timestep: FixedTimestep,
+}
+
+impl EntityWorld {
+    /// Fixed time step of entity systems in seconds.
+    pub const TIMESTEP: f32 = FixedTimestep::PHYSICS.step;
+
+    /// Creates entity without components.
+    pub fn spawn_empty(&mut self) -> Entity {
+        let idx = match self.free_slots.pop() {
+            Some(idx) => idx,
+            None => {
+                self.generations.push(0);
+                self.is_alive.push(false);
+                (self.generations.len() - 1) as u32
+            }
+        };
+
+        self.is_alive[idx as usize] = true;
+
+        Entity {
+            idx,
+            generation: self.generations[idx as usize],
+        }
+    }
+
+    /// Creates entity of `kind` with default components of that kind.
+    pub fn spawn(&mut self, kind: EntityKind, pos: vec3, velocity: vec3) -> Entity {
+        let entity = self.spawn_empty();
+
+        self.transforms.insert(entity, Transform { pos });
+        self.velocities.insert(entity, Velocity(velocity));
+        self.colliders.insert(entity, kind.collider());
+        self.kinds.insert(entity, kind);
+
+        if let Some(lifetime) = kind.lifetime() {
+            self.lifetimes.insert(entity, Lifetime(lifetime));
+        }
+
+        entity
+    }
+
+    /// Removes entity with all its components. Returns `false` if it was already removed.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        self.transforms.remove(entity);
+        self.velocities.remove(entity);
+        self.colliders.remove(entity);
+        self.kinds.remove(entity);
+        self.lifetimes.remove(entity);
+
+        self.is_alive[entity.idx()] = false;
+        self.generations[entity.idx()] += 1;
+        self.free_slots.push(entity.idx);
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.is_alive.get(entity.idx()).copied().unwrap_or(false)
+            && self.generations[entity.idx()] == entity.generation
+    }
+
+    /// Gives all alive entities.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.is_alive
+            .iter()
+            .zip(&self.generations)
+            .enumerate()
+            .filter(|&(_, (&is_alive, _))| is_alive)
+            .map(|(idx, (_, &generation))| Entity {
+                idx: idx as u32,
+                generation,
+            })
+    }
+
+    /// Gives number of alive entities.
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free_slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs all systems with fixed time step. Entities below `min_height` are despawned.
+    pub fn update(&mut self, dt: f32, min_height: f32, mut is_solid: impl FnMut(Int3) -> bool) {
+        for _ in 0..self.timestep.advance(dt) {
+            systems::integrate(self, Self::TIMESTEP, &mut is_solid);
+            systems::despawn(self, Self::TIMESTEP, min_height);
+        }
+
+        systems::bucket(self);
+    }
+
+    /// Gives entities in the chunk at `chunk_pos` as of the last [bucketing][systems::bucket].
+    pub fn in_chunk(&self, chunk_pos: Int3) -> &[Entity] {
+        self.buckets.get(&chunk_pos).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gives entities with centers within `radius` from `pos`. Only buckets
+    /// of nearby chunks are looked through.
+    pub fn near(&self, pos: vec3, radius: f32) -> Vec<Entity> {
+        let offset = vec3::all(radius);
+        let chunk_pos_from = Self::chunk_pos_of(pos - offset);
+        let chunk_pos_to = Self::chunk_pos_of(pos + offset) + Int3::ONE;
+
+        SpaceIter::new(chunk_pos_from..chunk_pos_to)
+            .flat_map(|chunk_pos| self.in_chunk(chunk_pos))
+            .copied()
+            .filter(|&entity| {
+                self.is_alive(entity)
+                    && self
+                        .transforms
+                        .get(entity)
+                        .is_some_and(|transform| (transform.pos - pos).len() <= radius)
+            })
+            .collect()
+    }
+
+    /// Gives saved state of entities in the chunk at `chunk_pos`.
+    pub fn records_in_chunk(&self, chunk_pos: Int3) -> Vec<EntityRecord> {
+        self.in_chunk(chunk_pos)
+            .iter()
+            .filter_map(|&entity| self.record(entity))
+            .collect()
+    }
+
+    /// Gives saved state of `entity`.
+    pub fn record(&self, entity: Entity) -> Option<EntityRecord> {
+        Some(EntityRecord {
+            kind: *self.kinds.get(entity)?,
+            pos: self.transforms.get(entity)?.pos,
+            velocity: self.velocities.get(entity).map_or(vec3::zero(), |v| v.0),
+            lifetime: self.lifetimes.get(entity).map(|lifetime| lifetime.0),
+        })
+    }
+
+    /// Constructs world from saved entities.
+    pub fn from_records(records: impl IntoIterator<Item = EntityRecord>) -> Self {
+        let mut world = Self::default();
+
+        for record in records {
+            let entity = world.spawn(record.kind, record.pos, record.velocity);
+
+            match record.lifetime {
+                Some(lifetime) => world.lifetimes.insert(entity, Lifetime(lifetime)),
+                None => world.lifetimes.remove(entity),
+            };
+        }
+
+        systems::bucket(&mut world);
+        world
+    }
+
+    /// Gives position of chunk containing `pos`.
+    pub fn chunk_pos_of(pos: vec3) -> Int3 {
+        Chunk::local_pos(Voxel::pos_of(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::terrain::voxel::{Voxel, voxel_data::data::STONE_VOXEL_DATA},
+    };
+
+    #[test]
+    fn despawned_handles_stay_invalid() {
+        let mut world = EntityWorld::default();
+
+        let first = world.spawn(EntityKind::Mob, vec3::zero(), vec3::zero());
+        assert!(world.despawn(first));
+        assert!(!world.despawn(first));
+
+        let second = world.spawn(EntityKind::Projectile, vec3::zero(), vec3::zero());
+
+        assert_eq!(first.idx(), second.idx());
+        assert!(!world.is_alive(first));
+        assert!(world.is_alive(second));
+        assert_eq!(world.len(), 1);
+        assert_eq!(world.entities().collect::<Vec<_>>(), vec![second]);
+    }
+
+    #[test]
+    fn components_are_removed_on_despawn() {
+        let mut world = EntityWorld::default();
+
+        let item = world.spawn(
+            EntityKind::Item {
+                id: STONE_VOXEL_DATA.id,
+            },
+            vec3::zero(),
+            vec3::zero(),
+        );
+
+        assert!(world.lifetimes.contains(item));
+        assert!(world.colliders.contains(item));
+
+        world.despawn(item);
+
+        assert!(!world.transforms.contains(item));
+        assert!(!world.lifetimes.contains(item));
+        assert!(world.is_empty());
+    }
+
+    #[test]
+    fn near_looks_across_chunks() {
+        let mut world = EntityWorld::default();
+        let border = Chunk::GLOBAL_SIZE - 0.5 * Voxel::SIZE;
+
+        let a = world.spawn(
+            EntityKind::Mob,
+            vec3::new(border - 0.5, 0.0, 0.0),
+            vec3::zero(),
+        );
+        let b = world.spawn(
+            EntityKind::Mob,
+            vec3::new(border + 0.5, 0.0, 0.0),
+            vec3::zero(),
+        );
+        world.spawn(
+            EntityKind::Mob,
+            vec3::new(border + 5.0, 0.0, 0.0),
+            vec3::zero(),
+        );
+        systems::bucket(&mut world);
+
+        assert_eq!(world.near(vec3::new(border, 0.0, 0.0), 1.0), vec![a, b]);
+
+        world.despawn(a);
+        assert_eq!(world.near(vec3::new(border, 0.0, 0.0), 1.0), vec![b]);
+    }
+
+    #[test]
+    fn records_are_restored() {
+        let mut world = EntityWorld::default();
+        world.spawn(EntityKind::Mob, vec3::new(1.0, 2.0, 3.0), vec3::zero());
+        world.spawn(
+            EntityKind::Projectile,
+            vec3::new(100.0, 2.0, 3.0),
+            vec3::new(0.0, 0.0, 5.0),
+        );
+        systems::bucket(&mut world);
+
+        let records = world.records_in_chunk(EntityWorld::chunk_pos_of(vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(records.len(), 1);
+
+        let restored = EntityWorld::from_records(records.clone());
+        let entity = restored.entities().next().unwrap();
+
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored.record(entity), Some(records[0]));
+    }
+}
@@ -0,0 +1,171 @@
+//!
+//! Draws entities as textured boxes into the deferred frame buffer.
+//!
+
+use {
+    super::EntityWorld,
+    crate::{
+        assets::{AssetManager, Handle},
+        graphics::{mesh::UnindexedMesh, shader::Shader},
+        prelude::*,
+        terrain::{chunk::mesh::FullVertex, voxel::shape::CubeDetailed},
+    },
+    glium::{
+        self as gl, DrawError, Surface, VertexBuffer, index::PrimitiveType, uniforms::Uniforms,
+        vertex::BufferCreationError,
+    },
+};
+
+#[derive(Debug)]
+pub struct EntityDrawBundle<'s> {
+    shader: Handle<Shader>,
+    draw_params: gl::DrawParameters<'s>,
+    /// Entities mesh of the current frame, see [`EntityDrawBundle::update`].
+    mesh: Option<UnindexedMesh<FullVertex>>,
+}
+
+impl<'s> EntityDrawBundle<'s> {
+    pub fn new(assets: &mut AssetManager) -> EntityDrawBundle<'s> {
+        let draw_params = gl::DrawParameters {
+            depth: gl::Depth {
+                test: gl::DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            backface_culling: gl::BackfaceCullingMode::CullClockwise,
+            ..Default::default()
+        };
+
+        // Entities share G-buffer layout with chunks.
+        EntityDrawBundle {
+            shader: assets.load(("full_detail", "full_detail")),
+            draw_params,
+            mesh: None,
+        }
+    }
+
+    /// Rebuilds mesh of `entities`. Called once per frame before drawing them.
+    pub fn update(
+        &mut self,
+        entities: &EntityWorld,
+        facade: &dyn gl::backend::Facade,
+    ) -> Result<(), EntityRenderError> {
+        self.mesh = None;
+
+        if entities.is_empty() {
+            return Ok(());
+        }
+
+        let vertices = VertexBuffer::new(facade, &entities.make_vertices())?;
+        self.mesh = Some(UnindexedMesh::new_unindexed(
+            vertices,
+            PrimitiveType::TrianglesList,
+        ));
+
+        Ok(())
+    }
+
+    /// Renders entities of the last [update][EntityDrawBundle::update].
+    pub fn render(
+        &self,
+        target: &mut impl Surface,
+        uniforms: &impl Uniforms,
+    ) -> Result<(), EntityRenderError> {
+        if let Some(mesh) = &self.mesh {
+            mesh.render(target, &self.shader.get(), &self.draw_params, uniforms)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EntityRenderError {
+    #[error(transparent)]
+    Draw(#[from] DrawError),
+
+    #[error(transparent)]
+    BufferCreation(#[from] BufferCreationError),
+}
+
+impl EntityWorld {
+    /// Gives vertices of all entities. Each entity is a stack of cubes filling its collider.
+    pub fn make_vertices(&self) -> Vec<FullVertex> {
+        const N_CUBE_VERTICES: usize = 36;
+
+        let mut result = vec![];
+
+        for entity in self.entities() {
+            let (Some(transform), Some(collider), Some(kind)) = (
+                self.transforms.get(entity),
+                self.colliders.get(entity),
+                self.kinds.get(entity),
+            ) else {
+                continue;
+            };
+
+            let half_size = collider.half_size;
+            let n_cubes = (half_size.y / half_size.x).round().max(1.0) as usize;
+            let cube_size = 2.0 * half_size.y / n_cubes as f32;
+            let bottom = transform.pos.y - half_size.y;
+
+            let cube = CubeDetailed::new(kind.look()).size(cube_size);
+            let mut vertices = SmallVec::<[_; N_CUBE_VERTICES]>::new();
+
+            for i in 0..n_cubes {
+                let center = vec3::new(
+                    transform.pos.x,
+                    bottom + cube_size * (i as f32 + 0.5),
+                    transform.pos.z,
+                );
+
+                cube.back(center, &mut vertices);
+                cube.front(center, &mut vertices);
+                cube.top(center, &mut vertices);
+                cube.bottom(center, &mut vertices);
+                cube.right(center, &mut vertices);
+                cube.left(center, &mut vertices);
+
+                result.extend(vertices.drain(..));
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            entity::components::EntityKind, terrain::voxel::voxel_data::data::DIRT_VOXEL_DATA,
+        },
+    };
+
+    #[test]
+    fn entities_are_stacks_of_cubes() {
+        let mut world = EntityWorld::default();
+        world.spawn(EntityKind::Mob, vec3::new(0.0, 0.9, 0.0), vec3::zero());
+        world.spawn(
+            EntityKind::Item {
+                id: DIRT_VOXEL_DATA.id,
+            },
+            vec3::zero(),
+            vec3::zero(),
+        );
+
+        let vertices = world.make_vertices();
+        assert_eq!(vertices.len(), (3 + 1) * 36);
+
+        let (lo, hi) = vertices[..3 * 36]
+            .iter()
+            .map(|vertex| vertex.position.1)
+            .minmax()
+            .into_option()
+            .unwrap();
+
+        assert!(lo.abs() < 1e-5);
+        assert!((hi - 1.8).abs() < 1e-5);
+    }
+}
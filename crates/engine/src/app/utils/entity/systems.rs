@@ -0,0 +1,203 @@
+//!
+//! Systems run over [entity world][EntityWorld] each fixed step.
+//!
+
+use {
+    super::{EntityWorld, components::EntityKind},
+    crate::{physics::collision, prelude::*},
+};
+
+/// Applies gravity and friction and moves entities colliding them with solid voxels.
+pub fn integrate(world: &mut EntityWorld, dt: f32, mut is_solid: impl FnMut(Int3) -> bool) {
+    let entities = world.entities().collect_vec();
+
+    for entity in entities {
+        let (Some(transform), Some(velocity)) = (
+            world.transforms.get_mut(entity),
+            world.velocities.get_mut(entity),
+        ) else {
+            continue;
+        };
+
+        let Some(collider) = world.colliders.get_mut(entity) else {
+            transform.pos += velocity.0 * dt;
+            continue;
+        };
+
+        velocity.0.y = (velocity.0.y - collider.gravity * dt).max(-EntityKind::MAX_FALL_SPEED);
+
+        if collider.is_on_ground {
+            let damping = (1.0 - collider.friction * dt).max(0.0);
+            velocity.0.x *= damping;
+            velocity.0.z *= damping;
+        }
+
+        let movement =
+            collision::move_aabb(collider.aabb(transform.pos), velocity.0 * dt, &mut is_solid);
+
+        transform.pos += movement.motion;
+
+        collider.is_on_ground = movement.is_blocked[1] && velocity.0.y < 0.0;
+        collider.has_hit = movement.is_blocked.contains(&true);
+
+        let mut components = velocity.0.as_array();
+        for (component, is_blocked) in components.iter_mut().zip(movement.is_blocked) {
+            if is_blocked {
+                *component = 0.0;
+            }
+        }
+        velocity.0 = vec3::from(components);
+    }
+}
+
+/// Despawns entities which lifetime is over, projectiles that hit a voxel
+/// and entities that fell below `min_height`. Returns number of despawned entities.
+pub fn despawn(world: &mut EntityWorld, dt: f32, min_height: f32) -> usize {
+    let entities = world.entities().collect_vec();
+    let mut n_despawned = 0;
+
+    for entity in entities {
+        let is_expired = world.lifetimes.get_mut(entity).is_some_and(|lifetime| {
+            lifetime.0 -= dt;
+            lifetime.0 <= 0.0
+        });
+
+        let has_hit = world
+            .colliders
+            .get(entity)
+            .is_some_and(|collider| collider.despawn_on_hit && collider.has_hit);
+
+        let has_fallen = world
+            .transforms
+            .get(entity)
+            .is_some_and(|transform| transform.pos.y < min_height);
+
+        if (is_expired || has_hit || has_fallen) && world.despawn(entity) {
+            n_despawned += 1;
+        }
+    }
+
+    n_despawned
+}
+
+/// Groups entities by chunks they are in.
+pub fn bucket(world: &mut EntityWorld) {
+    let mut buckets = mem::take(&mut world.buckets);
+
+    for bucket in buckets.values_mut() {
+        bucket.clear();
+    }
+
+    for entity in world.entities() {
+        if let Some(transform) = world.transforms.get(entity) {
+            buckets
+                .entry(EntityWorld::chunk_pos_of(transform.pos))
+                .or_default()
+                .push(entity);
+        }
+    }
+
+    buckets.retain(|_, bucket| !bucket.is_empty());
+    world.buckets = buckets;
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::terrain::{chunk::Chunk, voxel::voxel_data::data::STONE_VOXEL_DATA},
+    };
+
+    fn floor(pos: Int3) -> bool {
+        pos.y == 0
+    }
+
+    fn run(world: &mut EntityWorld, seconds: f32, is_solid: impl Fn(Int3) -> bool + Copy) {
+        for _ in 0..(seconds / EntityWorld::TIMESTEP) as usize {
+            integrate(world, EntityWorld::TIMESTEP, is_solid);
+            despawn(world, EntityWorld::TIMESTEP, -100.0);
+        }
+    }
+
+    #[test]
+    fn item_falls_and_rests_on_floor() {
+        let mut world = EntityWorld::default();
+        let item = world.spawn(
+            EntityKind::Item {
+                id: STONE_VOXEL_DATA.id,
+            },
+            vec3::new(0.0, 5.0, 0.0),
+            vec3::new(2.0, 0.0, 0.0),
+        );
+
+        run(&mut world, 3.0, floor);
+
+        let pos = world.transforms.get(item).unwrap().pos;
+        let collider = world.colliders.get(item).unwrap();
+        let bottom = pos.y - EntityKind::ITEM_HALF_SIZE.y;
+
+        assert!(collider.is_on_ground);
+        assert!((bottom - 0.5).abs() < 2.0 * collision::SKIN);
+        assert!(world.velocities.get(item).unwrap().0.x.abs() < 0.01);
+    }
+
+    #[test]
+    fn projectile_despawns_on_hit() {
+        let mut world = EntityWorld::default();
+        let projectile = world.spawn(
+            EntityKind::Projectile,
+            vec3::new(0.0, 5.0, 0.0),
+            vec3::new(0.0, 0.0, EntityKind::PROJECTILE_SPEED),
+        );
+
+        run(&mut world, 0.5, |pos| pos.z == 8);
+
+        assert!(!world.is_alive(projectile));
+    }
+
+    #[test]
+    fn lifetime_and_fall_despawn() {
+        let mut world = EntityWorld::default();
+        let item = world.spawn(
+            EntityKind::Item {
+                id: STONE_VOXEL_DATA.id,
+            },
+            vec3::new(0.0, 1.0, 0.0),
+            vec3::zero(),
+        );
+        let mob = world.spawn(EntityKind::Mob, vec3::new(10.0, 1.0, 0.0), vec3::zero());
+
+        world.lifetimes.get_mut(item).unwrap().0 = 0.5;
+        assert_eq!(despawn(&mut world, 1.0, -100.0), 1);
+        assert!(!world.is_alive(item));
+
+        assert_eq!(despawn(&mut world, 1.0, 5.0), 1);
+        assert!(!world.is_alive(mob));
+    }
+
+    #[test]
+    fn entities_are_bucketed_by_chunk() {
+        let mut world = EntityWorld::default();
+        let size = Chunk::GLOBAL_SIZE;
+
+        let a = world.spawn(EntityKind::Mob, vec3::new(1.0, 1.0, 1.0), vec3::zero());
+        let b = world.spawn(EntityKind::Mob, vec3::new(2.0, 3.0, 1.0), vec3::zero());
+        let c = world.spawn(
+            EntityKind::Mob,
+            vec3::new(size + 1.0, 1.0, -1.0),
+            vec3::zero(),
+        );
+        bucket(&mut world);
+
+        assert_eq!(world.in_chunk(Int3::ZERO), &[a, b]);
+        assert_eq!(world.in_chunk(Int3::new(1, 0, -1)), &[c]);
+
+        world.transforms.get_mut(a).unwrap().pos = vec3::new(size + 2.0, 1.0, -1.0);
+        world.despawn(b);
+        bucket(&mut world);
+
+        assert!(world.in_chunk(Int3::ZERO).is_empty());
+        assert_eq!(world.in_chunk(Int3::new(1, 0, -1)), &[a, c]);
+        assert_eq!(world.buckets.len(), 1);
+    }
+}
@@ -6,11 +6,35 @@ pub mod frustum;
 use {
     crate::{
         cfg::{camera::default as cam_def, window::default as window_def},
+        concurrency::channel::Channel,
         prelude::*,
     },
     frustum::Frustum,
+    std::sync::Mutex,
 };
 
+lazy_static! {
+    static ref COMMAND_CHANNEL: Mutex<Channel<CameraCommand>> = Mutex::new(Channel::default());
+}
+
+/// Request to the camera from outside of the main loop, e.g. from scripts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraCommand {
+    SetPosition(vec3),
+    SetRotation { roll: f32, pitch: f32, yaw: f32 },
+    SetSpeed(f32),
+}
+
+/// Sends command to the camera. It is applied on next [update][Camera::update].
+pub fn command(command: CameraCommand) {
+    COMMAND_CHANNEL
+        .lock()
+        .unwrap()
+        .sender
+        .send(command)
+        .expect("failed to send camera command");
+}
+
 /// Camera handler.
 #[derive(Debug)]
 pub struct Camera {
@@ -128,6 +152,21 @@ impl Camera {
         self.frustum = Some(Frustum::new(self));
     }
 
+    /// Applies all received [camera commands][CameraCommand].
+    pub fn process_commands(&mut self) {
+        let mut channel = COMMAND_CHANNEL.lock().unwrap();
+
+        while let Ok(command) = channel.receiver.try_recv() {
+            match command {
+                CameraCommand::SetPosition(pos) => self.set_position(pos.x, pos.y, pos.z),
+                CameraCommand::SetRotation { roll, pitch, yaw } => {
+                    self.set_rotation(roll, pitch, yaw)
+                }
+                CameraCommand::SetSpeed(speed) => self.speed_factor = speed,
+            }
+        }
+    }
+
     /// Updates camera (key press checking, etc).
     pub fn update(&mut self, dt: f32) {
         /* Camera move vector */
@@ -175,13 +214,14 @@ impl Camera {
             self.reset_rotation();
         }
 
+        self.update_rotation();
+    }
+
+    /// Rotates camera by mouse movement if cursor is grabbed.
+    pub fn update_rotation(&mut self) {
         /* Cursor borrow */
         if self.grabbes_cursor {
-            self.rotate(
-                0.0,
-                -mouse::get_dy_dt() * 0.002,
-                mouse::get_dx_dt() * 0.002,
-            );
+            self.rotate(0.0, -mouse::get_dy_dt() * 0.002, mouse::get_dx_dt() * 0.002);
         }
     }
 
@@ -201,14 +241,42 @@ impl Camera {
         .as_2d_array()
     }
 
-    pub fn get_ortho(&self, width: f32, height: f32) -> [[f32; 4]; 4] {
-        mat4::orthographic_lh(
-            width,
-            height,
-            cfg::camera::LIGHT_NEAR_PLANE,
-            cfg::camera::LIGHT_FAR_PLANE,
-        )
-        .as_2d_array()
+    /// Gives `front`, `right` and `up` vectors so that `front + x * right + y * up`
+    /// points through the screen point with normalized coordinates `(x, y)`.
+    pub fn view_ray_basis(&self) -> [vec3; 3] {
+        let half_vertical_side = (self.fov.get_radians() / 2.0).tan();
+        let half_horizontal_side = half_vertical_side / self.aspect_ratio;
+
+        // Same axes as the view matrix has.
+        let right = self.up.cross(self.front).normalized();
+        let up = self.front.cross(right);
+
+        [
+            self.front,
+            right * half_horizontal_side,
+            up * half_vertical_side,
+        ]
+    }
+
+    /// Gives corners of the frustum part between `near` and `far` view depths.
+    pub fn frustum_slice_corners(&self, near: f32, far: f32) -> [vec3; 8] {
+        let tan_half_fov = (self.fov.get_radians() / 2.0).tan();
+
+        [near, far]
+            .map(|depth| {
+                let half_vertical_side = tan_half_fov * depth;
+                let half_horizontal_side = half_vertical_side / self.aspect_ratio;
+                let center = self.pos + self.front * depth;
+
+                [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].map(|(right, up)| {
+                    center
+                        + self.right * (right * half_horizontal_side)
+                        + self.up * (up * half_vertical_side)
+                })
+            })
+            .concat()
+            .try_into()
+            .unwrap()
     }
 
     /// Checks if position is in camera frustum
@@ -317,3 +385,27 @@ impl Default for Camera {
         cam
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_rays_pass_through_screen_points() {
+        let cam = Camera::new()
+            .with_position(3.0, -2.0, 7.0)
+            .with_rotation(0.0, 0.4, 2.0);
+
+        let view = mat4::from_2d_array(cam.get_view());
+        let proj = mat4::from_2d_array(cam.get_proj());
+        let [front, right, up] = cam.view_ray_basis();
+
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (0.0, -1.0), (-0.5, 0.75)] {
+            let point = cam.pos + (front + right * x + up * y) * 10.0;
+            let clip = &view.multiply(&proj) * vec4::xyz1(point.x, point.y, point.z);
+
+            assert!((clip.x() / clip.w() - x).abs() < 1e-4);
+            assert!((clip.y() / clip.w() - y).abs() < 1e-4);
+        }
+    }
+}
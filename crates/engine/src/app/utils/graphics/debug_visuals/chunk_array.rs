@@ -4,7 +4,7 @@ use {
         graphics::mesh::Mesh,
         prelude::*,
         terrain::{
-            chunk::{Chunk, chunk_array::ChunkArray},
+            chunk::{Chunk, brush::Brush, chunk_array::ChunkArray},
             voxel::Voxel,
         },
     },
@@ -23,6 +23,12 @@ pub mod data {
     static IS_INIT: AtomicBool = AtomicBool::new(false);
     static mut SHADER: MaybeUninit<ShaderWrapper> = MaybeUninit::uninit();
 
+    thread_local! {
+        /// Last previewed brush with its mesh, rebuilt only when the brush changes.
+        pub static BRUSH_PREVIEW: RefCell<Option<(Brush, Option<UnindexedMesh<Vertex>>)>> =
+            const { RefCell::new(None) };
+    }
+
     lazy_static! {
         static ref DRAW_PARAMS: DrawParametersWrapper<'static> =
             DrawParametersWrapper(DrawParameters {
@@ -63,8 +69,8 @@ pub mod data {
 
     fn cond_init(facade: &dyn Facade) {
         if !IS_INIT.fetch_or(true, Ordering::SeqCst) {
-            let shader = Shader::new("debug_lines", "debug_lines", facade)
-                .expect("failed to make shader");
+            let shader =
+                Shader::new("debug_lines", "debug_lines", facade).expect("failed to make shader");
 
             let value = MaybeUninit::new(ShaderWrapper(shader));
 
@@ -150,6 +156,96 @@ pub mod data {
 
         Mesh::new_unindexed(vbuffer, PrimitiveType::LinesList)
     }
+
+    /// Outlines box of voxels `pos_from..pos_to`.
+    pub fn construct_box_mesh(
+        pos_from: Int3,
+        pos_to: Int3,
+        color: [f32; 4],
+        facade: &dyn Facade,
+    ) -> UnindexedMesh<Vertex> {
+        let bias = vec3::all(cfg::topology::Z_FIGHTING_BIAS);
+        let lo = (vec3::from(pos_from) - vec3::all(0.5)) * Voxel::SIZE - bias;
+        let hi = (vec3::from(pos_to) - vec3::all(0.5)) * Voxel::SIZE + bias;
+
+        let corner = |x: bool, y: bool, z: bool| Vertex {
+            pos: [
+                if x { hi.x } else { lo.x },
+                if y { hi.y } else { lo.y },
+                if z { hi.z } else { lo.z },
+            ],
+            color,
+        };
+
+        let mut vertices = Vec::with_capacity(24);
+
+        for a in [false, true] {
+            for b in [false, true] {
+                vertices.extend([corner(false, a, b), corner(true, a, b)]);
+                vertices.extend([corner(a, false, b), corner(a, true, b)]);
+                vertices.extend([corner(a, b, false), corner(a, b, true)]);
+            }
+        }
+
+        let vbuffer = VertexBuffer::new(facade, &vertices).expect("failed to create vertex buffer");
+
+        Mesh::new_unindexed(vbuffer, PrimitiveType::LinesList)
+    }
+
+    /// Outlines faces of [brush][Brush] shape that look outside of it.
+    pub fn construct_brush_preview_mesh(
+        brush: &Brush,
+        facade: &dyn Facade,
+    ) -> Option<UnindexedMesh<Vertex>> {
+        const COLOR: [f32; 4] = [1.0, 0.6, 0.1, 0.8];
+
+        let (pos_from, pos_to) = brush.bounds();
+        let mut vertices = vec![];
+
+        for pos in SpaceIter::new(pos_from..pos_to).filter(|&pos| brush.contains(pos)) {
+            for adj in SpaceIter::adj_iter(pos) {
+                if brush.contains(adj) {
+                    continue;
+                }
+
+                let normal = vec3::from(adj - pos);
+                let (tangent, bitangent) = match normal.x != 0.0 {
+                    true => (vec3::new(0.0, 0.5, 0.0), vec3::new(0.0, 0.0, 0.5)),
+                    false if normal.y != 0.0 => {
+                        (vec3::new(0.5, 0.0, 0.0), vec3::new(0.0, 0.0, 0.5))
+                    }
+                    false => (vec3::new(0.5, 0.0, 0.0), vec3::new(0.0, 0.5, 0.0)),
+                };
+
+                let center = (vec3::from(pos) + normal * (0.5 + cfg::topology::Z_FIGHTING_BIAS))
+                    * Voxel::SIZE;
+
+                let corners = [
+                    center - tangent - bitangent,
+                    center + tangent - bitangent,
+                    center + tangent + bitangent,
+                    center - tangent + bitangent,
+                ]
+                .map(|corner| Vertex {
+                    pos: corner.as_array(),
+                    color: COLOR,
+                });
+
+                for i in 0..4 {
+                    vertices.push(corners[i]);
+                    vertices.push(corners[(i + 1) % 4]);
+                }
+            }
+        }
+
+        if vertices.is_empty() {
+            return None;
+        }
+
+        let vbuffer = VertexBuffer::new(facade, &vertices).expect("failed to create vertex buffer");
+
+        Some(Mesh::new_unindexed(vbuffer, PrimitiveType::LinesList))
+    }
 }
 
 impl<'s> DebugVisualized<'s, ChunkArray> {
@@ -165,15 +261,21 @@ impl<'s> DebugVisualized<'s, ChunkArray> {
         }
     }
 
-    pub async fn render_chunk_debug(
-        &mut self,
+    /// Rebuilds chunk borders mesh if debug visuals are enabled.
+    /// Called once per frame before drawing them.
+    pub async fn update_chunk_debug(&mut self, facade: &dyn glium::backend::Facade) {
+        if ENABLED.load(Ordering::Relaxed) {
+            self.mesh = data::construct_mesh(self, facade).await;
+        }
+    }
+
+    pub fn render_chunk_debug(
+        &self,
         facade: &dyn glium::backend::Facade,
         target: &mut impl glium::Surface,
         uniforms: &impl Uniforms,
     ) -> Result<(), glium::DrawError> {
         if ENABLED.load(Ordering::Relaxed) {
-            self.mesh = data::construct_mesh(self, facade).await;
-
             let shader = data::get(facade).shader;
             let draw_params = data::get(facade).draw_params;
             self.mesh.render(target, shader, draw_params, uniforms)?;
@@ -181,4 +283,68 @@ impl<'s> DebugVisualized<'s, ChunkArray> {
 
         Ok(())
     }
+
+    /// Draws outline of the region selected by [region tool][crate::terrain::chunk::schematic::RegionTool].
+    pub fn render_selection(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut impl glium::Surface,
+        uniforms: &impl Uniforms,
+    ) -> Result<(), glium::DrawError> {
+        const COLOR: [f32; 4] = [0.2, 0.8, 1.0, 1.0];
+
+        let Some((pos_from, pos_to)) = self.region_tool.selection() else {
+            return Ok(());
+        };
+
+        let mesh = data::construct_box_mesh(pos_from, pos_to, COLOR, facade);
+        let statics = data::get(facade);
+
+        mesh.render(target, statics.shader, statics.draw_params, uniforms)
+    }
+
+    /// Outlines the voxel under cursor.
+    pub fn render_cursor(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut impl glium::Surface,
+        uniforms: &impl Uniforms,
+    ) -> Result<(), glium::DrawError> {
+        const COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+        let Some(pos) = self.cursor() else {
+            return Ok(());
+        };
+
+        let mesh = data::construct_box_mesh(pos, pos + Int3::ONE, COLOR, facade);
+        let statics = data::get(facade);
+
+        mesh.render(target, statics.shader, statics.draw_params, uniforms)
+    }
+
+    /// Draws preview of the brush aimed at the voxel under cursor.
+    pub fn render_brush_preview(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut impl glium::Surface,
+        uniforms: &impl Uniforms,
+    ) -> Result<(), glium::DrawError> {
+        let Some(brush) = self.brush_tool.preview(self.cursor()) else {
+            return Ok(());
+        };
+
+        data::BRUSH_PREVIEW.with_borrow_mut(|preview| {
+            if preview.as_ref().is_none_or(|(cached, _)| *cached != brush) {
+                let mesh = data::construct_brush_preview_mesh(&brush, facade);
+                *preview = Some((brush, mesh));
+            }
+
+            if let Some((_, Some(mesh))) = preview {
+                let statics = data::get(facade);
+                mesh.render(target, statics.shader, statics.draw_params, uniforms)?;
+            }
+
+            Ok(())
+        })
+    }
 }
@@ -7,32 +7,17 @@ pub struct DirectionalLight {
 }
 
 impl DirectionalLight {
-    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
-        use {
-            crate::app::utils::graphics::ui::imgui_constructor::make_window, std::f32::consts::PI,
-        };
-
-        make_window(ui, "Light").build(|| {
-            static ANGLES: (AtomicF32, AtomicF32) = (AtomicF32::new(0.3), AtomicF32::new(5.7));
-
-            let (mut horizontal, mut vertical) = (ANGLES.0.load(Acquire), ANGLES.1.load(Acquire));
-
-            ui.text("Rotation");
-            ui.slider("Horizontal", 0.0, 2.0 * PI, &mut horizontal);
-            ui.slider("Vertical", 0.0, 2.0 * PI, &mut vertical);
-
-            ANGLES.0.store(horizontal, Release);
-            ANGLES.1.store(vertical, Release);
-
-            self.cam.front = vec3::new(
-                f32::cos(vertical) * f32::cos(horizontal),
-                f32::sin(vertical),
-                f32::cos(vertical) * f32::sin(horizontal),
-            );
-        });
+    /// Points the light in `dir` direction.
+    pub fn set_direction(&mut self, dir: vec3) {
+        self.cam.front = dir.normalized();
     }
 
     pub fn update(&mut self, cam_pos: vec3) {
+        // Horizontal light never reaches the height.
+        if self.cam.front.y.abs() < f32::EPSILON {
+            return;
+        }
+
         let interest_pos = cam_pos;
 
         let height = self.relative_pos.y;
@@ -43,3 +28,59 @@ impl DirectionalLight {
         self.cam.set_position(x, y, z);
     }
 }
+
+/// Shape of [local light][LocalLight].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightShape {
+    Point,
+    /// Cone around `dir`, lit fully inside `inner_angle` and fading out to `outer_angle`.
+    Spot {
+        dir: vec3,
+        inner_angle: Angle,
+        outer_angle: Angle,
+    },
+}
+
+/// Light lighting voxels not further than `radius`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocalLight {
+    pub pos: vec3,
+    pub color: Color,
+    pub intensity: f32,
+    pub radius: f32,
+    pub shape: LightShape,
+}
+
+impl LocalLight {
+    pub const fn point(pos: vec3, color: Color, intensity: f32, radius: f32) -> Self {
+        Self {
+            pos,
+            color,
+            intensity,
+            radius,
+            shape: LightShape::Point,
+        }
+    }
+
+    pub const fn spot(
+        pos: vec3,
+        dir: vec3,
+        color: Color,
+        intensity: f32,
+        radius: f32,
+        inner_angle: Angle,
+        outer_angle: Angle,
+    ) -> Self {
+        Self {
+            pos,
+            color,
+            intensity,
+            radius,
+            shape: LightShape::Spot {
+                dir,
+                inner_angle,
+                outer_angle,
+            },
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//!
+//! Tiled shading of [local lights][LocalLight].
+//!
+//! Screen is split into tiles and each tile gets the list of lights which
+//! bounding spheres touch it. Lights and tile lists are uploaded as float
+//! textures read by `local_lights.frag`, so each pixel only evaluates lights
+//! of its tile.
+//!
+
+use {
+    super::{
+        camera::Camera,
+        light::{LightShape, LocalLight},
+    },
+    crate::{app::utils::cfg::local_light as cfg, prelude::*},
+    glium::{
+        Rect,
+        backend::Facade,
+        texture::{MipmapsOption, Texture2d, TextureCreationError, UncompressedFloatFormat},
+        uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler, Uniforms},
+    },
+};
+
+/// Texels per light in the light data texture.
+const LIGHT_ROWS: u32 = 4;
+
+/// Screen split into tiles with lists of lights touching them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LightGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Light indices of tiles row by row from the bottom left one.
+    pub tiles: Vec<Vec<u16>>,
+}
+
+impl LightGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![vec![]; width * height],
+        }
+    }
+
+    /// Gives normalized screen rectangle `(min, max)` covering sphere at `center`
+    /// with `radius` seen by `cam`. Gives [`None`] if the sphere is off the screen.
+    pub fn screen_rect(cam: &Camera, center: vec3, radius: f32) -> Option<(vec2, vec2)> {
+        let [front, right, up] = cam.view_ray_basis();
+        let offset = center - cam.pos;
+
+        // Coordinates in view space scaled so the screen is `[-1, 1]` at depth 1.
+        let depth = offset.dot(front);
+        let x = offset.dot(right) / right.sqr();
+        let y = offset.dot(up) / up.sqr();
+        let (radius_x, radius_y) = (radius / right.len(), radius / up.len());
+
+        if depth + radius <= cam.near_plane_dist {
+            return None;
+        }
+
+        let (min, max) = match depth - radius <= cam.near_plane_dist {
+            true => (vec2::all(-1.0), vec2::all(1.0)),
+            false => {
+                // Sphere is inside the box of depths `depth ± radius`, its projection
+                // is bounded by the projected box corners.
+                let (near, far) = (depth - radius, depth + radius);
+                let lo = |coord: f32| f32::min(coord / near, coord / far);
+                let hi = |coord: f32| f32::max(coord / near, coord / far);
+
+                (
+                    vec2::new(lo(x - radius_x), lo(y - radius_y)),
+                    vec2::new(hi(x + radius_x), hi(y + radius_y)),
+                )
+            }
+        };
+
+        let is_visible = min.x < 1.0 && min.y < 1.0 && -1.0 < max.x && -1.0 < max.y;
+        is_visible.then(|| {
+            (
+                vec2::new(min.x.max(-1.0), min.y.max(-1.0)),
+                vec2::new(max.x.min(1.0), max.y.min(1.0)),
+            )
+        })
+    }
+
+    /// Bins `lights` into tiles keeping at most `max_per_tile` lights in each.
+    pub fn build(&mut self, cam: &Camera, lights: &[LocalLight], max_per_tile: usize) {
+        for tile in self.tiles.iter_mut() {
+            tile.clear();
+        }
+
+        // Gives range of tiles covering `[min, max]` of normalized screen coordinates.
+        let tile_range = |min: f32, max: f32, size: usize| {
+            let to_tile = |coord: f32| {
+                let tile = ((0.5 * coord + 0.5) * size as f32) as usize;
+                tile.min(size.saturating_sub(1))
+            };
+
+            to_tile(min)..=to_tile(max)
+        };
+
+        for (idx, light) in lights.iter().enumerate() {
+            let Some((min, max)) = Self::screen_rect(cam, light.pos, light.radius) else {
+                continue;
+            };
+
+            let xs = tile_range(min.x, max.x, self.width);
+            let ys = tile_range(min.y, max.y, self.height);
+
+            for y in ys {
+                for x in xs.clone() {
+                    let tile = &mut self.tiles[y * self.width + x];
+
+                    if tile.len() < max_per_tile {
+                        tile.push(idx as u16);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gives the largest number of lights in one tile.
+    pub fn max_tile_len(&self) -> usize {
+        self.tiles.iter().map(Vec::len).max().unwrap_or_default()
+    }
+}
+
+/// Local lights of the frame with their GPU data.
+#[derive(Debug)]
+pub struct LocalLights {
+    /// Lights not spawned by voxels.
+    pub lights: Vec<LocalLight>,
+    pub grid: LightGrid,
+    /// Lights drawn in the last frame.
+    pub n_drawn: usize,
+
+    pub is_flashlight_enabled: bool,
+    pub are_emitters_enabled: bool,
+
+    /// Position and radius, color and shape of each light.
+    data_texture: Texture2d,
+    /// Light count and indices of each tile.
+    grid_texture: Texture2d,
+}
+
+impl LocalLights {
+    pub fn new(facade: &dyn Facade) -> Result<Self, TextureCreationError> {
+        let empty = |format, width, height| {
+            Texture2d::empty_with_format(facade, format, MipmapsOption::NoMipmap, width, height)
+        };
+
+        Ok(Self {
+            lights: vec![],
+            grid: LightGrid::new(cfg::TILES_X, cfg::TILES_Y),
+            n_drawn: 0,
+            is_flashlight_enabled: false,
+            are_emitters_enabled: true,
+            data_texture: empty(
+                UncompressedFloatFormat::F32F32F32F32,
+                cfg::MAX_LIGHTS as u32,
+                LIGHT_ROWS,
+            )?,
+            grid_texture: empty(
+                UncompressedFloatFormat::F32,
+                cfg::MAX_LIGHTS_PER_TILE as u32 + 1,
+                (cfg::TILES_X * cfg::TILES_Y) as u32,
+            )?,
+        })
+    }
+
+    /// Gives light attached to the camera.
+    pub fn flashlight(cam: &Camera) -> LocalLight {
+        LocalLight::spot(
+            cam.pos,
+            cam.front,
+            Color::new(1.0, 0.95, 0.8),
+            4.0,
+            32.0,
+            Angle::from_degrees(15.0),
+            Angle::from_degrees(25.0),
+        )
+    }
+
+    /// Uploads own lights and `emitters` nearest to the camera for drawing.
+    pub fn update(&mut self, cam: &Camera, emitters: impl IntoIterator<Item = LocalLight>) {
+        let mut lights = self.lights.clone();
+
+        if self.is_flashlight_enabled {
+            lights.push(Self::flashlight(cam));
+        }
+
+        if self.are_emitters_enabled {
+            lights.extend(emitters);
+        }
+
+        lights.sort_by(|lhs, rhs| {
+            let distance = |light: &LocalLight| (light.pos - cam.pos).sqr();
+            distance(lhs).total_cmp(&distance(rhs))
+        });
+        lights.truncate(cfg::MAX_LIGHTS);
+
+        self.n_drawn = lights.len();
+        self.grid.build(cam, &lights, cfg::MAX_LIGHTS_PER_TILE);
+
+        self.write_lights(&lights);
+        self.write_grid();
+    }
+
+    fn write_lights(&self, lights: &[LocalLight]) {
+        if lights.is_empty() {
+            return;
+        }
+
+        let mut rows = vec![Vec::with_capacity(lights.len()); LIGHT_ROWS as usize];
+
+        for light in lights {
+            let color = light.color * light.intensity;
+
+            let (is_spot, dir, cos_inner, cos_outer) = match light.shape {
+                LightShape::Point => (0.0, vec3::zero(), -1.0, -1.0),
+                LightShape::Spot {
+                    dir,
+                    inner_angle,
+                    outer_angle,
+                } => (
+                    1.0,
+                    dir.normalized(),
+                    inner_angle.get_radians().cos(),
+                    outer_angle.get_radians().cos(),
+                ),
+            };
+
+            rows[0].push((light.pos.x, light.pos.y, light.pos.z, light.radius));
+            rows[1].push((color.r, color.g, color.b, is_spot));
+            rows[2].push((dir.x, dir.y, dir.z, cos_outer));
+            rows[3].push((cos_inner, 0.0, 0.0, 0.0));
+        }
+
+        let rect = Rect {
+            left: 0,
+            bottom: 0,
+            width: lights.len() as u32,
+            height: LIGHT_ROWS,
+        };
+
+        self.data_texture.write(rect, rows);
+    }
+
+    fn write_grid(&self) {
+        let rows = self
+            .grid
+            .tiles
+            .iter()
+            .map(|tile| {
+                let mut row = vec![0.0; cfg::MAX_LIGHTS_PER_TILE + 1];
+                row[0] = tile.len() as f32;
+
+                for (texel, &idx) in row[1..].iter_mut().zip(tile) {
+                    *texel = idx as f32;
+                }
+
+                row
+            })
+            .collect_vec();
+
+        let rect = Rect {
+            left: 0,
+            bottom: 0,
+            width: cfg::MAX_LIGHTS_PER_TILE as u32 + 1,
+            height: rows.len() as u32,
+        };
+
+        self.grid_texture.write(rect, rows);
+    }
+
+    /// Gives `light_data`, `light_grid` and `light_grid_size` uniforms.
+    pub fn uniforms(&self) -> impl Uniforms + '_ {
+        let nearest = |texture| {
+            Sampler::new(texture)
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+        };
+
+        glium::uniform! {
+            light_data: nearest(&self.data_texture),
+            light_grid: nearest(&self.grid_texture),
+            light_grid_size: [self.grid.width as i32, self.grid.height as i32],
+        }
+    }
+
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Local lights")
+            .always_auto_resize(true)
+            .build(|| {
+                ui.checkbox("Emissive voxels", &mut self.are_emitters_enabled);
+                ui.checkbox("Flashlight", &mut self.is_flashlight_enabled);
+
+                ui.text(format!("Drawn lights: {}", self.n_drawn));
+                ui.text(format!(
+                    "Most lights in a tile: {}",
+                    self.grid.max_tile_len()
+                ));
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights_are_binned_into_covered_tiles() {
+        let cam = Camera::new().with_position(0.0, 0.0, 0.0);
+
+        let ahead = cam.pos + cam.front * 20.0;
+        let lights = [
+            LocalLight::point(ahead, Color::all(1.0), 1.0, 1.0),
+            LocalLight::point(cam.pos - cam.front * 20.0, Color::all(1.0), 1.0, 1.0),
+            LocalLight::point(cam.pos, Color::all(1.0), 1.0, 4.0),
+        ];
+
+        let mut grid = LightGrid::new(8, 4);
+        grid.build(&cam, &lights, 8);
+
+        // Light around the camera covers everything, the one behind is culled.
+        assert!(grid.tiles.iter().all(|tile| tile.contains(&2)));
+        assert!(grid.tiles.iter().all(|tile| !tile.contains(&1)));
+
+        // Small light in the center touches only the central tiles.
+        let center_tiles = grid
+            .tiles
+            .iter()
+            .positions(|tile| tile.contains(&0))
+            .collect_vec();
+        assert_eq!(center_tiles, vec![11, 12, 19, 20]);
+
+        grid.build(&cam, &lights, 1);
+        assert_eq!(grid.max_tile_len(), 1);
+    }
+
+    #[test]
+    fn screen_rect_contains_sphere() {
+        let cam = Camera::new()
+            .with_position(5.0, 3.0, -2.0)
+            .with_rotation(0.0, 0.2, 0.7);
+
+        let center = cam.pos + cam.front * 30.0 + cam.up * 4.0;
+        let radius = 2.5;
+        let (min, max) = LightGrid::screen_rect(&cam, center, radius).unwrap();
+
+        let [front, right, up] = cam.view_ray_basis();
+
+        for offset in [
+            cam.right, -cam.right, cam.up, -cam.up, cam.front, -cam.front,
+        ] {
+            let point = center + offset * radius - cam.pos;
+            let depth = point.dot(front);
+            let screen = vec2::new(
+                point.dot(right) / right.sqr() / depth,
+                point.dot(up) / up.sqr() / depth,
+            );
+
+            assert!(min.x <= screen.x && screen.x <= max.x);
+            assert!(min.y <= screen.y && screen.y <= max.y);
+        }
+    }
+}
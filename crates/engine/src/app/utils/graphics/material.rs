@@ -0,0 +1,224 @@
+//!
+//! [Materials][Material] of voxel types uploaded for physically based shading.
+//!
+//! Materials from [`VOXEL_DATA`] can be overridden by `assets/materials.cfg`,
+//! each of its lines is a voxel name followed by `property=value` pairs, e.g.
+//! `Stone roughness=0.6 parallax_depth=0.05`. Lines starting with `#` are comments.
+//!
+
+use {
+    crate::terrain::voxel::voxel_data::{Id, Material, VoxelData, data::VOXEL_DATA},
+    glium::uniforms::{DynamicUniforms, Uniforms},
+    thiserror::Error,
+};
+
+/// Length of `materials` uniform array, injected into shaders as `MAX_MATERIALS`.
+pub const MAX_MATERIALS: usize = 64;
+
+/// Materials of all voxel types indexed by voxel id.
+#[derive(Debug)]
+pub struct Materials {
+    /// Roughness, metalness, emission and parallax depth of each material.
+    values: Vec<[f32; 4]>,
+    /// Uniform names of array elements.
+    names: Vec<String>,
+}
+
+impl Materials {
+    pub fn new(voxels: &[VoxelData]) -> Self {
+        assert!(
+            voxels.len() <= MAX_MATERIALS,
+            "there can be at most {MAX_MATERIALS} materials",
+        );
+
+        let values = voxels
+            .iter()
+            .map(|voxel| {
+                let Material {
+                    roughness,
+                    metallic,
+                    emissive,
+                    parallax_depth,
+                } = voxel.material;
+
+                [roughness, metallic, emissive, parallax_depth]
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            names: (0..values.len())
+                .map(|i| format!("materials[{i}]"))
+                .collect(),
+            values,
+        }
+    }
+
+    /// Replaces material of voxel `id`.
+    pub fn set(&mut self, id: Id, material: Material) {
+        let Material {
+            roughness,
+            metallic,
+            emissive,
+            parallax_depth,
+        } = material;
+
+        self.values[id as usize] = [roughness, metallic, emissive, parallax_depth];
+    }
+
+    /// Gives `materials` uniform.
+    pub fn uniforms(&self) -> impl Uniforms + '_ {
+        let mut uniforms = DynamicUniforms::new();
+
+        for (name, value) in self.names.iter().zip(self.values.iter()) {
+            uniforms.add(name, value);
+        }
+
+        uniforms
+    }
+}
+
+impl Default for Materials {
+    fn default() -> Self {
+        Self::new(VOXEL_DATA)
+    }
+}
+
+/// Parses material overrides of `voxels` from `src`, see [module docs][self].
+pub fn parse_overrides(
+    src: &str,
+    voxels: &[VoxelData],
+) -> Result<Vec<(Id, Material)>, MaterialsParseError> {
+    let mut overrides = vec![];
+
+    for (line_idx, line) in src.lines().enumerate() {
+        let line_number = line_idx + 1;
+        let mut words = line.split_whitespace();
+
+        let Some(name) = words.next().filter(|word| !word.starts_with('#')) else {
+            continue;
+        };
+
+        let voxel = voxels
+            .iter()
+            .find(|voxel| voxel.name == name)
+            .ok_or_else(|| MaterialsParseError::UnknownVoxel {
+                line: line_number,
+                name: name.into(),
+            })?;
+
+        let mut material = voxel.material;
+
+        for word in words {
+            let (property, value) =
+                word.split_once('=')
+                    .ok_or_else(|| MaterialsParseError::MissingValue {
+                        line: line_number,
+                        property: word.into(),
+                    })?;
+
+            let value = value
+                .parse::<f32>()
+                .map_err(|_| MaterialsParseError::InvalidValue {
+                    line: line_number,
+                    value: value.into(),
+                })?;
+
+            match property {
+                "roughness" => material.roughness = value,
+                "metallic" => material.metallic = value,
+                "emissive" => material.emissive = value,
+                "parallax_depth" => material.parallax_depth = value,
+                _ => {
+                    return Err(MaterialsParseError::UnknownProperty {
+                        line: line_number,
+                        property: property.into(),
+                    });
+                }
+            }
+        }
+
+        overrides.push((voxel.id, material));
+    }
+
+    Ok(overrides)
+}
+
+#[derive(Debug, Error)]
+pub enum MaterialsParseError {
+    #[error("line {line}: unknown voxel {name}")]
+    UnknownVoxel { line: usize, name: String },
+
+    #[error("line {line}: unknown material property {property}")]
+    UnknownProperty { line: usize, property: String },
+
+    #[error("line {line}: expected `{property}=value`")]
+    MissingValue { line: usize, property: String },
+
+    #[error("line {line}: {value} is not a number")]
+    InvalidValue { line: usize, value: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn materials_are_indexed_by_voxel_id() {
+        let materials = Materials::default();
+        assert_eq!(materials.values.len(), VOXEL_DATA.len());
+
+        for voxel in VOXEL_DATA {
+            let [roughness, metallic, emissive, parallax_depth] =
+                materials.values[voxel.id as usize];
+
+            assert_eq!(
+                Material {
+                    roughness,
+                    metallic,
+                    emissive,
+                    parallax_depth
+                },
+                voxel.material,
+            );
+            assert!((0.0..=1.0).contains(&roughness) && (0.0..=1.0).contains(&metallic));
+        }
+    }
+
+    #[test]
+    fn overrides_change_only_given_properties() {
+        let stone = VOXEL_DATA
+            .iter()
+            .find(|voxel| voxel.name == "Stone")
+            .unwrap();
+
+        let overrides =
+            parse_overrides("# comment\n\nStone roughness=0.25 metallic=1\n", VOXEL_DATA).unwrap();
+
+        assert_eq!(
+            overrides,
+            vec![(
+                stone.id,
+                Material {
+                    roughness: 0.25,
+                    metallic: 1.0,
+                    ..stone.material
+                },
+            )],
+        );
+
+        let err = parse_overrides("Stone\nUnobtainium roughness=0.5", VOXEL_DATA).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unknown voxel Unobtainium");
+
+        let err = parse_overrides("Stone shininess=0.5", VOXEL_DATA).unwrap_err();
+        assert!(matches!(
+            err,
+            MaterialsParseError::UnknownProperty { line: 1, .. }
+        ));
+
+        let err = parse_overrides("Stone roughness=rough", VOXEL_DATA).unwrap_err();
+        assert!(matches!(
+            err,
+            MaterialsParseError::InvalidValue { line: 1, .. }
+        ));
+    }
+}
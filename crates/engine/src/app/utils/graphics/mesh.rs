@@ -1,5 +1,5 @@
 use {
-    crate::graphics::Shader,
+    crate::graphics::shader::Shader,
     glium::{
         DrawError, DrawParameters, Surface, Vertex, VertexBuffer,
         backend::Facade,
@@ -1,20 +1,24 @@
 pub mod camera;
 pub mod debug_visuals;
 pub mod light;
+pub mod local_lights;
+pub mod material;
 pub mod mesh;
+pub mod pipeline;
+pub mod postprocessing;
+pub mod render_graph;
 pub mod shader;
-pub mod surface;
+pub mod shadow;
 pub mod texture;
 pub mod ui;
 
 use {
-    crate::app::utils::{cfg, logger},
+    crate::app::utils::{assets::AssetManager, cfg, logger},
     derive_deref_rs::Deref,
     imgui_glium_renderer::{Renderer as ImguiRenderer, RendererError as ImguiRendererError},
     math_linear::prelude::*,
-    shader::{Shader, ShaderError},
-    std::{path::PathBuf, pin::Pin},
-    surface::{Surface, SurfaceError},
+    render_graph::{RenderGraph, RenderGraphError},
+    std::{path::Path, pin::Pin},
     thiserror::Error,
 };
 
@@ -33,9 +37,9 @@ pub struct QuadVertex {
 
 glium::implement_vertex! { QuadVertex, position, texcoord }
 
+/// Fullscreen quad drawn by [render graph][RenderGraph] passes.
 #[derive(Debug)]
 pub struct QuadDrawResources {
-    pub shader: Shader,
     pub vertices: glium::VertexBuffer<QuadVertex>,
     pub indices: glium::IndexBuffer<u16>,
 }
@@ -55,13 +59,13 @@ pub struct Graphics {
     pub imguir: ImguiRendererWrapper,
 
     /* Deferred rendering stuff */
-    pub surface: Surface<'static>,
-    pub quad_draw_resources: QuadDrawResources,
+    pub render_graph: RenderGraph,
 }
 
 impl Graphics {
     /// Creates new [`Graphics`] that holds some renderer stuff.
-    pub fn new() -> Result<Self, GraphicsError> {
+    /// Shaders of the render graph are loaded by `assets`.
+    pub fn new(assets: &mut AssetManager) -> Result<Self, GraphicsError> {
         let _log_guard = logger::work("graphics", "initialization");
 
         /* Glutin event loop */
@@ -77,7 +81,9 @@ impl Graphics {
 
         /* Create ImGui context ant set settings file name. */
         let mut imgui_context = imgui::Context::create();
-        imgui_context.set_ini_filename(Some(PathBuf::from(r"assets/imgui_settings.ini")));
+        imgui_context.set_ini_filename(Some(
+            Path::new(cfg::assets::DIRECTORY).join("imgui_settings.ini"),
+        ));
 
         /* Bound ImGui to winit. */
         let mut winit_platform = imgui_winit_support::WinitPlatform::new(&mut imgui_context);
@@ -132,20 +138,16 @@ impl Graphics {
             )
             .map_err(GraphicsError::IndexBuffferCreation)?;
 
-            let shader = Shader::new(
-                "postprocessing",
-                "postprocessing",
-                display.as_ref().get_ref(),
-            )?;
-
-            QuadDrawResources {
-                shader,
-                vertices,
-                indices,
-            }
+            QuadDrawResources { vertices, indices }
         };
 
-        let surface = Surface::new(display.as_ref().get_ref(), UInt2::from(SIZES))?;
+        let render_graph = RenderGraph::new(
+            display.as_ref().get_ref(),
+            UInt2::from(SIZES),
+            pipeline::deferred(),
+            quad_draw_resources,
+            assets,
+        )?;
 
         Ok(Self {
             display,
@@ -154,24 +156,13 @@ impl Graphics {
             imguir: ImguiRendererWrapper(imgui_renderer),
             imguip: winit_platform,
             event_loop: Some(event_loop),
-            surface,
-            quad_draw_resources,
+            render_graph,
         })
     }
 
-    pub fn refresh_postprocessing_shaders(&mut self) -> Result<(), ShaderError> {
-        self.quad_draw_resources.shader = Shader::new(
-            "postprocessing",
-            "postprocessing",
-            self.display.as_ref().get_ref(),
-        )?;
-
-        Ok(())
-    }
-
-    pub fn on_window_resize(&mut self, new_size: UInt2) -> Result<(), SurfaceError> {
+    pub fn on_window_resize(&mut self, new_size: UInt2) -> Result<(), RenderGraphError> {
         self.display.resize((new_size.x, new_size.y));
-        self.surface
+        self.render_graph
             .on_window_resize(self.display.as_ref().get_ref(), new_size)
     }
 
@@ -197,11 +188,8 @@ pub enum GraphicsError {
     #[error("failed to create quad index buffer: {0}")]
     IndexBuffferCreation(IndexCreationError),
 
-    #[error("failed to create shader: {0}")]
-    ShaderCreation(#[from] ShaderError),
-
-    #[error("failed to create surface: {0}")]
-    Surface(#[from] SurfaceError),
+    #[error("failed to create render graph: {0}")]
+    RenderGraph(#[from] RenderGraphError),
 }
 
 #[derive(Deref)]
@@ -212,75 +200,3 @@ impl std::fmt::Debug for ImguiRendererWrapper {
         write!(f, "imgui_glium_renderer::Renderer {{...}}")
     }
 }
-
-pub use crate::draw;
-
-/// Draw to target macro.
-/// It will execute draw calls passed it after clearing target and before finishing it.
-#[macro_export]
-macro_rules! draw {
-    (
-        render_shadows: $render_shadows:expr,
-        $graphics:expr,
-        $make_target:expr,
-        let $uniforms_name:ident = {
-            $(
-                $($uniform_name:ident : $uniform_def:expr),+
-                $(,)?
-            )?
-        },
-        |&mut $fb_name:ident| $fb_draw_call:expr,
-        |mut $target_name:ident| $target_draw_call:expr
-        $(,)?
-    ) => {{
-        use $crate::app::utils::cfg::shader::{CLEAR_COLOR, CLEAR_DEPTH, CLEAR_STENCIL};
-
-        let $uniforms_name = ::glium::uniform! {
-            render_shadows: $render_shadows,
-            $(
-                $($uniform_name : $uniform_def,)+
-            )?
-        };
-
-        let result1 = if $render_shadows {
-            $graphics.surface.shadow_buffer.clear_all(CLEAR_COLOR, CLEAR_DEPTH, CLEAR_STENCIL);
-            {
-                let $uniforms_name = $uniforms_name.add("is_shadow_pass", true);
-                let $fb_name = &mut $graphics.surface.shadow_buffer;
-                $fb_draw_call
-            }
-        } else { };
-
-        $graphics.surface.frame_buffer.clear_all(CLEAR_COLOR, CLEAR_DEPTH, CLEAR_STENCIL);
-        let result2 = {
-            let $uniforms_name = $uniforms_name.add("is_shadow_pass", false);
-            let $fb_name = &mut $graphics.surface.frame_buffer;
-            $fb_draw_call
-        };
-
-        let mut $target_name = { $make_target };
-            let quad_uniforms = ::glium::uniform! {
-                render_shadows: $render_shadows,
-                depth_texture:       &$graphics.surface.get_textures().depth,
-                albedo_texture:      &$graphics.surface.get_textures().albedo,
-                normal_texture:      &$graphics.surface.get_textures().normal,
-                light_depth_texture: &$graphics.surface.get_textures().light_depth,
-                position_texture:    &$graphics.surface.get_textures().position,
-                $(
-                    $($uniform_name : $uniform_def,)+
-                )?
-            };
-
-            $target_name.draw(
-                &$graphics.quad_draw_resources.vertices,
-                &$graphics.quad_draw_resources.indices,
-                &$graphics.quad_draw_resources.shader.program,
-                &quad_uniforms,
-                &Default::default()
-            ).expect("failed to draw to target");
-            let result3 = { $target_draw_call };
-        $target_name.finish().expect("failed to finish target");
-
-        (result1, result2, result3)
-    }};
-}
@@ -0,0 +1,146 @@
+//!
+//! Deferred rendering pipeline as a [render graph][RenderGraphDesc].
+//!
+
+use {
+    super::render_graph::{AttachmentDesc, PassDesc, RenderGraphDesc},
+    crate::app::utils::cfg::shadow::{MAX_CASCADES, N_CASCADES, RESOLUTION},
+    glium::texture::{DepthFormat, UncompressedFloatFormat},
+};
+
+const _: () = assert!(N_CASCADES <= MAX_CASCADES, "too many shadow cascades");
+
+/// Draw the scene depth from the sun, one pass per [shadow cascade][super::shadow].
+pub const SHADOW_PASSES: [&str; MAX_CASCADES] = ["shadow0", "shadow1", "shadow2", "shadow3"];
+
+/// Depth attachments of the shadow passes.
+const SHADOW_MAPS: [&str; MAX_CASCADES] =
+    ["shadow_map0", "shadow_map1", "shadow_map2", "shadow_map3"];
+
+/// Draws the scene into G-buffer.
+pub const GEOMETRY_PASS: &str = "geometry";
+
+/// Darkens creases of G-buffer geometry.
+pub const SSAO_PASS: &str = "ssao";
+
+/// Lights G-buffer with [local lights][super::local_lights].
+pub const LOCAL_LIGHTS_PASS: &str = "local_lights";
+
+/// Lights G-buffer into the HDR target.
+pub const LIGHTING_PASS: &str = "lighting";
+
+/// Extract and blur bright parts of the HDR target.
+pub const BLOOM_PASSES: [&str; 3] = [
+    "bloom_bright",
+    "bloom_blur_horizontal",
+    "bloom_blur_vertical",
+];
+
+/// Applies exposure and maps HDR colors to displayable ones.
+pub const TONEMAP_PASS: &str = "tonemap";
+
+/// Gamma-corrects the image onto the window.
+pub const GAMMA_PASS: &str = "gamma";
+
+/// Gives names of the used shadow passes.
+pub fn shadow_passes() -> &'static [&'static str] {
+    &SHADOW_PASSES[..N_CASCADES]
+}
+
+/// Gives description of the deferred pipeline.
+pub fn deferred() -> RenderGraphDesc {
+    use {DepthFormat as Depth, UncompressedFloatFormat as Color};
+
+    let mut lighting = PassDesc::fullscreen(LIGHTING_PASS, "postprocessing")
+        .with_input("depth")
+        .with_input("albedo")
+        .with_input("normal")
+        .with_input("position")
+        .with_input("material")
+        .with_input("light")
+        .with_input("ao")
+        .with_input("local_light")
+        .with_output("out_color", "hdr");
+
+    let mut desc = RenderGraphDesc::new()
+        .attachment(AttachmentDesc::depth("depth", Depth::F32))
+        .attachment(AttachmentDesc::color("albedo", Color::F11F11F10))
+        .attachment(AttachmentDesc::color("normal", Color::F32F32F32))
+        .attachment(AttachmentDesc::color("position", Color::F32F32F32))
+        .attachment(AttachmentDesc::color("material", Color::F16F16F16))
+        .attachment(AttachmentDesc::color("light", Color::F16F16))
+        .attachment(AttachmentDesc::color("ao", Color::F16))
+        .attachment(AttachmentDesc::color("local_light", Color::F16F16F16))
+        .attachment(AttachmentDesc::color("hdr", Color::F16F16F16F16))
+        .attachment(AttachmentDesc::color("bloom_bright", Color::F16F16F16))
+        .attachment(AttachmentDesc::color("bloom_blurred", Color::F16F16F16))
+        .attachment(AttachmentDesc::color("bloom", Color::F16F16F16))
+        .attachment(AttachmentDesc::color("ldr", Color::F16F16F16));
+
+    for (cascade, (&pass, &map)) in shadow_passes().iter().zip(SHADOW_MAPS.iter()).enumerate() {
+        desc = desc
+            .attachment(AttachmentDesc::depth(map, Depth::F32).with_size(RESOLUTION, RESOLUTION))
+            .pass(
+                PassDesc::shadow(pass, cascade)
+                    .with_depth(map)
+                    .with_flag("is_shadow_pass", true)
+                    .with_int("cascade_idx", cascade as i32)
+                    .disabled(),
+            );
+
+        lighting = lighting.with_input(map);
+    }
+
+    desc.pass(
+        PassDesc::geometry(GEOMETRY_PASS)
+            .with_output("out_albedo", "albedo")
+            .with_output("out_normal", "normal")
+            .with_output("out_position", "position")
+            .with_output("out_material", "material")
+            .with_output("out_light", "light")
+            .with_depth("depth")
+            .with_flag("is_shadow_pass", false),
+    )
+    .pass(
+        PassDesc::fullscreen(SSAO_PASS, "ssao")
+            .with_input("normal")
+            .with_input("position")
+            .with_output("out_ao", "ao"),
+    )
+    .pass(
+        PassDesc::fullscreen(LOCAL_LIGHTS_PASS, "local_lights")
+            .with_input("albedo")
+            .with_input("normal")
+            .with_input("position")
+            .with_output("out_color", "local_light"),
+    )
+    .pass(lighting)
+    .pass(
+        PassDesc::fullscreen(BLOOM_PASSES[0], "bloom_bright")
+            .with_input("hdr")
+            .with_output("out_color", "bloom_bright"),
+    )
+    .pass(
+        PassDesc::fullscreen(BLOOM_PASSES[1], "blur")
+            .with_input_as("bloom_bright", "source")
+            .with_output("out_color", "bloom_blurred")
+            .with_flag("is_horizontal", true),
+    )
+    .pass(
+        PassDesc::fullscreen(BLOOM_PASSES[2], "blur")
+            .with_input_as("bloom_blurred", "source")
+            .with_output("out_color", "bloom")
+            .with_flag("is_horizontal", false),
+    )
+    .pass(
+        PassDesc::fullscreen(TONEMAP_PASS, "tonemap")
+            .with_input("hdr")
+            .with_input("bloom")
+            .with_output("out_color", "ldr"),
+    )
+    .pass(
+        PassDesc::fullscreen(GAMMA_PASS, "gamma")
+            .with_input("ldr")
+            .to_window(),
+    )
+}
@@ -0,0 +1,169 @@
+//!
+//! Settings of the HDR post-processing chain of the [deferred pipeline][pipeline::deferred].
+//!
+
+use {
+    super::{pipeline, render_graph::RenderGraph},
+    glium::uniforms::Uniforms,
+};
+
+/// Operator mapping HDR colors to displayable ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Tonemapper {
+    /// Colors are clamped.
+    None = 0,
+    Reinhard = 1,
+    #[default]
+    Aces = 2,
+}
+
+impl Tonemapper {
+    pub const ALL: [Self; 3] = [Self::None, Self::Reinhard, Self::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Reinhard => "Reinhard",
+            Self::Aces => "ACES",
+        }
+    }
+}
+
+/// Post-processing stages and their parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostProcessing {
+    pub is_ssao_enabled: bool,
+    /// Radius of occluders search in voxels.
+    pub ssao_radius: f32,
+    pub ssao_strength: f32,
+
+    pub is_bloom_enabled: bool,
+    /// Brightness bloom starts at.
+    pub bloom_threshold: f32,
+    pub bloom_intensity: f32,
+
+    pub tonemapper: Tonemapper,
+    pub exposure: f32,
+
+    pub is_gamma_enabled: bool,
+    pub gamma: f32,
+
+    pub is_fog_enabled: bool,
+    /// Fog density at `fog_height`.
+    pub fog_density: f32,
+    /// Height fog is the densest below.
+    pub fog_height: f32,
+    /// How fast fog thins out above `fog_height`.
+    pub fog_falloff: f32,
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self {
+            is_ssao_enabled: true,
+            ssao_radius: 1.0,
+            ssao_strength: 1.0,
+            is_bloom_enabled: true,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.3,
+            tonemapper: Tonemapper::default(),
+            exposure: 1.0,
+            is_gamma_enabled: true,
+            gamma: 2.2,
+            is_fog_enabled: true,
+            fog_density: 0.002,
+            fog_height: 0.0,
+            fog_falloff: 0.02,
+        }
+    }
+}
+
+impl PostProcessing {
+    /// Enables passes of enabled stages in `graph`.
+    pub fn apply(&self, graph: &mut RenderGraph) {
+        graph.set_enabled(pipeline::SSAO_PASS, self.is_ssao_enabled);
+
+        for pass in pipeline::BLOOM_PASSES {
+            graph.set_enabled(pass, self.is_bloom_enabled);
+        }
+    }
+
+    /// Gives uniforms of the post-processing shaders.
+    pub fn uniforms(&self) -> impl Uniforms {
+        glium::uniform! {
+            is_ssao_enabled: self.is_ssao_enabled,
+            ssao_radius: self.ssao_radius,
+            ssao_strength: self.ssao_strength,
+
+            is_bloom_enabled: self.is_bloom_enabled,
+            bloom_threshold: self.bloom_threshold,
+            bloom_intensity: self.bloom_intensity,
+
+            tonemapper: self.tonemapper as i32,
+            exposure: self.exposure,
+
+            is_gamma_enabled: self.is_gamma_enabled,
+            gamma: self.gamma,
+
+            is_fog_enabled: self.is_fog_enabled,
+            fog_density: self.fog_density,
+            fog_height: self.fog_height,
+            fog_falloff: self.fog_falloff,
+        }
+    }
+
+    /// Spawns window with stage switches. Changes are applied to `graph`.
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui, graph: &mut RenderGraph) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        let old = *self;
+
+        make_window(ui, "Post-processing")
+            .always_auto_resize(true)
+            .build(|| {
+                ui.checkbox("Fog", &mut self.is_fog_enabled);
+                if self.is_fog_enabled {
+                    ui.slider("Fog density", 0.0, 0.02, &mut self.fog_density);
+                    ui.slider("Fog height", -64.0, 256.0, &mut self.fog_height);
+                    ui.slider("Fog falloff", 0.001, 0.2, &mut self.fog_falloff);
+                }
+
+                ui.checkbox("SSAO", &mut self.is_ssao_enabled);
+                if self.is_ssao_enabled {
+                    ui.slider("SSAO radius", 0.1, 4.0, &mut self.ssao_radius);
+                    ui.slider("SSAO strength", 0.0, 2.0, &mut self.ssao_strength);
+                }
+
+                ui.checkbox("Bloom", &mut self.is_bloom_enabled);
+                if self.is_bloom_enabled {
+                    ui.slider("Bloom threshold", 0.0, 4.0, &mut self.bloom_threshold);
+                    ui.slider("Bloom intensity", 0.0, 2.0, &mut self.bloom_intensity);
+                }
+
+                let mut tonemapper_idx = Tonemapper::ALL
+                    .iter()
+                    .position(|&tonemapper| tonemapper == self.tonemapper)
+                    .unwrap_or_default();
+
+                if ui.combo(
+                    "Tonemapping",
+                    &mut tonemapper_idx,
+                    &Tonemapper::ALL,
+                    |tonemapper| tonemapper.name().into(),
+                ) {
+                    self.tonemapper = Tonemapper::ALL[tonemapper_idx];
+                }
+
+                ui.slider("Exposure", 0.05, 8.0, &mut self.exposure);
+
+                ui.checkbox("Gamma correction", &mut self.is_gamma_enabled);
+                if self.is_gamma_enabled {
+                    ui.slider("Gamma", 1.0, 3.0, &mut self.gamma);
+                }
+            });
+
+        if *self != old {
+            self.apply(graph);
+        }
+    }
+}
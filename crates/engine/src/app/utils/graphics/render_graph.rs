@@ -0,0 +1,819 @@
+//!
+//! Render graph: named passes reading and writing shared attachments.
+//!
+
+use {
+    super::{QuadDrawResources, shader::Shader},
+    crate::{
+        app::utils::cfg::shader::{CLEAR_COLOR, CLEAR_DEPTH, CLEAR_STENCIL},
+        assets::{AssetManager, Handle},
+        prelude::*,
+    },
+    glium::{
+        BlitMask, BlitTarget, DrawError, DrawParameters, Frame, Program, Rect, Surface,
+        backend::Facade,
+        framebuffer::{MultiOutputFrameBuffer, SimpleFrameBuffer, ValidationError},
+        index::IndicesSource,
+        texture::{
+            DepthFormat, DepthTexture2d, MipmapsOption, Texture2d, TextureCreationError,
+            UncompressedFloatFormat,
+        },
+        uniforms::{MagnifySamplerFilter, UniformValue, Uniforms},
+        vertex::MultiVerticesSource,
+    },
+    thiserror::Error,
+};
+
+/// Format of [attachment][Attachment] texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttachmentFormat {
+    Color(UncompressedFloatFormat),
+    Depth(DepthFormat),
+}
+
+/// Texture written by some passes and read by others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttachmentDesc {
+    pub name: &'static str,
+    pub format: AttachmentFormat,
+    /// Size of the texture relative to the window.
+    pub scale: u32,
+    /// Size of the texture independent of the window, overrides `scale`.
+    pub size: Option<(u32, u32)>,
+}
+
+impl AttachmentDesc {
+    pub const fn color(name: &'static str, format: UncompressedFloatFormat) -> Self {
+        Self {
+            name,
+            format: AttachmentFormat::Color(format),
+            scale: 1,
+            size: None,
+        }
+    }
+
+    pub const fn depth(name: &'static str, format: DepthFormat) -> Self {
+        Self {
+            name,
+            format: AttachmentFormat::Depth(format),
+            scale: 1,
+            size: None,
+        }
+    }
+
+    pub const fn with_scale(self, scale: u32) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub const fn with_size(self, width: u32, height: u32) -> Self {
+        Self {
+            size: Some((width, height)),
+            ..self
+        }
+    }
+}
+
+/// Texture allocated for [attachment description][AttachmentDesc].
+#[derive(Debug)]
+pub enum Attachment {
+    Color(Texture2d),
+    Depth(DepthTexture2d),
+}
+
+impl Attachment {
+    pub fn new(
+        facade: &dyn Facade,
+        desc: &AttachmentDesc,
+        window_size: UInt2,
+    ) -> Result<Self, TextureCreationError> {
+        let (width, height) = desc
+            .size
+            .unwrap_or((window_size.x * desc.scale, window_size.y * desc.scale));
+
+        Ok(match desc.format {
+            AttachmentFormat::Color(format) => Self::Color(Texture2d::empty_with_format(
+                facade,
+                format,
+                MipmapsOption::NoMipmap,
+                width,
+                height,
+            )?),
+            AttachmentFormat::Depth(format) => Self::Depth(DepthTexture2d::empty_with_format(
+                facade,
+                format,
+                MipmapsOption::NoMipmap,
+                width,
+                height,
+            )?),
+        })
+    }
+
+    pub fn as_uniform(&self) -> UniformValue<'_> {
+        match self {
+            Self::Color(texture) => UniformValue::Texture2d(texture, None),
+            Self::Depth(texture) => UniformValue::DepthTexture2d(texture, None),
+        }
+    }
+}
+
+/// Vertex shader of [fullscreen][PassKind::Fullscreen] passes.
+const FULLSCREEN_VERTEX_SHADER: &str = "fullscreen";
+
+/// What the pass draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassKind {
+    /// Scene geometry drawn by the caller of [`RenderGraph::execute`].
+    Geometry,
+    /// Shadow casters drawn by the caller of [`RenderGraph::execute`]
+    /// into the shadow map of cascade with given index.
+    Shadow(usize),
+    /// Fullscreen quad drawn with shader of given name.
+    Fullscreen(&'static str),
+}
+
+/// Description of a render pass.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassDesc {
+    pub name: &'static str,
+    pub kind: PassKind,
+    /// Attachments and names they are bound to as `<name>_texture` uniforms.
+    pub inputs: Vec<(&'static str, &'static str)>,
+    /// Shader outputs and color attachments they are written to.
+    pub outputs: Vec<(&'static str, &'static str)>,
+    pub depth: Option<&'static str>,
+    /// Boolean uniforms set for this pass only.
+    pub flags: Vec<(&'static str, bool)>,
+    /// Integer uniforms set for this pass only.
+    pub ints: Vec<(&'static str, i32)>,
+    /// Pass draws to the window instead of attachments.
+    pub is_final: bool,
+    pub is_enabled: bool,
+}
+
+impl PassDesc {
+    pub fn new(name: &'static str, kind: PassKind) -> Self {
+        Self {
+            name,
+            kind,
+            inputs: vec![],
+            outputs: vec![],
+            depth: None,
+            flags: vec![],
+            ints: vec![],
+            is_final: false,
+            is_enabled: true,
+        }
+    }
+
+    pub fn geometry(name: &'static str) -> Self {
+        Self::new(name, PassKind::Geometry)
+    }
+
+    pub fn shadow(name: &'static str, cascade: usize) -> Self {
+        Self::new(name, PassKind::Shadow(cascade))
+    }
+
+    pub fn fullscreen(name: &'static str, shader: &'static str) -> Self {
+        Self::new(name, PassKind::Fullscreen(shader))
+    }
+
+    pub fn with_input(self, attachment: &'static str) -> Self {
+        self.with_input_as(attachment, attachment)
+    }
+
+    /// Binds `attachment` as `<name>_texture` uniform.
+    pub fn with_input_as(mut self, attachment: &'static str, name: &'static str) -> Self {
+        self.inputs.push((attachment, name));
+        self
+    }
+
+    pub fn with_output(mut self, shader_output: &'static str, attachment: &'static str) -> Self {
+        self.outputs.push((shader_output, attachment));
+        self
+    }
+
+    pub fn with_depth(mut self, attachment: &'static str) -> Self {
+        self.depth = Some(attachment);
+        self
+    }
+
+    pub fn with_flag(mut self, name: &'static str, value: bool) -> Self {
+        self.flags.push((name, value));
+        self
+    }
+
+    pub fn with_int(mut self, name: &'static str, value: i32) -> Self {
+        self.ints.push((name, value));
+        self
+    }
+
+    pub fn to_window(mut self) -> Self {
+        self.is_final = true;
+        self
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.is_enabled = false;
+        self
+    }
+
+    /// Gives all attachments written by the pass.
+    pub fn written(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.outputs
+            .iter()
+            .map(|&(_, attachment)| attachment)
+            .chain(self.depth)
+    }
+}
+
+/// Declarative description of the whole pipeline.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderGraphDesc {
+    pub attachments: Vec<AttachmentDesc>,
+    pub passes: Vec<PassDesc>,
+}
+
+impl RenderGraphDesc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn attachment(mut self, desc: AttachmentDesc) -> Self {
+        self.attachments.push(desc);
+        self
+    }
+
+    pub fn pass(mut self, desc: PassDesc) -> Self {
+        self.passes.push(desc);
+        self
+    }
+
+    fn find_attachment(
+        &self,
+        pass: &'static str,
+        attachment: &'static str,
+    ) -> Result<&AttachmentDesc, RenderGraphError> {
+        self.attachments
+            .iter()
+            .find(|desc| desc.name == attachment)
+            .ok_or(RenderGraphError::UnknownAttachment { pass, attachment })
+    }
+
+    /// Checks that passes refer to declared attachments of right formats
+    /// and read only attachments written by previous passes.
+    pub fn validate(&self) -> Result<(), RenderGraphError> {
+        let mut names = HashSet::new();
+        for desc in self.attachments.iter() {
+            if !names.insert(desc.name) {
+                return Err(RenderGraphError::DuplicateAttachment(desc.name));
+            }
+        }
+
+        let mut names = HashSet::new();
+        let mut written = HashSet::new();
+
+        for pass in self.passes.iter() {
+            if !names.insert(pass.name) {
+                return Err(RenderGraphError::DuplicatePass(pass.name));
+            }
+
+            for &(attachment, _) in pass.inputs.iter() {
+                self.find_attachment(pass.name, attachment)?;
+
+                if !written.contains(attachment) {
+                    return Err(RenderGraphError::NotWritten {
+                        pass: pass.name,
+                        attachment,
+                    });
+                }
+            }
+
+            for &(_, attachment) in pass.outputs.iter() {
+                let desc = self.find_attachment(pass.name, attachment)?;
+
+                if !matches!(desc.format, AttachmentFormat::Color(_)) {
+                    return Err(RenderGraphError::WrongFormat {
+                        pass: pass.name,
+                        attachment,
+                    });
+                }
+            }
+
+            if let Some(attachment) = pass.depth {
+                let desc = self.find_attachment(pass.name, attachment)?;
+
+                if !matches!(desc.format, AttachmentFormat::Depth(_)) {
+                    return Err(RenderGraphError::WrongFormat {
+                        pass: pass.name,
+                        attachment,
+                    });
+                }
+            }
+
+            match (pass.is_final, pass.written().next().is_some()) {
+                (true, true) => return Err(RenderGraphError::FinalWithOutputs(pass.name)),
+                (false, false) => return Err(RenderGraphError::NoOutputs(pass.name)),
+                _ => {}
+            }
+
+            if pass.is_final && !matches!(pass.kind, PassKind::Fullscreen(_)) {
+                return Err(RenderGraphError::GeometryToWindow(pass.name));
+            }
+
+            written.extend(pass.written());
+        }
+
+        Ok(())
+    }
+}
+
+/// Frame buffer of a pass drawing to attachments.
+pub enum PassFrameBuffer<'a> {
+    Simple(SimpleFrameBuffer<'a>),
+    Multi(MultiOutputFrameBuffer<'a>),
+}
+
+macro_rules! delegate {
+    ($self:ident, $fb:ident => $call:expr) => {
+        match $self {
+            PassFrameBuffer::Simple($fb) => $call,
+            PassFrameBuffer::Multi($fb) => $call,
+        }
+    };
+}
+
+impl Surface for PassFrameBuffer<'_> {
+    fn clear(
+        &mut self,
+        rect: Option<&Rect>,
+        color: Option<(f32, f32, f32, f32)>,
+        color_srgb: bool,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        delegate!(self, fb => fb.clear(rect, color, color_srgb, depth, stencil))
+    }
+
+    fn get_dimensions(&self) -> (u32, u32) {
+        delegate!(self, fb => fb.get_dimensions())
+    }
+
+    fn get_depth_buffer_bits(&self) -> Option<u16> {
+        delegate!(self, fb => fb.get_depth_buffer_bits())
+    }
+
+    fn get_stencil_buffer_bits(&self) -> Option<u16> {
+        delegate!(self, fb => fb.get_stencil_buffer_bits())
+    }
+
+    fn draw<'a, 'b, V, I, U>(
+        &mut self,
+        vertices: V,
+        indices: I,
+        program: &Program,
+        uniforms: &U,
+        draw_parameters: &DrawParameters<'_>,
+    ) -> Result<(), DrawError>
+    where
+        V: MultiVerticesSource<'b>,
+        I: Into<IndicesSource<'a>>,
+        U: Uniforms,
+    {
+        delegate!(self, fb => fb.draw(vertices, indices, program, uniforms, draw_parameters))
+    }
+
+    fn blit_buffers_from_frame(
+        &self,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        delegate!(self, fb => fb.blit_buffers_from_frame(source_rect, target_rect, filter, mask))
+    }
+
+    fn blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &SimpleFrameBuffer<'_>,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        delegate!(self, fb => fb.blit_buffers_from_simple_framebuffer(
+            source, source_rect, target_rect, filter, mask,
+        ))
+    }
+
+    fn blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &MultiOutputFrameBuffer<'_>,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        delegate!(self, fb => fb.blit_buffers_from_multioutput_framebuffer(
+            source, source_rect, target_rect, filter, mask,
+        ))
+    }
+
+    fn blit_color<S: Surface>(
+        &self,
+        source_rect: &Rect,
+        target: &S,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+    ) {
+        delegate!(self, fb => fb.blit_color(source_rect, target, target_rect, filter))
+    }
+}
+
+/// Uniforms of a pass: shared ones, pass flags and input attachments.
+pub struct PassUniforms<'u, U> {
+    shared: &'u U,
+    flags: &'u [(&'static str, bool)],
+    ints: &'u [(&'static str, i32)],
+    inputs: Vec<(&'u str, &'u Attachment)>,
+}
+
+impl<U: Uniforms> Uniforms for PassUniforms<'_, U> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        self.shared.visit_values(&mut visit);
+
+        for &(name, value) in self.flags {
+            visit(name, UniformValue::Bool(value));
+        }
+
+        for &(name, value) in self.ints {
+            visit(name, UniformValue::SignedInt(value));
+        }
+
+        for &(name, attachment) in self.inputs.iter() {
+            visit(name, attachment.as_uniform());
+        }
+    }
+}
+
+/// Uniforms of both `A` and `B`.
+pub struct ChainedUniforms<A, B>(pub A, pub B);
+
+impl<A: Uniforms, B: Uniforms> Uniforms for ChainedUniforms<A, B> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        self.0.visit_values(&mut visit);
+        self.1.visit_values(&mut visit);
+    }
+}
+
+/// Pass with its runtime resources.
+struct Pass {
+    desc: PassDesc,
+    shader: Option<Handle<Shader>>,
+    /// Uniform names of the inputs.
+    input_names: Vec<String>,
+}
+
+impl Pass {
+    fn new(desc: PassDesc, assets: &mut AssetManager) -> Self {
+        let shader = match desc.kind {
+            PassKind::Geometry | PassKind::Shadow(_) => None,
+            PassKind::Fullscreen(name) => Some(assets.load((FULLSCREEN_VERTEX_SHADER, name))),
+        };
+
+        let input_names = desc
+            .inputs
+            .iter()
+            .map(|(_, name)| format!("{name}_texture"))
+            .collect();
+
+        Self {
+            desc,
+            shader,
+            input_names,
+        }
+    }
+}
+
+/// Passes drawn in order with attachments allocated for the window size.
+pub struct RenderGraph {
+    // Frame buffers borrow attachments so they should be dropped first.
+    frame_buffers: Vec<Option<PassFrameBuffer<'static>>>,
+    attachments: HashMap<&'static str, Attachment>,
+    attachment_descs: Vec<AttachmentDesc>,
+    passes: Vec<Pass>,
+    quad: QuadDrawResources,
+}
+
+impl RenderGraph {
+    /// Constructs graph from validated `desc` and allocates its attachments.
+    /// Shaders of fullscreen passes are loaded by `assets`.
+    pub fn new(
+        facade: &dyn Facade,
+        window_size: UInt2,
+        desc: RenderGraphDesc,
+        quad: QuadDrawResources,
+        assets: &mut AssetManager,
+    ) -> Result<Self, RenderGraphError> {
+        desc.validate()?;
+
+        let passes = desc
+            .passes
+            .into_iter()
+            .map(|pass| Pass::new(pass, assets))
+            .collect();
+
+        let mut result = Self {
+            frame_buffers: vec![],
+            attachments: HashMap::new(),
+            attachment_descs: desc.attachments,
+            passes,
+            quad,
+        };
+
+        result.on_window_resize(facade, window_size)?;
+
+        Ok(result)
+    }
+
+    /// Reallocates attachments for the new window size.
+    pub fn on_window_resize(
+        &mut self,
+        facade: &dyn Facade,
+        new_size: UInt2,
+    ) -> Result<(), RenderGraphError> {
+        self.frame_buffers.clear();
+
+        self.attachments = self
+            .attachment_descs
+            .iter()
+            .map(|desc| Ok((desc.name, Attachment::new(facade, desc, new_size)?)))
+            .collect::<Result<_, TextureCreationError>>()?;
+
+        // * Safety:
+        // * Safe, because attachments are not modified while frame buffers live.
+        // * On reallocation frame buffers are dropped first and then made again.
+        let attachments = &self.attachments as *const HashMap<&'static str, Attachment>;
+        let attachments = unsafe { attachments.as_ref().unwrap_unchecked() };
+
+        self.frame_buffers = self
+            .passes
+            .iter()
+            .map(|pass| Self::make_frame_buffer(&pass.desc, attachments, facade))
+            .collect::<Result<_, _>>()?;
+
+        Ok(())
+    }
+
+    fn make_frame_buffer<'a>(
+        desc: &PassDesc,
+        attachments: &'a HashMap<&'static str, Attachment>,
+        facade: &dyn Facade,
+    ) -> Result<Option<PassFrameBuffer<'a>>, ValidationError> {
+        if desc.is_final {
+            return Ok(None);
+        }
+
+        let colors = desc
+            .outputs
+            .iter()
+            .filter_map(|&(output, name)| match &attachments[name] {
+                Attachment::Color(texture) => Some((output, texture)),
+                Attachment::Depth(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let depth = desc.depth.and_then(|name| match &attachments[name] {
+            Attachment::Depth(texture) => Some(texture),
+            Attachment::Color(_) => None,
+        });
+
+        let frame_buffer = match (colors.is_empty(), depth) {
+            (true, Some(depth)) => {
+                PassFrameBuffer::Simple(SimpleFrameBuffer::depth_only(facade, depth)?)
+            }
+            (_, Some(depth)) => PassFrameBuffer::Multi(MultiOutputFrameBuffer::with_depth_buffer(
+                facade, colors, depth,
+            )?),
+            (_, None) => PassFrameBuffer::Multi(MultiOutputFrameBuffer::new(facade, colors)?),
+        };
+
+        Ok(Some(frame_buffer))
+    }
+
+    pub fn attachment(&self, name: &str) -> Option<&Attachment> {
+        self.attachments.get(name)
+    }
+
+    /// Checks if pass named `name` is enabled. Gives `false` if there's no such pass.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.passes
+            .iter()
+            .any(|pass| pass.desc.name == name && pass.desc.is_enabled)
+    }
+
+    /// Enables or disables pass named `name`. Gives `false` if there's no such pass.
+    pub fn set_enabled(&mut self, name: &str, is_enabled: bool) -> bool {
+        match self.passes.iter_mut().find(|pass| pass.desc.name == name) {
+            Some(pass) => {
+                pass.desc.is_enabled = is_enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draws enabled passes in order. Geometry and shadow passes are drawn by `draw_geometry`
+    /// to their frame buffer with pass uniforms, final passes are drawn to `target`.
+    pub fn execute<U: Uniforms>(
+        &mut self,
+        target: &mut Frame,
+        uniforms: &U,
+        mut draw_geometry: impl FnMut(PassKind, &mut PassFrameBuffer<'static>, &PassUniforms<'_, U>),
+    ) -> Result<(), DrawError> {
+        for (pass, frame_buffer) in self.passes.iter().zip(self.frame_buffers.iter_mut()) {
+            if !pass.desc.is_enabled {
+                continue;
+            }
+
+            let pass_uniforms = PassUniforms {
+                shared: uniforms,
+                flags: &pass.desc.flags,
+                ints: &pass.desc.ints,
+                inputs: pass
+                    .input_names
+                    .iter()
+                    .zip(pass.desc.inputs.iter())
+                    .map(|(uniform_name, &(name, _))| {
+                        (uniform_name.as_str(), &self.attachments[name])
+                    })
+                    .collect(),
+            };
+
+            if let Some(frame_buffer) = frame_buffer {
+                frame_buffer.clear_all(CLEAR_COLOR, CLEAR_DEPTH, CLEAR_STENCIL);
+            }
+
+            match (&pass.shader, frame_buffer) {
+                (None, Some(frame_buffer)) => {
+                    draw_geometry(pass.desc.kind, frame_buffer, &pass_uniforms)
+                }
+
+                (Some(shader), Some(frame_buffer)) => frame_buffer.draw(
+                    &self.quad.vertices,
+                    &self.quad.indices,
+                    &shader.get().program,
+                    &pass_uniforms,
+                    &Default::default(),
+                )?,
+
+                (Some(shader), None) => target.draw(
+                    &self.quad.vertices,
+                    &self.quad.indices,
+                    &shader.get().program,
+                    &pass_uniforms,
+                    &Default::default(),
+                )?,
+
+                (None, None) => unreachable!("geometry pass can not draw to the window"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spawns window with pass switches.
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Render graph").build(|| {
+            for pass in self.passes.iter_mut() {
+                ui.checkbox(pass.desc.name, &mut pass.desc.is_enabled);
+            }
+        });
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RenderGraphError {
+    #[error("attachment {0} is declared twice")]
+    DuplicateAttachment(&'static str),
+
+    #[error("pass {0} is declared twice")]
+    DuplicatePass(&'static str),
+
+    #[error("pass {pass} refers to unknown attachment {attachment}")]
+    UnknownAttachment {
+        pass: &'static str,
+        attachment: &'static str,
+    },
+
+    #[error("attachment {attachment} has wrong format for its use in pass {pass}")]
+    WrongFormat {
+        pass: &'static str,
+        attachment: &'static str,
+    },
+
+    #[error("pass {pass} reads attachment {attachment} before any pass writes it")]
+    NotWritten {
+        pass: &'static str,
+        attachment: &'static str,
+    },
+
+    #[error("pass {0} writes nothing")]
+    NoOutputs(&'static str),
+
+    #[error("pass {0} draws to the window so it can not write attachments")]
+    FinalWithOutputs(&'static str),
+
+    #[error("geometry or shadow pass {0} can not draw to the window")]
+    GeometryToWindow(&'static str),
+
+    #[error("failed to create attachment: {0}")]
+    TextureCreation(#[from] TextureCreationError),
+
+    #[error("failed to validate frame buffer: {0}")]
+    Validation(#[from] ValidationError),
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::graphics::pipeline};
+
+    #[test]
+    fn deferred_pipeline_is_valid() {
+        let desc = pipeline::deferred();
+        desc.validate().unwrap();
+
+        for (cascade, &name) in pipeline::shadow_passes().iter().enumerate() {
+            let pass = desc.passes.iter().find(|pass| pass.name == name).unwrap();
+            assert_eq!(pass.kind, PassKind::Shadow(cascade));
+        }
+    }
+
+    #[test]
+    fn only_fullscreen_passes_draw_to_window() {
+        let desc = RenderGraphDesc::new().pass(PassDesc::shadow("shadow", 0).to_window());
+
+        assert!(matches!(
+            desc.validate(),
+            Err(RenderGraphError::GeometryToWindow("shadow")),
+        ));
+    }
+
+    #[test]
+    fn passes_refer_to_declared_attachments() {
+        let desc = RenderGraphDesc::new()
+            .attachment(AttachmentDesc::depth("depth", DepthFormat::F32))
+            .pass(PassDesc::geometry("scene").with_output("out_color", "color"));
+
+        assert!(matches!(
+            desc.validate(),
+            Err(RenderGraphError::UnknownAttachment {
+                pass: "scene",
+                attachment: "color",
+            }),
+        ));
+
+        let desc = desc
+            .attachment(AttachmentDesc::color(
+                "color",
+                UncompressedFloatFormat::F16F16F16F16,
+            ))
+            .pass(PassDesc::geometry("depth_as_color").with_output("out_color", "depth"));
+
+        assert!(matches!(
+            desc.validate(),
+            Err(RenderGraphError::WrongFormat {
+                pass: "depth_as_color",
+                attachment: "depth",
+            }),
+        ));
+    }
+
+    #[test]
+    fn inputs_are_written_before_read() {
+        let desc = RenderGraphDesc::new()
+            .attachment(AttachmentDesc::color(
+                "color",
+                UncompressedFloatFormat::F16F16F16F16,
+            ))
+            .pass(
+                PassDesc::fullscreen("present", "present")
+                    .with_input("color")
+                    .to_window(),
+            )
+            .pass(PassDesc::geometry("scene").with_output("out_color", "color"));
+
+        assert!(matches!(
+            desc.validate(),
+            Err(RenderGraphError::NotWritten {
+                pass: "present",
+                attachment: "color",
+            }),
+        ));
+
+        let mut desc = desc;
+        desc.passes.reverse();
+        desc.validate().unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+//!
+//! This module contains [`Shader`] struct.
+//!
+
+pub mod preprocessor;
+
+use {
+    crate::app::utils::{
+        cfg::{
+            self,
+            shader::{DIRECTORY, FRAGMENT_FILE_EXTENTION, VERTEX_FILE_EXTENTION},
+        },
+        graphics::material::MAX_MATERIALS,
+        logger,
+    },
+    derive_deref_rs::Deref,
+    glium::ProgramCreationError,
+    itertools::Itertools,
+    preprocessor::{PreprocessError, Preprocessed, Preprocessor},
+    std::{fs, io, path::PathBuf},
+    thiserror::Error,
+};
+
+/// Makes preprocessor reading shader files from [`DIRECTORY`]
+/// with constants shared with Rust code defined.
+pub fn preprocessor() -> Preprocessor<impl Fn(&str) -> io::Result<String>> {
+    Preprocessor::new(|name: &str| fs::read_to_string(format!("{DIRECTORY}{name}")))
+        .define("Z_NEAR", cfg::camera::default::NEAR_PLANE)
+        .define("Z_FAR", cfg::camera::default::FAR_PLANE)
+        .define("CLEAR_COLOR", cfg::shader::CLEAR_COLOR)
+        .define("MAX_CASCADES", cfg::shadow::MAX_CASCADES)
+        .define("MAX_MATERIALS", MAX_MATERIALS)
+}
+
+/// Shader struct is container for shader source code.
+#[derive(Debug, Deref)]
+pub struct Shader {
+    pub vertex_src: String,
+    pub fragment_src: String,
+
+    #[deref]
+    pub program: glium::Program,
+}
+
+impl Shader {
+    /// Returns new Shader object that contains preprocessed shader source code from their path.
+    /// It adds [`DIRECTORY`] before the name and special extention (a.g. `.vert` for vertex) after.
+    pub fn new(
+        vertex_name: &str,
+        fragment_name: &str,
+        display: &dyn glium::backend::Facade,
+    ) -> Result<Self, ShaderError> {
+        let _work_guard = logger::work("shader loader", format!("{vertex_name}, {fragment_name}."));
+
+        Self::preprocess(vertex_name, fragment_name)?.compile(display)
+    }
+
+    /// Reads and preprocesses shader files without compiling them,
+    /// so it can be done off the thread owning GL context.
+    pub fn preprocess(
+        vertex_name: &str,
+        fragment_name: &str,
+    ) -> Result<ShaderSources, ShaderError> {
+        let preprocessor = preprocessor();
+
+        Ok(ShaderSources {
+            vertex: preprocessor.process(&format!("{vertex_name}.{VERTEX_FILE_EXTENTION}"))?,
+            fragment: preprocessor
+                .process(&format!("{fragment_name}.{FRAGMENT_FILE_EXTENTION}"))?,
+        })
+    }
+
+    pub fn from_source(
+        vertex_src: String,
+        fragment_src: String,
+        display: &dyn glium::backend::Facade,
+    ) -> Result<Self, ShaderError> {
+        let program =
+            glium::Program::from_source(display, vertex_src.as_str(), fragment_src.as_str(), None)?;
+
+        Ok(Shader {
+            vertex_src,
+            fragment_src,
+            program,
+        })
+    }
+}
+
+/// Preprocessed sources of vertex and fragment shaders.
+#[derive(Clone, Debug)]
+pub struct ShaderSources {
+    pub vertex: Preprocessed,
+    pub fragment: Preprocessed,
+}
+
+impl ShaderSources {
+    /// Paths of all files the shaders are made of, including included ones.
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.vertex
+            .files
+            .iter()
+            .chain(&self.fragment.files)
+            .unique()
+            .map(|name| PathBuf::from(format!("{DIRECTORY}{name}")))
+    }
+
+    /// Compiles shader program.
+    pub fn compile(&self, display: &dyn glium::backend::Facade) -> Result<Shader, ShaderError> {
+        Shader::from_source(
+            self.vertex.source.clone(),
+            self.fragment.source.clone(),
+            display,
+        )
+        .map_err(|err| match err {
+            ShaderError::ProgramCreation(err) => ShaderError::Compilation {
+                err,
+                vertex_files: self.vertex.file_map(),
+                fragment_files: self.fragment.file_map(),
+            },
+            err => err,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ShaderError {
+    #[error("failed to create gl shader program: {0}")]
+    ProgramCreation(#[from] ProgramCreationError),
+
+    /// Lines in errors are given as `source_string_number(line)`.
+    #[error(
+        "failed to compile shader: {err}\n\
+         vertex source strings: {vertex_files}\n\
+         fragment source strings: {fragment_files}"
+    )]
+    Compilation {
+        err: ProgramCreationError,
+        vertex_files: String,
+        fragment_files: String,
+    },
+
+    #[error("failed to preprocess shader: {0}")]
+    Preprocess(#[from] PreprocessError),
+}
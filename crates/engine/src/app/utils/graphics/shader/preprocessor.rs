@@ -0,0 +1,331 @@
+//!
+//! GLSL preprocessor run before shaders are compiled.
+//!
+//! Supports `#include "name"` of shared snippets and injects `#define`s of
+//! Rust-side constants right after the `#version` directive. `#line` directives
+//! are emitted so compiler errors point to lines of the original files, the
+//! source string number of the error is the index into [`Preprocessed::files`].
+//!
+
+use {
+    std::{fmt, io},
+    thiserror::Error,
+};
+
+/// Constant which can be injected into GLSL as a `#define`.
+pub trait GlslConst {
+    fn to_glsl(&self) -> String;
+}
+
+impl GlslConst for f32 {
+    fn to_glsl(&self) -> String {
+        // `Debug` always prints the decimal point, so the literal stays `float`.
+        format!("{self:?}")
+    }
+}
+
+impl GlslConst for i32 {
+    fn to_glsl(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl GlslConst for usize {
+    fn to_glsl(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl GlslConst for (f32, f32, f32, f32) {
+    fn to_glsl(&self) -> String {
+        let (x, y, z, w) = self;
+        format!(
+            "vec4({}, {}, {}, {})",
+            x.to_glsl(),
+            y.to_glsl(),
+            z.to_glsl(),
+            w.to_glsl(),
+        )
+    }
+}
+
+/// Preprocessed shader source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preprocessed {
+    pub source: String,
+    /// Names of files source consists of by their source string number.
+    pub files: Vec<String>,
+}
+
+impl Preprocessed {
+    /// Lists source string numbers of files to decode compiler errors.
+    pub fn file_map(&self) -> String {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| format!("{idx} is {name}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// GLSL preprocessor reading files with a user-given function.
+pub struct Preprocessor<F> {
+    read_file: F,
+    defines: Vec<(String, String)>,
+}
+
+impl<F> fmt::Debug for Preprocessor<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Preprocessor")
+            .field("defines", &self.defines)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: Fn(&str) -> io::Result<String>> Preprocessor<F> {
+    /// Constructs preprocessor reading files by name with `read_file`.
+    pub fn new(read_file: F) -> Self {
+        Self {
+            read_file,
+            defines: vec![],
+        }
+    }
+
+    /// Adds `#define` of `name` to `value` to processed shaders.
+    pub fn define(mut self, name: impl Into<String>, value: impl GlslConst) -> Self {
+        self.defines.push((name.into(), value.to_glsl()));
+        self
+    }
+
+    /// Processes shader file `name` with its includes.
+    pub fn process(&self, name: &str) -> Result<Preprocessed, PreprocessError> {
+        let source = (self.read_file)(name).map_err(|io_err| PreprocessError::Read {
+            name: name.into(),
+            included_from: None,
+            io_err,
+        })?;
+
+        let (version, rest) = source.split_once('\n').unwrap_or((&source, ""));
+
+        if !version.trim_start().starts_with("#version") {
+            return Err(PreprocessError::MissingVersion { file: name.into() });
+        }
+
+        let mut result = Preprocessed {
+            source: format!("{version}\n"),
+            files: vec![name.into()],
+        };
+
+        for (define, value) in &self.defines {
+            result.source += &format!("#define {define} {value}\n");
+        }
+
+        result.source += "#line 2 0\n";
+
+        let mut stack = vec![name.to_owned()];
+        self.process_lines(rest, 2, 0, &mut stack, &mut result)?;
+
+        Ok(result)
+    }
+
+    /// Appends `source` starting at line `first_line` of file number `file_idx`
+    /// to `result` with includes expanded.
+    /// `stack` holds names of files being included to detect cycles.
+    fn process_lines(
+        &self,
+        source: &str,
+        first_line: usize,
+        file_idx: usize,
+        stack: &mut Vec<String>,
+        result: &mut Preprocessed,
+    ) -> Result<(), PreprocessError> {
+        for (line_number, line) in (first_line..).zip(source.lines()) {
+            let Some(directive) = line.trim_start().strip_prefix("#include") else {
+                result.source += line;
+                result.source.push('\n');
+                continue;
+            };
+
+            let location = Location {
+                file: stack.last().cloned().unwrap_or_default(),
+                line: line_number,
+            };
+
+            let name = directive
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| PreprocessError::MalformedInclude {
+                    location: location.clone(),
+                })?;
+
+            if stack.iter().any(|file| file == name) {
+                return Err(PreprocessError::RecursiveInclude {
+                    location,
+                    name: name.into(),
+                });
+            }
+
+            // Every file is included once, like with `#pragma once`.
+            if !result.files.iter().any(|file| file == name) {
+                let source = (self.read_file)(name).map_err(|io_err| PreprocessError::Read {
+                    name: name.into(),
+                    included_from: Some(location),
+                    io_err,
+                })?;
+
+                let include_idx = result.files.len();
+                result.files.push(name.into());
+                result.source += &format!("#line 1 {include_idx}\n");
+
+                stack.push(name.into());
+                self.process_lines(&source, 1, include_idx, stack, result)?;
+                stack.pop();
+            }
+
+            result.source += &format!("#line {} {file_idx}\n", line_number + 1);
+        }
+
+        Ok(())
+    }
+}
+
+/// Line of a shader file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PreprocessError {
+    #[error("failed to read shader file {name}{}: {io_err}", match included_from {
+        Some(location) => format!(" included from {location}"),
+        None => String::new(),
+    })]
+    Read {
+        name: String,
+        included_from: Option<Location>,
+        io_err: io::Error,
+    },
+
+    #[error("{file}:1: shader should start with #version directive")]
+    MissingVersion { file: String },
+
+    #[error("{location}: #include should be followed by a quoted file name")]
+    MalformedInclude { location: Location },
+
+    #[error("{location}: {name} includes itself")]
+    RecursiveInclude { location: Location, name: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashMap};
+
+    fn preprocessor(
+        files: &[(&'static str, &'static str)],
+    ) -> Preprocessor<impl Fn(&str) -> io::Result<String>> {
+        let files = files.iter().copied().collect::<HashMap<_, _>>();
+
+        Preprocessor::new(move |name: &str| {
+            files
+                .get(name)
+                .map(|source| source.to_string())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        })
+    }
+
+    #[test]
+    fn defines_are_injected_after_version() {
+        let processed = preprocessor(&[("main.frag", "#version 440\nvoid main() {}\n")])
+            .define("Z_NEAR", 0.5_f32)
+            .define("COUNT", 4_usize)
+            .define("CLEAR_COLOR", (0.0_f32, 0.5, 1.0, 1.0))
+            .process("main.frag")
+            .unwrap();
+
+        assert_eq!(
+            processed.source,
+            "#version 440\n\
+             #define Z_NEAR 0.5\n\
+             #define COUNT 4\n\
+             #define CLEAR_COLOR vec4(0.0, 0.5, 1.0, 1.0)\n\
+             #line 2 0\n\
+             void main() {}\n",
+        );
+        assert_eq!(processed.files, vec!["main.frag"]);
+    }
+
+    #[test]
+    fn includes_are_expanded_once_with_line_mapping() {
+        let processed = preprocessor(&[
+            (
+                "main.frag",
+                "#version 440\n#include \"a.glsl\"\n#include \"b.glsl\"\nvoid main() {}\n",
+            ),
+            ("a.glsl", "float a;\n"),
+            ("b.glsl", "  #include \"a.glsl\"\nfloat b;\n"),
+        ])
+        .process("main.frag")
+        .unwrap();
+
+        assert_eq!(
+            processed.source,
+            "#version 440\n\
+             #line 2 0\n\
+             #line 1 1\n\
+             float a;\n\
+             #line 3 0\n\
+             #line 1 2\n\
+             #line 2 2\n\
+             float b;\n\
+             #line 4 0\n\
+             void main() {}\n",
+        );
+        assert_eq!(processed.files, vec!["main.frag", "a.glsl", "b.glsl"]);
+        assert_eq!(
+            processed.file_map(),
+            "0 is main.frag, 1 is a.glsl, 2 is b.glsl"
+        );
+    }
+
+    #[test]
+    fn errors_point_to_file_and_line() {
+        let files = [
+            ("main.frag", "#version 440\n\n#include \"a.glsl\"\n"),
+            ("a.glsl", "float a;\n#include \"a.glsl\"\n"),
+            ("broken.frag", "#version 440\n#include a.glsl\n"),
+            ("missing.frag", "#version 440\n#include \"none.glsl\"\n"),
+            ("no_version.frag", "void main() {}\n"),
+        ];
+        let preprocessor = preprocessor(&files);
+
+        let err = preprocessor.process("main.frag").unwrap_err();
+        assert_eq!(err.to_string(), "a.glsl:2: a.glsl includes itself");
+
+        let err = preprocessor.process("broken.frag").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "broken.frag:2: #include should be followed by a quoted file name",
+        );
+
+        let err = preprocessor.process("missing.frag").unwrap_err();
+        assert!(matches!(
+            err,
+            PreprocessError::Read { ref name, included_from: Some(Location { ref file, line: 2 }), .. }
+                if name == "none.glsl" && file == "missing.frag"
+        ));
+
+        let err = preprocessor.process("no_version.frag").unwrap_err();
+        assert!(matches!(err, PreprocessError::MissingVersion { .. }));
+    }
+}
@@ -0,0 +1,306 @@
+//!
+//! Cascaded shadow maps for the sun.
+//!
+//! Camera view is split in depth into cascades, each one is covered by its own
+//! orthographic shadow map. Cascades are bounded by spheres, so their sizes do
+//! not change when the camera rotates, and moved by whole texels, so shadow
+//! edges do not shimmer when the camera moves.
+//!
+
+use {
+    crate::{app::utils::cfg::shadow as cfg, graphics::camera::Camera, prelude::*},
+    glium::uniforms::{DynamicUniforms, Uniforms},
+};
+
+/// Gives far view depths of `count` cascades between `near` and `far`.
+///
+/// Practical split scheme: `lambda` blends logarithmic splits (`1.0`)
+/// with uniform ones (`0.0`).
+pub fn split_distances(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let part = i as f32 / count as f32;
+            let log = near * (far / near).powf(part);
+            let uniform = near + (far - near) * part;
+
+            lambda * log + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// Gives light space axes orthogonal to `light_dir`, the same as view matrix ones.
+fn light_axes(light_dir: vec3) -> (vec3, vec3) {
+    let up = match light_dir.y.abs() < 0.99 {
+        true => vec3::new(0.0, 1.0, 0.0),
+        false => vec3::new(0.0, 0.0, 1.0),
+    };
+
+    let right = up.cross(light_dir).normalized();
+    let up = light_dir.cross(right);
+
+    (right, up)
+}
+
+/// Cascade covering some part of the camera view.
+#[derive(Clone, Copy, Debug)]
+pub struct Cascade {
+    /// Projection and view of the light.
+    pub light_space: mat4,
+    /// Size of shadow map texel in world space.
+    pub texel_size: f32,
+}
+
+impl Cascade {
+    /// Fits cascade of shadow map with `resolution` around `corners` looking in `light_dir`.
+    pub fn fit(corners: &[vec3], light_dir: vec3, resolution: u32) -> Self {
+        let light_dir = light_dir.normalized();
+
+        let center = corners
+            .iter()
+            .fold(vec3::zero(), |sum, &corner| sum + corner)
+            / corners.len() as f32;
+
+        // Rounded so float errors do not change the texel size.
+        let radius = corners
+            .iter()
+            .map(|&corner| (corner - center).len())
+            .fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        let texel_size = 2.0 * radius / resolution as f32;
+
+        let (right, up) = light_axes(light_dir);
+        let snap = |axis: vec3| {
+            let offset = center.dot(axis);
+            axis * ((offset / texel_size).round() * texel_size - offset)
+        };
+        let center = center + snap(right) + snap(up);
+
+        let eye = center - light_dir * (radius + cfg::CASTER_DISTANCE);
+        let view = mat4::look_at_lh(eye, center, up);
+        let proj = mat4::orthographic_lh(
+            2.0 * radius,
+            2.0 * radius,
+            0.0,
+            2.0 * radius + cfg::CASTER_DISTANCE,
+        );
+
+        Self {
+            light_space: view.multiply(&proj),
+            texel_size,
+        }
+    }
+}
+
+/// Sun shadow cascades for the current frame.
+#[derive(Debug)]
+pub struct ShadowCascades {
+    resolution: u32,
+    count: i32,
+    matrices: Vec<[[f32; 4]; 4]>,
+    splits: Vec<f32>,
+    texel_sizes: Vec<f32>,
+    /// Uniform names of array elements.
+    names: Vec<[String; 3]>,
+}
+
+impl ShadowCascades {
+    pub fn new(count: usize, resolution: u32) -> Self {
+        assert!(
+            count <= cfg::MAX_CASCADES,
+            "there can be at most {} cascades",
+            cfg::MAX_CASCADES,
+        );
+
+        Self {
+            resolution,
+            count: count as i32,
+            matrices: vec![mat4::identity().as_2d_array(); count],
+            splits: vec![0.0; count],
+            texel_sizes: vec![0.0; count],
+            names: (0..count)
+                .map(|i| {
+                    [
+                        format!("shadow_matrices[{i}]"),
+                        format!("cascade_splits[{i}]"),
+                        format!("cascade_texel_sizes[{i}]"),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Fits cascades to the view of `cam` lit from `light_dir`.
+    pub fn update(&mut self, cam: &Camera, light_dir: vec3) {
+        let near = cam.near_plane_dist;
+        let far = cam.far_plane_dist.min(cfg::DISTANCE);
+
+        self.splits = split_distances(near, far, self.matrices.len(), cfg::SPLIT_LAMBDA);
+
+        let starts = std::iter::once(near).chain(self.splits.iter().copied());
+
+        for (i, (start, &end)) in starts.zip(self.splits.iter()).enumerate() {
+            let corners = cam.frustum_slice_corners(start, end);
+            let cascade = Cascade::fit(&corners, light_dir, self.resolution);
+
+            self.matrices[i] = cascade.light_space.as_2d_array();
+            self.texel_sizes[i] = cascade.texel_size;
+        }
+    }
+
+    /// Checks if `aabb` may cast shadows into cascade `idx`, i.e. it intersects the cascade
+    /// volume extended toward the light by [`CASTER_DISTANCE`][cfg::CASTER_DISTANCE].
+    pub fn is_aabb_in_cascade(&self, idx: usize, aabb: Aabb) -> bool {
+        let Some(&matrix) = self.matrices.get(idx) else {
+            return false;
+        };
+
+        let light_space = mat4::from_2d_array(matrix);
+        let corners = aabb.as_vertex_array().map(|corner| light_space * corner);
+
+        let lo = corners
+            .iter()
+            .fold(vec3::all(f32::INFINITY), |lo, &corner| {
+                vec3::new(lo.x.min(corner.x), lo.y.min(corner.y), lo.z.min(corner.z))
+            });
+        let hi = corners
+            .iter()
+            .fold(vec3::all(f32::NEG_INFINITY), |hi, &corner| {
+                vec3::new(hi.x.max(corner.x), hi.y.max(corner.y), hi.z.max(corner.z))
+            });
+
+        // Light space is the clip space of orthographic projection.
+        let volume = Aabb::from_float3(vec3::new(-1.0, -1.0, 0.0), vec3::all(1.0));
+
+        Aabb::from_float3(lo, hi).intersects(volume)
+    }
+
+    /// Gives `shadow_matrices`, `cascade_splits`, `cascade_texel_sizes`
+    /// and `cascade_count` uniforms.
+    pub fn uniforms(&self) -> impl Uniforms + '_ {
+        let mut uniforms = DynamicUniforms::new();
+        uniforms.add("cascade_count", &self.count);
+
+        for (i, [matrix, split, texel_size]) in self.names.iter().enumerate() {
+            uniforms.add(matrix, &self.matrices[i]);
+            uniforms.add(split, &self.splits[i]);
+            uniforms.add(texel_size, &self.texel_sizes[i]);
+        }
+
+        uniforms
+    }
+}
+
+impl Default for ShadowCascades {
+    fn default() -> Self {
+        Self::new(cfg::N_CASCADES, cfg::RESOLUTION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Transforms `pos` with row-major `matrix` as the shaders do.
+    fn transform(matrix: mat4, pos: vec3) -> [f32; 3] {
+        let m = matrix.as_2d_array();
+        let p = [pos.x, pos.y, pos.z, 1.0];
+
+        let clip = [0, 1, 2, 3].map(|col| (0..4).map(|row| p[row] * m[row][col]).sum::<f32>());
+        [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]]
+    }
+
+    #[test]
+    fn splits_blend_log_and_uniform() {
+        let uniform = split_distances(1.0, 100.0, 4, 0.0);
+        assert_eq!(uniform, vec![25.75, 50.5, 75.25, 100.0]);
+
+        let log = split_distances(1.0, 10_000.0, 4, 1.0);
+        for (split, expected) in log.iter().zip([10.0, 100.0, 1_000.0, 10_000.0]) {
+            assert!((split - expected).abs() / expected < 1e-4);
+        }
+
+        let practical = split_distances(0.5, 512.0, 4, 0.8);
+        assert!(practical.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!((practical[3] - 512.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cascade_contains_corners() {
+        let mut cam = Camera::new();
+        cam.set_position(10.0, 20.0, -30.0);
+        cam.set_rotation(0.0, 0.3, 1.2);
+
+        let light_dir = vec3::new(0.3, -1.0, 0.2);
+        let corners = cam.frustum_slice_corners(5.0, 40.0);
+        let cascade = Cascade::fit(&corners, light_dir, 1024);
+
+        for corner in corners {
+            let [x, y, z] = transform(cascade.light_space, corner);
+            assert!(x.abs() <= 1.0 && y.abs() <= 1.0, "{corner:?} is outside");
+            assert!((0.0..=1.0).contains(&z), "{corner:?} is outside");
+        }
+
+        let caster = corners[0] - light_dir.normalized() * (0.5 * cfg::CASTER_DISTANCE);
+        let [.., z] = transform(cascade.light_space, caster);
+        assert!((0.0..=1.0).contains(&z));
+    }
+
+    #[test]
+    fn casters_are_culled_by_cascade_volume() {
+        let mut cam = Camera::new();
+        cam.set_position(0.0, 0.0, 0.0);
+
+        let light_dir = vec3::new(0.0, -1.0, 0.0);
+        let mut cascades = ShadowCascades::new(1, 1024);
+        cascades.update(&cam, light_dir);
+
+        let cube =
+            |center: vec3| Aabb::from_float3(center - vec3::all(0.5), center + vec3::all(0.5));
+
+        let corners = cam.frustum_slice_corners(cam.near_plane_dist, cascades.splits[0]);
+        let inside = corners
+            .iter()
+            .fold(vec3::zero(), |sum, &corner| sum + corner)
+            / corners.len() as f32;
+        assert!(cascades.is_aabb_in_cascade(0, cube(inside)));
+
+        // Caster above the view is out of camera frustum but its shadow falls into it.
+        let caster = inside + vec3::new(0.0, 0.5 * cfg::CASTER_DISTANCE, 0.0);
+        assert!(cascades.is_aabb_in_cascade(0, cube(caster)));
+
+        let beyond_caster_distance = inside + vec3::new(0.0, 4.0 * cfg::CASTER_DISTANCE, 0.0);
+        assert!(!cascades.is_aabb_in_cascade(0, cube(beyond_caster_distance)));
+
+        let aside = inside + vec3::new(4.0 * cfg::DISTANCE, 0.0, 0.0);
+        assert!(!cascades.is_aabb_in_cascade(0, cube(aside)));
+
+        assert!(!cascades.is_aabb_in_cascade(1, cube(inside)));
+    }
+
+    #[test]
+    fn cascade_moves_by_whole_texels() {
+        let light_dir = vec3::new(0.4, -1.0, -0.3);
+        let resolution = 512;
+
+        let corners = Camera::new().frustum_slice_corners(1.0, 50.0);
+        let origin = transform(
+            Cascade::fit(&corners, light_dir, resolution).light_space,
+            vec3::zero(),
+        );
+
+        for shift in [0.013, 0.37, 1.9] {
+            let moved = corners.map(|corner| corner + vec3::new(shift, 0.5 * shift, 0.0));
+            let cascade = Cascade::fit(&moved, light_dir, resolution);
+            let moved_origin = transform(cascade.light_space, vec3::zero());
+
+            for axis in 0..2 {
+                let texels = (moved_origin[axis] - origin[axis]) * resolution as f32 / 2.0;
+                assert!(
+                    (texels - texels.round()).abs() < 0.01,
+                    "moved by {texels} texels"
+                );
+            }
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use {
-    crate::prelude::*,
+    crate::{app::utils::cfg::texture::ANISOTROPY, prelude::*, terrain::voxel::atlas},
     glium::{
         backend::Facade,
-        texture::{MipmapsOption, RawImage2d, Texture2d},
+        texture::{MipmapsOption, RawImage2d, Texture2d, Texture2dArray},
         uniforms::SamplerWrapFunction,
         uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler},
     },
+    image::RgbaImage,
     std::{
         fs,
         io::{self, Cursor},
@@ -13,14 +14,32 @@ use {
     },
 };
 
+/// Reads PNG image from `path`.
+fn load_png(path: &Path) -> Result<RgbaImage, io::Error> {
+    let image_bytes = fs::read(path)?;
+
+    let image = image::load(Cursor::new(image_bytes), image::ImageFormat::Png)
+        .map_err(io::Error::other)?
+        .to_rgba8();
+
+    Ok(image)
+}
+
+/// Converts `image` to glium image flipped so its first row is at the bottom.
+fn to_raw(image: RgbaImage) -> RawImage2d<'static, u8> {
+    let image_size = image.dimensions();
+    RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_size)
+}
+
 /// Texture struct.
 /// Contains texture stuff.
 #[derive(Debug, Deref)]
 pub struct Texture {
     pub path: PathBuf,
 
+    /// Shared to be registered in imgui.
     #[deref]
-    pub inner: Texture2d,
+    pub inner: Rc<Texture2d>,
 }
 
 impl Texture {
@@ -32,31 +51,91 @@ impl Texture {
             path = path.as_ref()
         );
 
-        let path_buf = path.as_ref().to_owned();
-        let image_bytes = fs::read(path)?;
+        let image = Self::read(path.as_ref())?;
+
+        Ok(Self::from_image(path, image, display))
+    }
+
+    /// Reads texture image from path, does not need GL context.
+    pub fn read(path: &Path) -> Result<RgbaImage, io::Error> {
+        load_png(path)
+    }
+
+    /// Uploads `image` read from `path` to the GPU.
+    pub fn from_image(path: impl AsRef<Path>, image: RgbaImage, display: &dyn Facade) -> Self {
+        let texture = Texture2d::with_mipmaps(display, to_raw(image), MipmapsOption::NoMipmap)
+            .expect("failed to create texture");
+
+        Self {
+            path: path.as_ref().to_owned(),
+            inner: Rc::new(texture),
+        }
+    }
+}
+
+/// Texture array with a layer per texture atlas item.
+#[derive(Debug, Deref)]
+pub struct TextureArray {
+    pub path: PathBuf,
+
+    #[deref]
+    pub inner: Texture2dArray,
+}
+
+impl TextureArray {
+    /// Loads texture atlas from path and splits it into layers.
+    /// Each layer gets its own full mipmap chain.
+    pub fn from_atlas(path: impl AsRef<Path>, display: &dyn Facade) -> Result<Self, io::Error> {
+        let _log_guard = logger::work!(
+            from = "texture loader",
+            "array from {path:?}",
+            path = path.as_ref()
+        );
+
+        let layers = Self::read_atlas(path.as_ref())?;
+
+        Ok(Self::from_layers(path, layers, display))
+    }
+
+    /// Reads texture atlas from path and splits it into layers, does not need GL context.
+    pub fn read_atlas(path: &Path) -> Result<Vec<RgbaImage>, io::Error> {
+        let image = load_png(path)?;
+
+        let min_size = atlas::ATLAS_ROW_SIZE_IN_PIXELS as u32;
+        if image.width() < min_size || image.height() < min_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("atlas should be at least {min_size} pixels wide and high"),
+            ));
+        }
+
+        Ok(atlas::split_items(&image))
+    }
 
-        let image = image::load(Cursor::new(image_bytes), image::ImageFormat::Png)
-            .expect("failed to load image")
-            .to_rgba8();
-        let image_size = image.dimensions();
-        let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_size);
+    /// Uploads `layers` read from `path` to the GPU.
+    pub fn from_layers(
+        path: impl AsRef<Path>,
+        layers: Vec<RgbaImage>,
+        display: &dyn Facade,
+    ) -> Self {
+        let layers = layers.into_iter().map(to_raw).collect_vec();
 
         let texture =
-            Texture2d::with_mipmaps(display, image, MipmapsOption::AutoGeneratedMipmapsMax(4))
-                .expect("failed to add mipmaps to texture");
+            Texture2dArray::with_mipmaps(display, layers, MipmapsOption::AutoGeneratedMipmaps)
+                .expect("failed to add mipmaps to texture array");
 
-        Ok(Self {
-            path: path_buf,
+        Self {
+            path: path.as_ref().to_owned(),
             inner: texture,
-        })
+        }
     }
 
-    /// Adds mips to texture uniform.
-    pub fn get_sampler(&self) -> Sampler<Texture2d> {
+    /// Adds mips, anisotropic filtering and tiling to texture uniform.
+    pub fn get_sampler(&self) -> Sampler<'_, Texture2dArray> {
         Sampler::new(&self.inner)
             .magnify_filter(MagnifySamplerFilter::Nearest)
-            .minify_filter(MinifySamplerFilter::NearestMipmapNearest)
-            .wrap_function(SamplerWrapFunction::Clamp)
-            .anisotropy(4)
+            .minify_filter(MinifySamplerFilter::NearestMipmapLinear)
+            .wrap_function(SamplerWrapFunction::Repeat)
+            .anisotropy(ANISOTROPY)
     }
 }
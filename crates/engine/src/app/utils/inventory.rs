@@ -0,0 +1,492 @@
+//!
+//! Player inventory with a hotbar choosing voxels to place.
+//!
+
+use {
+    crate::{
+        assets::Handle,
+        graphics::texture::Texture,
+        prelude::*,
+        terrain::voxel::{
+            self,
+            atlas::UV,
+            block_entity::ContainerSlot,
+            voxel_data::{Id, data::*},
+        },
+    },
+    glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior},
+    imgui::TextureId,
+    imgui_glium_renderer::Renderer,
+};
+
+/// How voxels are spent and gained.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Display)]
+pub enum GameMode {
+    /// Stacks are infinite, mined voxels are not collected.
+    #[default]
+    Creative,
+    /// Placing spends voxels, mined voxels drop as items to collect.
+    Survival,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 2] = [Self::Creative, Self::Survival];
+
+    /// Gives mode by case-insensitive name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Stacks of voxels. First [`HOTBAR_SIZE`][Inventory::HOTBAR_SIZE] slots make the hotbar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inventory {
+    pub slots: Vec<Option<ContainerSlot>>,
+    /// Index of selected hotbar slot.
+    pub selected: usize,
+    pub mode: GameMode,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new_creative()
+    }
+}
+
+impl Inventory {
+    pub const HOTBAR_SIZE: usize = 9;
+    pub const N_SLOTS: usize = 4 * Self::HOTBAR_SIZE;
+    pub const MAX_STACK_SIZE: u32 = 64;
+
+    /// Keys selecting hotbar slots.
+    pub const HOTBAR_KEYS: [Key; Self::HOTBAR_SIZE] = [
+        Key::Digit1,
+        Key::Digit2,
+        Key::Digit3,
+        Key::Digit4,
+        Key::Digit5,
+        Key::Digit6,
+        Key::Digit7,
+        Key::Digit8,
+        Key::Digit9,
+    ];
+
+    /// Constructs empty inventory.
+    pub fn new(mode: GameMode) -> Self {
+        Self {
+            slots: vec![None; Self::N_SLOTS],
+            selected: 0,
+            mode,
+        }
+    }
+
+    /// Constructs creative inventory with every placeable voxel in the hotbar.
+    pub fn new_creative() -> Self {
+        let mut result = Self::new(GameMode::Creative);
+
+        let ids = VOXEL_DATA
+            .iter()
+            .map(|data| data.id)
+            .filter(|&id| id != AIR_VOXEL_DATA.id);
+
+        for (slot, id) in result.slots.iter_mut().zip(ids) {
+            *slot = Some(ContainerSlot { id, count: 1 });
+        }
+
+        result
+    }
+
+    /// Gives slots of the hotbar.
+    pub fn hotbar(&self) -> &[Option<ContainerSlot>] {
+        &self.slots[..Self::HOTBAR_SIZE]
+    }
+
+    pub fn selected_slot(&self) -> Option<ContainerSlot> {
+        self.slots[self.selected]
+    }
+
+    pub fn selected_id(&self) -> Option<Id> {
+        self.selected_slot().map(|slot| slot.id)
+    }
+
+    /// Selects hotbar slot `idx`.
+    pub fn select(&mut self, idx: usize) {
+        self.selected = idx.min(Self::HOTBAR_SIZE - 1);
+    }
+
+    /// Moves selection by `offset` slots wrapping around the hotbar.
+    pub fn scroll(&mut self, offset: i32) {
+        self.selected =
+            (self.selected as i32 + offset).rem_euclid(Self::HOTBAR_SIZE as i32) as usize;
+    }
+
+    /// Selects slots by number keys and mouse wheel.
+    pub fn process_input(&mut self, is_cursor_grabbed: bool) {
+        for (idx, &key) in Self::HOTBAR_KEYS.iter().enumerate() {
+            if keyboard::just_pressed(key) {
+                self.select(idx);
+            }
+        }
+
+        let wheel_dy = mouse::get_wheel_dy();
+        if is_cursor_grabbed && wheel_dy != 0.0 {
+            // Scrolling up moves selection to the left.
+            self.scroll(-wheel_dy.signum() as i32);
+        }
+    }
+
+    /// Gives voxel to place from the selected slot. Survival mode spends it.
+    pub fn take_selected(&mut self) -> Option<Id> {
+        let slot = self.slots[self.selected].as_mut()?;
+        let id = slot.id;
+
+        if self.mode == GameMode::Survival {
+            slot.count -= 1;
+
+            if slot.count == 0 {
+                self.slots[self.selected] = None;
+            }
+        }
+
+        Some(id)
+    }
+
+    /// Puts `count` voxels of `id` into stacks of the same voxel first and then
+    /// into empty slots. Returns number of voxels that did not fit.
+    pub fn add(&mut self, id: Id, count: u32) -> u32 {
+        let mut left = count;
+
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.id == id && slot.count < Self::MAX_STACK_SIZE {
+                let n_added = left.min(Self::MAX_STACK_SIZE - slot.count);
+                slot.count += n_added;
+                left -= n_added;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if left == 0 {
+                break;
+            }
+
+            if slot.is_none() {
+                let n_added = left.min(Self::MAX_STACK_SIZE);
+                *slot = Some(ContainerSlot { id, count: n_added });
+                left -= n_added;
+            }
+        }
+
+        left
+    }
+
+    /// Counts voxels of `id` in all stacks.
+    pub fn count(&self, id: Id) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|slot| slot.id == id)
+            .map(|slot| slot.count)
+            .sum()
+    }
+
+    /// Spawns hotbar HUD at the bottom of the screen.
+    pub fn spawn_hotbar(&self, ui: &imgui::Ui, icons: TextureId) {
+        const ICON_SIZE: f32 = 40.0;
+        const BOTTOM_OFFSET: f32 = 16.0;
+
+        let [width, height] = ui.io().display_size;
+
+        ui.window("Hotbar")
+            .position(
+                [0.5 * width, height - BOTTOM_OFFSET],
+                imgui::Condition::Always,
+            )
+            .position_pivot([0.5, 1.0])
+            .no_decoration()
+            .always_auto_resize(true)
+            .movable(false)
+            .bg_alpha(0.4)
+            .build(|| {
+                for (idx, slot) in self.hotbar().iter().enumerate() {
+                    if idx != 0 {
+                        ui.same_line();
+                    }
+
+                    let border = match idx == self.selected {
+                        true => [1.0, 1.0, 1.0, 1.0],
+                        false => [0.2, 0.2, 0.2, 1.0],
+                    };
+
+                    let group = ui.begin_group();
+
+                    match slot {
+                        Some(slot) => {
+                            let uv = UV::new(VOXEL_DATA[slot.id as usize].textures.front);
+
+                            imgui::Image::new(icons, [ICON_SIZE; 2])
+                                .uv0(uv.lo.as_array())
+                                .uv1(uv.hi.as_array())
+                                .border_col(border)
+                                .build(ui);
+
+                            if ui.is_item_hovered() {
+                                ui.tooltip_text(VOXEL_DATA[slot.id as usize].name);
+                            }
+                        }
+
+                        None => {
+                            imgui::Image::new(TextureId::new(usize::MAX), [ICON_SIZE; 2])
+                                .tint_col([0.0; 4])
+                                .border_col(border)
+                                .build(ui);
+                        }
+                    }
+
+                    let count = match (slot, self.mode) {
+                        (Some(slot), GameMode::Survival) => slot.count.to_string(),
+                        _ => String::new(),
+                    };
+                    ui.text(format!("{key} {count}", key = idx + 1));
+
+                    group.end();
+                }
+            });
+    }
+
+    /// Spawns window with all slots and game mode control.
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Inventory")
+            .always_auto_resize(true)
+            .build(|| {
+                let mut mode_idx = GameMode::ALL
+                    .iter()
+                    .position(|&mode| mode == self.mode)
+                    .unwrap_or_default();
+
+                if ui.combo("Mode", &mut mode_idx, &GameMode::ALL, |mode| {
+                    mode.to_string().into()
+                }) {
+                    self.mode = GameMode::ALL[mode_idx];
+                }
+
+                // Any voxel can be put into the selected slot in creative mode.
+                if self.mode == GameMode::Creative {
+                    let mut id = self.selected_id().unwrap_or(AIR_VOXEL_DATA.id);
+
+                    if voxel::id_combo(ui, "Selected voxel", &mut id) {
+                        self.slots[self.selected] =
+                            (id != AIR_VOXEL_DATA.id).then_some(ContainerSlot { id, count: 1 });
+                    }
+                }
+
+                ui.separator();
+
+                for row in self.slots.chunks(Self::HOTBAR_SIZE) {
+                    let line = row
+                        .iter()
+                        .map(|slot| match slot {
+                            Some(slot) => format!(
+                                "{name:>7} {count:<2}",
+                                name = VOXEL_DATA[slot.id as usize].name,
+                                count = slot.count,
+                            ),
+                            None => format!("{:>10}", "-"),
+                        })
+                        .join(" ");
+
+                    ui.text(line);
+                }
+            });
+    }
+}
+
+/// Texture atlas registered in imgui to draw voxel icons. Follows atlas reloads.
+#[derive(Debug)]
+pub struct Icons {
+    pub id: TextureId,
+    atlas: Handle<Texture>,
+    /// Atlas version registered by `id`.
+    registered: Rc<Texture>,
+}
+
+impl Icons {
+    /// Registers loaded `atlas` in imgui.
+    pub fn new(renderer: &mut Renderer, atlas: Handle<Texture>) -> Self {
+        let registered = atlas.get();
+        let id = renderer.textures().insert(Self::imgui_texture(&registered));
+
+        Self {
+            id,
+            atlas,
+            registered,
+        }
+    }
+
+    /// Registers the atlas again if it was reloaded.
+    pub fn update(&mut self, renderer: &mut Renderer) {
+        let atlas = self.atlas.get();
+
+        if Rc::ptr_eq(&atlas, &self.registered) {
+            return;
+        }
+
+        renderer
+            .textures()
+            .replace(self.id, Self::imgui_texture(&atlas));
+        self.registered = atlas;
+    }
+
+    fn imgui_texture(atlas: &Texture) -> imgui_glium_renderer::Texture {
+        imgui_glium_renderer::Texture {
+            texture: Rc::clone(&atlas.inner),
+            sampler: SamplerBehavior {
+                magnify_filter: MagnifySamplerFilter::Nearest,
+                minify_filter: MinifySamplerFilter::Nearest,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl AsBytes for Inventory {
+    fn as_bytes(&self) -> Vec<u8> {
+        compose! {
+            std::iter::once(self.mode as u8),
+            self.selected.as_bytes(),
+            self.slots.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for Inventory {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        let mut reader = ByteReader::new(source);
+
+        let mode = match reader.read::<u8>()? {
+            0 => GameMode::Creative,
+            1 => GameMode::Survival,
+            byte => {
+                return Err(ReinterpretError::Conversion(format!(
+                    "conversion of too large byte ({byte}) to GameMode"
+                )));
+            }
+        };
+
+        let selected: usize = reader.read()?;
+        let len: usize = reader.read()?;
+        let mut slots = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            slots.push(reader.read()?);
+        }
+
+        if Self::HOTBAR_SIZE <= selected || len < Self::HOTBAR_SIZE {
+            return Err(ReinterpretError::Conversion(format!(
+                "invalid inventory with {len} slots and selected slot {selected}"
+            )));
+        }
+
+        Ok(Self {
+            slots,
+            selected,
+            mode,
+        })
+    }
+}
+
+impl DynamicSize for Inventory {
+    fn dynamic_size(&self) -> usize {
+        u8::static_size()
+            + usize::static_size()
+            + usize::static_size()
+            + self
+                .slots
+                .iter()
+                .map(DynamicSize::dynamic_size)
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creative_stacks_are_infinite() {
+        let mut inventory = Inventory::new_creative();
+        let id = inventory.selected_id().unwrap();
+
+        for _ in 0..100 {
+            assert_eq!(inventory.take_selected(), Some(id));
+        }
+
+        let n_placeable = VOXEL_DATA.len() - 1;
+        assert_eq!(inventory.slots.iter().flatten().count(), n_placeable);
+        assert!(inventory.slots.iter().flatten().all(|slot| slot.count == 1));
+    }
+
+    #[test]
+    fn survival_spends_and_collects() {
+        let mut inventory = Inventory::new(GameMode::Survival);
+        assert_eq!(inventory.take_selected(), None);
+
+        assert_eq!(inventory.add(STONE_VOXEL_DATA.id, 70), 0);
+        assert_eq!(inventory.add(DIRT_VOXEL_DATA.id, 1), 0);
+        assert_eq!(inventory.slots[0].unwrap().count, Inventory::MAX_STACK_SIZE);
+        assert_eq!(inventory.slots[1].unwrap().count, 6);
+        assert_eq!(inventory.slots[2].unwrap().id, DIRT_VOXEL_DATA.id);
+
+        // Existing stacks are filled before empty slots.
+        assert_eq!(inventory.add(STONE_VOXEL_DATA.id, 3), 0);
+        assert_eq!(inventory.slots[1].unwrap().count, 9);
+
+        inventory.select(2);
+        assert_eq!(inventory.take_selected(), Some(DIRT_VOXEL_DATA.id));
+        assert_eq!(inventory.take_selected(), None);
+        assert_eq!(inventory.count(STONE_VOXEL_DATA.id), 73);
+    }
+
+    #[test]
+    fn full_inventory_returns_leftover() {
+        let mut inventory = Inventory::new(GameMode::Survival);
+        let capacity = Inventory::N_SLOTS as u32 * Inventory::MAX_STACK_SIZE;
+
+        assert_eq!(inventory.add(LOG_VOXEL_DATA.id, capacity + 5), 5);
+        assert_eq!(inventory.add(STONE_VOXEL_DATA.id, 1), 1);
+    }
+
+    #[test]
+    fn selection_wraps_around_hotbar() {
+        let mut inventory = Inventory::new(GameMode::Creative);
+
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected, Inventory::HOTBAR_SIZE - 1);
+
+        inventory.scroll(2);
+        assert_eq!(inventory.selected, 1);
+
+        inventory.select(100);
+        assert_eq!(inventory.selected, Inventory::HOTBAR_SIZE - 1);
+    }
+
+    #[test]
+    fn reinterpret_inventory() {
+        let mut before = Inventory::new(GameMode::Survival);
+        before.add(GRASS_VOXEL_DATA.id, 12);
+        before.slots[20] = Some(ContainerSlot {
+            id: LOG_VOXEL_DATA.id,
+            count: 3,
+        });
+        before.select(4);
+
+        let after = Inventory::from_bytes(&before.as_bytes()).unwrap();
+
+        assert_eq!(before, after);
+        assert_eq!(before.dynamic_size(), before.as_bytes().len());
+    }
+}
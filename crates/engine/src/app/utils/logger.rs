@@ -140,59 +140,7 @@ pub fn spawn_window(ui: &imgui::Ui) {
                 .lock()
                 .expect("messages lock should be not poisoned");
 
-            static INPUT: Mutex<String> = Mutex::new(String::new());
-            let mut input = INPUT.lock().unwrap();
-
-            let _is_enter_pressed = ui
-                .input_text("Console", &mut input)
-                .enter_returns_true(true)
-                .build();
-
-            let _buf = input.replace("^;", "\n");
-
-            // FIXME(hack3rmann): remove cpython from project
-            // let gil = Python::acquire_gil();
-            // let py = gil.python();
-
-            // let voxel_set = py_fn!(py, voxel_set(x: i32, y: i32, z: i32, new_id: u16) -> PyResult<i32> {
-            //     command(Command::SetVoxel { pos: veci!(x, y, z), new_id });
-            //     Ok(0)
-            // });
-
-            // let voxel_fill = py_fn!(py, voxel_fill(
-            //     sx: i32, sy: i32, sz: i32,
-            //     ex: i32, ey: i32, ez: i32, new_id: u16
-            // ) -> PyResult<i32> {
-            //     command(Command::FillVoxels { pos_from: veci!(sx, sy, sz), pos_to: veci!(ex, ey, ez), new_id });
-            //     Ok(0)
-            // });
-
-            // let drop_all_meshes = py_fn!(py, drop_all_meshes() -> PyResult<i32> {
-            //     command(Command::DropAllMeshes);
-            //     Ok(0)
-            // });
-
-            // let locals = PyDict::new(py);
-
-            // locals.set_item(py, "voxel_set", voxel_set)
-            //     .unwrap_or_else(|err|
-            //         log!(Error, from = "logger", "failed to set 'voxel_set' item: {err:?}")
-            //     );
-
-            // locals.set_item(py, "voxel_fill", voxel_fill)
-            //     .unwrap_or_else(|err|
-            //         log!(Error, from = "logger", "failed to set 'voxel_fill' item: {err:?}")
-            //     );
-            //
-            // locals.set_item(py, "drop_all_meshes", drop_all_meshes)
-            //     .unwrap_or_else(|err|
-            //         log!(Error, from = "logger", "failed to set 'drop_all_meshes' item: {err:?}")
-            //     );
-
-            // if is_enter_pressed {
-            //     py.run(&buf, None, Some(&locals))
-            //         .unwrap_or_else(|err| log!(Error, from = "logger", "{err:?}"));
-            // }
+            crate::console::spawn_input(ui);
 
             for msg in messages.iter().rev() {
                 let color = match msg.msg_type {
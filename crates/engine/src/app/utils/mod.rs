@@ -1,9 +1,11 @@
+pub mod assets;
 pub mod concurrency;
 pub mod graphics;
 pub mod profiler;
 pub mod reinterpreter;
 pub mod runtime;
 pub mod saves;
+pub mod script;
 pub mod terrain;
 pub mod time;
 pub mod user_io;
@@ -11,5 +13,9 @@ pub mod window;
 // FIXME(hack3rmann): support unix
 // pub mod werror;
 pub mod cfg;
+pub mod console;
+pub mod entity;
+pub mod inventory;
 pub mod io;
 pub mod logger;
+pub mod physics;
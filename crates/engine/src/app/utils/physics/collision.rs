@@ -0,0 +1,182 @@
+//!
+//! Swept AABB collision against the voxel grid.
+//!
+
+use {
+    crate::{prelude::*, terrain::voxel::Voxel},
+    std::ops::Range,
+};
+
+/// Gap kept between colliding boxes so that they never touch exactly.
+pub const SKIN: f32 = 0.001;
+
+/// Gives bounding box of voxel at `pos`.
+pub fn voxel_aabb(pos: Int3) -> Aabb {
+    let center = vec3::from(pos) * Voxel::SIZE;
+    let half_size = vec3::all(0.5 * Voxel::SIZE);
+
+    Aabb::from_float3(center - half_size, center + half_size)
+}
+
+/// Gives range of positions of all voxels overlapping `aabb`.
+pub fn overlapping_range(aabb: Aabb) -> Range<Int3> {
+    let to_voxel = |coord: f32| (coord / Voxel::SIZE + 0.5).floor() as i32;
+    let (lo, hi) = (aabb.lo(), aabb.hi());
+
+    Int3::new(to_voxel(lo.x), to_voxel(lo.y), to_voxel(lo.z))
+        ..Int3::new(to_voxel(hi.x), to_voxel(hi.y), to_voxel(hi.z)) + Int3::ONE
+}
+
+/// Gives positions of all voxels overlapping `aabb`.
+pub fn overlapping_voxels(aabb: Aabb) -> SpaceIter {
+    SpaceIter::new(overlapping_range(aabb))
+}
+
+/// Checks if `aabb` overlaps any solid voxel.
+pub fn intersects_solid(aabb: Aabb, mut is_solid: impl FnMut(Int3) -> bool) -> bool {
+    overlapping_voxels(aabb).any(|pos| is_solid(pos) && voxel_aabb(pos).intersects(aabb))
+}
+
+/// Gives the part of `delta` along `axis` that `aabb` can move without entering
+/// a solid voxel. Voxels that already overlap `aabb` are ignored.
+pub fn sweep_axis(
+    aabb: Aabb,
+    axis: usize,
+    delta: f32,
+    mut is_solid: impl FnMut(Int3) -> bool,
+) -> f32 {
+    if delta == 0.0 {
+        return 0.0;
+    }
+
+    let (lo, hi) = (aabb.lo().as_array(), aabb.hi().as_array());
+
+    let mut offset = [0.0; 3];
+    offset[axis] = delta;
+    let swept = Aabb::from_float3(
+        vec3::from(array_init(|i| lo[i].min(lo[i] + offset[i]))),
+        vec3::from(array_init(|i| hi[i].max(hi[i] + offset[i]))),
+    );
+
+    let mut allowed = delta;
+
+    for pos in overlapping_voxels(swept) {
+        let voxel = voxel_aabb(pos);
+
+        if !voxel.intersects(swept) || voxel.intersects(aabb) || !is_solid(pos) {
+            continue;
+        }
+
+        let (voxel_lo, voxel_hi) = (voxel.lo().as_array(), voxel.hi().as_array());
+
+        allowed = if 0.0 < delta {
+            allowed.min(voxel_lo[axis] - hi[axis] - SKIN)
+        } else {
+            allowed.max(voxel_hi[axis] - lo[axis] + SKIN)
+        };
+    }
+
+    // Do not move backwards if the box is already closer than the skin.
+    if 0.0 < delta {
+        allowed.max(0.0)
+    } else {
+        allowed.min(0.0)
+    }
+}
+
+/// Result of [`move_aabb`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Movement {
+    pub aabb: Aabb,
+    /// Applied motion.
+    pub motion: vec3,
+    /// Axes where the motion was blocked.
+    pub is_blocked: [bool; 3],
+}
+
+/// Moves `aabb` by `motion` resolving collisions axis by axis, vertical axis first.
+pub fn move_aabb(aabb: Aabb, motion: vec3, mut is_solid: impl FnMut(Int3) -> bool) -> Movement {
+    let wanted = motion.as_array();
+    let mut applied = [0.0; 3];
+    let mut is_blocked = [false; 3];
+    let mut aabb = aabb;
+
+    for axis in [1, 0, 2] {
+        let delta = sweep_axis(aabb, axis, wanted[axis], &mut is_solid);
+
+        let mut offset = [0.0; 3];
+        offset[axis] = delta;
+        aabb = aabb.translated(vec3::from(offset));
+
+        applied[axis] = delta;
+        is_blocked[axis] = delta != wanted[axis];
+    }
+
+    Movement {
+        aabb,
+        motion: vec3::from(applied),
+        is_blocked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor(size: i32) -> impl Fn(Int3) -> bool {
+        move |pos| pos.y == 0 && pos.x.abs() <= size && pos.z.abs() <= size
+    }
+
+    fn unit_box(center: vec3) -> Aabb {
+        Aabb::from_float3(center - vec3::all(0.25), center + vec3::all(0.25))
+    }
+
+    #[test]
+    fn falling_box_lands_on_floor() {
+        let aabb = unit_box(vec3::new(0.0, 3.0, 0.0));
+        let movement = move_aabb(aabb, vec3::new(0.0, -10.0, 0.0), floor(4));
+
+        assert!(movement.is_blocked[1]);
+        assert!((movement.aabb.lo().y - 0.5).abs() < 2.0 * SKIN);
+    }
+
+    #[test]
+    fn touching_floor_does_not_block_sliding() {
+        let aabb = unit_box(vec3::new(0.0, 0.75 + SKIN, 0.0));
+        let movement = move_aabb(aabb, vec3::new(2.0, 0.0, 1.0), floor(4));
+
+        assert_eq!(movement.is_blocked, [false; 3]);
+        assert_eq!(movement.motion, vec3::new(2.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn wall_stops_horizontal_motion() {
+        let wall = |pos: Int3| pos.x == 3;
+        let aabb = unit_box(vec3::new(0.0, 0.0, 0.0));
+        let movement = move_aabb(aabb, vec3::new(5.0, 0.0, 0.5), wall);
+
+        assert!(movement.is_blocked[0]);
+        assert!(!movement.is_blocked[2]);
+        assert!((movement.aabb.hi().x - 2.5).abs() < 2.0 * SKIN);
+    }
+
+    #[test]
+    fn fast_motion_does_not_tunnel() {
+        let wall = |pos: Int3| pos.z == 10;
+        let aabb = unit_box(vec3::zero());
+        let movement = move_aabb(aabb, vec3::new(0.0, 0.0, 1000.0), wall);
+
+        assert!(movement.aabb.hi().z < 9.5);
+    }
+
+    #[test]
+    fn overlapping_voxels_cover_box() {
+        let aabb = Aabb::from_float3(vec3::new(-0.6, 0.0, 0.4), vec3::new(0.6, 1.8, 0.6));
+        let voxels: Vec<_> = overlapping_voxels(aabb).collect();
+
+        assert!(voxels.contains(&Int3::new(-1, 0, 0)));
+        assert!(voxels.contains(&Int3::new(1, 2, 1)));
+        assert!(intersects_solid(aabb, |pos| pos == Int3::new(1, 2, 1)));
+        assert!(!intersects_solid(aabb, |pos| pos == Int3::new(2, 2, 1)));
+    }
+}
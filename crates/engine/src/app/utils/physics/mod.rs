@@ -0,0 +1,7 @@
+//!
+//! Fixed-timestep physics colliding with voxels.
+//!
+
+pub mod collision;
+pub mod player;
+pub mod timestep;
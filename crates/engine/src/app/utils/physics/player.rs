@@ -0,0 +1,396 @@
+//!
+//! Walking player controlled by keyboard with gravity, jumping, step-up and crouch.
+//!
+
+use {
+    super::{
+        collision::{self, Movement},
+        timestep::FixedTimestep,
+    },
+    crate::{graphics::camera::Camera, prelude::*, terrain::voxel::Voxel},
+};
+
+/// How player moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Display)]
+pub enum MoveMode {
+    /// Free camera flying through terrain.
+    #[default]
+    Fly,
+    /// Player walks colliding with terrain.
+    Walk,
+}
+
+/// Player intent read from input devices.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct PlayerInput {
+    /// Desired horizontal direction. Either zero or normalized.
+    pub direction: vec3,
+    pub jump: bool,
+    pub crouch: bool,
+}
+
+impl PlayerInput {
+    /// Reads input from keyboard relative to camera orientation.
+    pub fn from_keyboard(cam: &Camera) -> Self {
+        let front = vecf!(cam.front.x, 0, cam.front.z).normalized();
+        let right = vecf!(cam.right.x, 0, cam.right.z).normalized();
+
+        let mut direction = vec3::zero();
+
+        if keyboard::is_pressed(Key::KeyW) {
+            direction += front;
+        }
+        if keyboard::is_pressed(Key::KeyS) {
+            direction -= front;
+        }
+        if keyboard::is_pressed(Key::KeyA) {
+            direction += right;
+        }
+        if keyboard::is_pressed(Key::KeyD) {
+            direction -= right;
+        }
+
+        if direction != vec3::zero() {
+            direction = direction.normalized();
+        }
+
+        Self {
+            direction,
+            jump: keyboard::is_pressed(Key::Space),
+            crouch: keyboard::is_pressed(Key::ShiftLeft),
+        }
+    }
+}
+
+/// Walking player. Position is the center of its feet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    pub pos: vec3,
+    pub velocity: vec3,
+    pub is_on_ground: bool,
+    pub is_crouching: bool,
+    timestep: FixedTimestep,
+}
+
+impl Player {
+    pub const WIDTH: f32 = 0.6;
+    pub const HEIGHT: f32 = 1.8;
+    pub const CROUCH_HEIGHT: f32 = 1.5;
+    pub const EYE_HEIGHT: f32 = 1.62;
+    pub const CROUCH_EYE_HEIGHT: f32 = 1.27;
+
+    pub const WALK_SPEED: f32 = 4.3;
+    pub const CROUCH_SPEED: f32 = 1.3;
+    pub const JUMP_SPEED: f32 = 8.4;
+    pub const GRAVITY: f32 = 28.0;
+    pub const MAX_FALL_SPEED: f32 = 60.0;
+    /// Player climbs single voxels without jumping.
+    pub const STEP_HEIGHT: f32 = 1.05 * Voxel::SIZE;
+    /// Distance below feet where ground is looked for.
+    pub const GROUND_PROBE_DEPTH: f32 = 0.1;
+
+    /// Physics step duration.
+    pub const TIMESTEP: f32 = FixedTimestep::PHYSICS.step;
+
+    /// Constructs player standing at `pos`.
+    pub fn new(pos: vec3) -> Self {
+        Self {
+            pos,
+            velocity: vec3::zero(),
+            is_on_ground: false,
+            is_crouching: false,
+            timestep: FixedTimestep::PHYSICS,
+        }
+    }
+
+    /// Constructs player with eyes at camera position.
+    pub fn from_camera(cam: &Camera) -> Self {
+        Self::new(cam.pos - vecf!(0, Self::EYE_HEIGHT, 0))
+    }
+
+    pub fn height(&self) -> f32 {
+        match self.is_crouching {
+            true => Self::CROUCH_HEIGHT,
+            false => Self::HEIGHT,
+        }
+    }
+
+    pub fn eye_pos(&self) -> vec3 {
+        let eye_height = match self.is_crouching {
+            true => Self::CROUCH_EYE_HEIGHT,
+            false => Self::EYE_HEIGHT,
+        };
+
+        self.pos + vecf!(0, eye_height, 0)
+    }
+
+    /// Gives bounding box of player with `height` at `pos`.
+    pub fn aabb_at(pos: vec3, height: f32) -> Aabb {
+        let half_width = 0.5 * Self::WIDTH;
+
+        Aabb::from_float3(
+            pos - vecf!(half_width, 0, half_width),
+            pos + vecf!(half_width, height, half_width),
+        )
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Self::aabb_at(self.pos, self.height())
+    }
+
+    /// Simulates `dt` seconds in fixed [steps][Player::TIMESTEP].
+    pub fn update(&mut self, dt: f32, input: PlayerInput, mut is_solid: impl FnMut(Int3) -> bool) {
+        for _ in 0..self.timestep.advance(dt) {
+            self.step(Self::TIMESTEP, input, &mut is_solid);
+        }
+    }
+
+    /// Makes one physics step.
+    pub fn step(&mut self, dt: f32, input: PlayerInput, mut is_solid: impl FnMut(Int3) -> bool) {
+        self.update_crouch(input.crouch, &mut is_solid);
+
+        let speed = match self.is_crouching {
+            true => Self::CROUCH_SPEED,
+            false => Self::WALK_SPEED,
+        };
+
+        self.velocity.x = input.direction.x * speed;
+        self.velocity.z = input.direction.z * speed;
+
+        if input.jump && self.is_on_ground {
+            self.velocity.y = Self::JUMP_SPEED;
+        }
+
+        self.velocity.y = (self.velocity.y - Self::GRAVITY * dt).max(-Self::MAX_FALL_SPEED);
+
+        let mut motion = self.velocity * dt;
+
+        if self.is_crouching && self.is_on_ground {
+            motion = self.clamp_to_edges(motion, &mut is_solid);
+        }
+
+        let mut movement = collision::move_aabb(self.aabb(), motion, &mut is_solid);
+
+        let is_horizontally_blocked = movement.is_blocked[0] || movement.is_blocked[2];
+        if self.is_on_ground
+            && is_horizontally_blocked
+            && let Some(stepped) = self.try_step_up(motion, &mut is_solid)
+            && movement.motion.xz().len() < stepped.motion.xz().len()
+        {
+            movement = stepped;
+        }
+
+        self.pos += movement.motion;
+
+        self.is_on_ground = movement.is_blocked[1] && motion.y < 0.0;
+
+        for (axis, is_blocked) in movement.is_blocked.into_iter().enumerate() {
+            if is_blocked {
+                let mut velocity = self.velocity.as_array();
+                velocity[axis] = 0.0;
+                self.velocity = vec3::from(velocity);
+            }
+        }
+    }
+
+    /// Moves up by [step height][Player::STEP_HEIGHT], then horizontally, then down.
+    fn try_step_up(
+        &self,
+        motion: vec3,
+        mut is_solid: impl FnMut(Int3) -> bool,
+    ) -> Option<Movement> {
+        let aabb = self.aabb();
+
+        let up = collision::move_aabb(aabb, vecf!(0, Self::STEP_HEIGHT, 0), &mut is_solid);
+        let across = collision::move_aabb(up.aabb, vecf!(motion.x, 0, motion.z), &mut is_solid);
+        let down = collision::move_aabb(
+            across.aabb,
+            vecf!(0, -up.motion.y + motion.y.min(0.0), 0),
+            &mut is_solid,
+        );
+
+        // Stepping should land on something.
+        if !down.is_blocked[1] {
+            return None;
+        }
+
+        Some(Movement {
+            aabb: down.aabb,
+            motion: up.motion + across.motion + down.motion,
+            is_blocked: [across.is_blocked[0], true, across.is_blocked[2]],
+        })
+    }
+
+    /// Stops horizontal motion that would move crouching player off the edge.
+    fn clamp_to_edges(&self, motion: vec3, mut is_solid: impl FnMut(Int3) -> bool) -> vec3 {
+        let mut has_ground_below = |offset: vec3| {
+            let below = self
+                .aabb()
+                .translated(offset - vecf!(0, Self::GROUND_PROBE_DEPTH, 0));
+            collision::intersects_solid(below, &mut is_solid)
+        };
+
+        let mut motion = motion;
+
+        if !has_ground_below(vecf!(motion.x, 0, 0)) {
+            motion.x = 0.0;
+        }
+
+        if !has_ground_below(vecf!(motion.x, 0, motion.z)) {
+            motion.z = 0.0;
+        }
+
+        motion
+    }
+
+    /// Crouches or stands up if there is enough space above.
+    fn update_crouch(&mut self, crouch: bool, is_solid: impl FnMut(Int3) -> bool) {
+        if crouch {
+            self.is_crouching = true;
+        } else if self.is_crouching
+            && !collision::intersects_solid(Self::aabb_at(self.pos, Self::HEIGHT), is_solid)
+        {
+            self.is_crouching = false;
+        }
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new(vec3::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat floor with top face at `y = 0.5`.
+    fn floor(pos: Int3) -> bool {
+        pos.y == 0 && pos.x.abs() < 16 && pos.z.abs() < 16
+    }
+
+    fn simulate(player: &mut Player, input: PlayerInput, seconds: f32, is_solid: fn(Int3) -> bool) {
+        for _ in 0..(seconds / Player::TIMESTEP) as usize {
+            player.step(Player::TIMESTEP, input, is_solid);
+        }
+    }
+
+    fn forward() -> PlayerInput {
+        PlayerInput {
+            direction: vecf!(1, 0, 0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn falls_and_lands() {
+        let mut player = Player::new(vecf!(0, 10, 0));
+        simulate(&mut player, PlayerInput::default(), 2.0, floor);
+
+        assert!(player.is_on_ground);
+        assert!((player.pos.y - 0.5).abs() < 0.01);
+        assert_eq!(player.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn jump_goes_up_and_returns() {
+        let mut player = Player::new(vecf!(0, 0.6, 0));
+        simulate(&mut player, PlayerInput::default(), 0.5, floor);
+
+        let jump = PlayerInput {
+            jump: true,
+            ..Default::default()
+        };
+        player.step(Player::TIMESTEP, jump, floor);
+        simulate(&mut player, PlayerInput::default(), 0.2, floor);
+        assert!(1.0 < player.pos.y);
+
+        simulate(&mut player, PlayerInput::default(), 1.0, floor);
+        assert!(player.is_on_ground);
+        assert!((player.pos.y - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn steps_up_single_block() {
+        fn stair(pos: Int3) -> bool {
+            floor(pos) || pos.y == 1 && 3 <= pos.x && pos.x < 16 && pos.z.abs() < 16
+        }
+
+        let mut player = Player::new(vecf!(0, 0.5, 0));
+        simulate(&mut player, PlayerInput::default(), 0.2, stair);
+        assert!(player.is_on_ground);
+
+        simulate(&mut player, forward(), 2.0, stair);
+
+        assert!(4.0 < player.pos.x);
+        assert!((player.pos.y - 1.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn wall_blocks_walking() {
+        fn wall(pos: Int3) -> bool {
+            floor(pos) || (1..=2).contains(&pos.y) && pos.x == 3
+        }
+
+        let mut player = Player::new(vecf!(0, 0.5, 0));
+        simulate(&mut player, forward(), 3.0, wall);
+
+        assert!(player.pos.x < 2.5 - 0.5 * Player::WIDTH);
+        assert!(2.0 < player.pos.x);
+    }
+
+    #[test]
+    fn crouching_does_not_fall_off_edge() {
+        fn ledge(pos: Int3) -> bool {
+            pos.y == 0 && pos.x.abs() <= 2 && pos.z.abs() <= 2
+        }
+
+        let mut player = Player::new(vecf!(0, 0.5, 0));
+        simulate(&mut player, PlayerInput::default(), 0.2, ledge);
+
+        let input = PlayerInput {
+            crouch: true,
+            ..forward()
+        };
+        simulate(&mut player, input, 5.0, ledge);
+
+        assert!(player.is_on_ground);
+        assert!((player.pos.y - 0.5).abs() < 0.01);
+
+        simulate(&mut player, forward(), 3.0, ledge);
+        assert!(player.pos.y < 0.0);
+    }
+
+    #[test]
+    fn cannot_stand_up_under_ceiling() {
+        fn ceiling(pos: Int3) -> bool {
+            pos.y == 3
+        }
+
+        let mut player = Player::new(vecf!(0, 1, 0));
+        player.is_crouching = true;
+
+        player.update_crouch(false, ceiling);
+        assert!(player.is_crouching);
+
+        player.pos.y = 0.5;
+        player.update_crouch(false, ceiling);
+        assert!(!player.is_crouching);
+    }
+
+    #[test]
+    fn fixed_timestep_does_not_depend_on_frame_rate() {
+        let (mut a, mut b) = (Player::new(vecf!(0, 10, 0)), Player::new(vecf!(0, 10, 0)));
+
+        for _ in 0..30 {
+            a.update(1.0 / 30.0, PlayerInput::default(), floor);
+        }
+
+        for _ in 0..120 {
+            b.update(1.0 / 120.0, PlayerInput::default(), floor);
+        }
+
+        assert!((a.pos.y - b.pos.y).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,72 @@
+//!
+//! Fixed time step shared by physics, entity systems and world ticks.
+//!
+
+/// Splits frame time into fixed steps, the rest is carried to the next update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedTimestep {
+    /// Step duration in seconds.
+    pub step: f32,
+    /// Limits steps per update so that long frames do not stall the game.
+    /// Time of skipped steps is dropped.
+    pub max_steps: usize,
+    /// Time not yet simulated.
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Time step of player and entity physics.
+    pub const PHYSICS: Self = Self::new(1.0 / 60.0, 10);
+
+    pub const fn new(step: f32, max_steps: usize) -> Self {
+        Self {
+            step,
+            max_steps,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds `dt` seconds and gives number of steps to run.
+    pub fn advance(&mut self, dt: f32) -> usize {
+        self.accumulator += dt;
+
+        let n_steps = (self.accumulator / self.step) as usize;
+
+        if self.max_steps < n_steps {
+            self.accumulator = 0.0;
+            return self.max_steps;
+        }
+
+        self.accumulator -= n_steps as f32 * self.step;
+
+        n_steps
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::PHYSICS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remainder_is_carried() {
+        let mut timestep = FixedTimestep::new(0.5, 10);
+
+        assert_eq!(timestep.advance(0.75), 1);
+        assert_eq!(timestep.advance(0.25), 1);
+        assert_eq!(timestep.advance(0.25), 0);
+    }
+
+    #[test]
+    fn long_updates_are_limited() {
+        let mut timestep = FixedTimestep::new(0.5, 2);
+
+        assert_eq!(timestep.advance(10.25), 2);
+        assert_eq!(timestep.advance(0.25), 0);
+    }
+}
@@ -622,6 +622,11 @@ impl<E: Copy + Into<Enumerator>> Save<E> {
         }
     }
 
+    /// Checks that save has data enumerated by `enumerator`.
+    pub fn contains(&self, enumerator: E) -> bool {
+        self.offsets.contains_key(&enumerator.into())
+    }
+
     /// Loads offset by enumerator.
     fn load_offset(&self, enumerator: E) -> Offset {
         *self
@@ -0,0 +1,432 @@
+//!
+//! Embedded [Rhai](https://rhai.rs) scripting. Scripts control the world through the
+//! [command channel][commands::command], the [camera][camera::command], the
+//! [generator][generator] and saves. Output is captured into the log window.
+//!
+
+use {
+    crate::{
+        graphics::camera::{self, Camera, CameraCommand},
+        prelude::*,
+        terrain::{
+            chunk::commands::{self, Command},
+            voxel::{self, generator, voxel_data::Id},
+        },
+    },
+    rhai::{Array, Dynamic, Engine, EvalAltResult, FLOAT, INT, Scope},
+    std::{fs, path::PathBuf},
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Camera state visible to scripts. Updated every frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct CameraState {
+    pos: vec3,
+    roll: f32,
+    pitch: f32,
+    yaw: f32,
+}
+
+/// Script engine with persistent REPL variables.
+pub struct ScriptEngine {
+    engine: Engine,
+    scope: Scope<'static>,
+    camera: Arc<spin::RwLock<CameraState>>,
+    /// Script files requested by `run_script` from other scripts.
+    queued_files: Arc<spin::Mutex<Vec<String>>>,
+    file_names: Vec<String>,
+}
+
+impl ScriptEngine {
+    /// Constructs engine with all world bindings registered.
+    pub fn new() -> Self {
+        let camera = Arc::new(spin::RwLock::new(CameraState::default()));
+        let queued_files = Arc::new(spin::Mutex::new(vec![]));
+
+        let mut engine = Engine::new();
+
+        engine
+            .set_max_operations(cfg::script::MAX_OPERATIONS)
+            .set_module_resolver(rhai::module_resolvers::FileModuleResolver::new_with_path(
+                cfg::script::DIRECTORY,
+            ))
+            .on_print(|text| logger::log!(Info, from = "script", "{text}"))
+            .on_debug(|text, source, pos| {
+                logger::log!(
+                    Info,
+                    from = "script",
+                    "{source}:{pos}: {text}",
+                    source = source.unwrap_or("console"),
+                )
+            });
+
+        Self::register_voxels(&mut engine);
+        Self::register_camera(&mut engine, &camera);
+        Self::register_generator(&mut engine);
+        Self::register_saves(&mut engine);
+
+        engine.register_fn("run_script", {
+            let queued_files = Arc::clone(&queued_files);
+            move |name: &str| queued_files.lock().push(name.to_owned())
+        });
+
+        Self {
+            engine,
+            scope: Scope::new(),
+            camera,
+            queued_files,
+            file_names: Self::list_files(),
+        }
+    }
+
+    fn register_voxels(engine: &mut Engine) {
+        engine
+            .register_fn(
+                "voxel_set",
+                |x: INT, y: INT, z: INT, id: Dynamic| -> ScriptResult<()> {
+                    commands::command(Command::SetVoxel {
+                        pos: Self::int3(x, y, z)?,
+                        new_id: Self::voxel_id(id)?,
+                    });
+                    Ok(())
+                },
+            )
+            .register_fn(
+                "voxel_fill",
+                |sx: INT,
+                 sy: INT,
+                 sz: INT,
+                 ex: INT,
+                 ey: INT,
+                 ez: INT,
+                 id: Dynamic|
+                 -> ScriptResult<()> {
+                    commands::command(Command::FillVoxels {
+                        pos_from: Self::int3(sx, sy, sz)?,
+                        pos_to: Self::int3(ex, ey, ez)?,
+                        new_id: Self::voxel_id(id)?,
+                    });
+                    Ok(())
+                },
+            )
+            .register_fn("voxel_id", |name: &str| -> ScriptResult<INT> {
+                Self::voxel_id(name.into()).map(INT::from)
+            })
+            .register_fn("drop_all_meshes", || {
+                commands::command(Command::DropAllMeshes)
+            })
+            .register_fn("undo", || commands::command(Command::Undo))
+            .register_fn("redo", || commands::command(Command::Redo));
+    }
+
+    fn register_camera(engine: &mut Engine, camera: &Arc<spin::RwLock<CameraState>>) {
+        engine
+            .register_fn("camera_pos", {
+                let camera = Arc::clone(camera);
+                move || -> Array {
+                    let pos = camera.read().pos;
+                    vec![
+                        Dynamic::from(pos.x as FLOAT),
+                        Dynamic::from(pos.y as FLOAT),
+                        Dynamic::from(pos.z as FLOAT),
+                    ]
+                }
+            })
+            .register_fn("camera_rotation", {
+                let camera = Arc::clone(camera);
+                move || -> Array {
+                    let state = *camera.read();
+                    vec![
+                        Dynamic::from(state.roll as FLOAT),
+                        Dynamic::from(state.pitch as FLOAT),
+                        Dynamic::from(state.yaw as FLOAT),
+                    ]
+                }
+            })
+            .register_fn(
+                "camera_set_pos",
+                |x: Dynamic, y: Dynamic, z: Dynamic| -> ScriptResult<()> {
+                    let pos = vec3::new(Self::float(x)?, Self::float(y)?, Self::float(z)?);
+                    camera::command(CameraCommand::SetPosition(pos));
+                    Ok(())
+                },
+            )
+            .register_fn(
+                "camera_set_rotation",
+                |roll: Dynamic, pitch: Dynamic, yaw: Dynamic| -> ScriptResult<()> {
+                    camera::command(CameraCommand::SetRotation {
+                        roll: Self::float(roll)?,
+                        pitch: Self::float(pitch)?,
+                        yaw: Self::float(yaw)?,
+                    });
+                    Ok(())
+                },
+            )
+            .register_fn("camera_set_speed", |speed: Dynamic| -> ScriptResult<()> {
+                camera::command(CameraCommand::SetSpeed(Self::float(speed)?));
+                Ok(())
+            });
+    }
+
+    fn register_generator(engine: &mut Engine) {
+        engine
+            .register_fn("generator_seed", || INT::from(generator::seed()))
+            .register_fn("generator_set_seed", |seed: INT| -> ScriptResult<()> {
+                let seed = u32::try_from(seed).map_err(|_| format!("invalid seed {seed}"))?;
+                generator::set_seed(seed);
+                Ok(())
+            })
+            .register_fn(
+                "generator_set_frequency",
+                |freq: Dynamic| -> ScriptResult<()> {
+                    generator::set_frequency(Self::float(freq)?);
+                    Ok(())
+                },
+            )
+            .register_fn("generator_set_octaves", |n: INT| -> ScriptResult<()> {
+                let n = usize::try_from(n).map_err(|_| format!("invalid octaves count {n}"))?;
+                generator::set_n_octaves(n);
+                Ok(())
+            })
+            .register_fn("generator_rebuild", generator::rebuild)
+            .register_fn("generate", |x: INT, y: INT, z: INT| -> ScriptResult<()> {
+                let sizes = Self::int3(x, y, z)?;
+                if sizes.x <= 0 || sizes.y <= 0 || sizes.z <= 0 {
+                    return Err(format!("invalid world sizes {sizes}").into());
+                }
+
+                commands::command(Command::Generate {
+                    sizes: USize3::from(sizes),
+                });
+                Ok(())
+            });
+    }
+
+    fn register_saves(engine: &mut Engine) {
+        engine
+            .register_fn("save_world", || {
+                commands::command(Command::Save {
+                    name: cfg::save::WORLD_NAME.into(),
+                })
+            })
+            .register_fn("save_world", |name: &str| {
+                commands::command(Command::Save { name: name.into() })
+            })
+            .register_fn("load_world", || {
+                commands::command(Command::Load {
+                    name: cfg::save::WORLD_NAME.into(),
+                })
+            })
+            .register_fn("load_world", |name: &str| {
+                commands::command(Command::Load { name: name.into() })
+            });
+    }
+
+    fn int3(x: INT, y: INT, z: INT) -> ScriptResult<Int3> {
+        let coord = |value: INT| {
+            i32::try_from(value).map_err(|_| format!("coordinate {value} is out of range"))
+        };
+
+        Ok(Int3::new(coord(x)?, coord(y)?, coord(z)?))
+    }
+
+    fn float(value: Dynamic) -> ScriptResult<f32> {
+        match value.as_float() {
+            Ok(value) => Ok(value as f32),
+            Err(_) => match value.as_int() {
+                Ok(value) => Ok(value as f32),
+                Err(type_name) => Err(format!("expected number, found {type_name}").into()),
+            },
+        }
+    }
+
+    /// Converts voxel id or voxel name to [`Id`].
+    fn voxel_id(value: Dynamic) -> ScriptResult<Id> {
+        if let Ok(id) = value.as_int() {
+            return match Id::try_from(id) {
+                Ok(id) if voxel::is_id_valid(id) => Ok(id),
+                _ => Err(format!("invalid voxel id {id}").into()),
+            };
+        }
+
+        let name = value
+            .into_immutable_string()
+            .map_err(|type_name| format!("expected voxel id or name, found {type_name}"))?;
+
+        voxel::id_by_name(&name).ok_or_else(|| format!("unknown voxel '{name}'").into())
+    }
+
+    /// Evaluates `source` keeping variables between calls and gives the result.
+    pub fn try_eval(&mut self, source: &str) -> Result<Dynamic, ScriptError> {
+        Ok(self
+            .engine
+            .eval_with_scope::<Dynamic>(&mut self.scope, source)?)
+    }
+
+    /// Evaluates console line and logs its result.
+    pub fn eval(&mut self, source: &str) {
+        logger::log!(Info, from = "console", "> {source}");
+
+        match self.try_eval(source) {
+            Ok(result) if result.is_unit() => (),
+            Ok(result) => logger::log!(Info, from = "script", "{result}"),
+            Err(err) => logger::log!(Error, from = "script", "{err}"),
+        }
+    }
+
+    /// Gives path to script file named `name`.
+    pub fn file_path(name: &str) -> PathBuf {
+        PathBuf::from(cfg::script::DIRECTORY)
+            .join(name)
+            .with_extension(cfg::script::FILE_EXTENSION)
+    }
+
+    /// Runs script file from [scripts directory][cfg::script::DIRECTORY].
+    pub fn run_file(&mut self, name: &str) -> Result<(), ScriptError> {
+        let _work_guard = logger::work("script", format!("running '{name}'"));
+
+        let ast = self.engine.compile_file(Self::file_path(name))?;
+        self.engine.run_ast_with_scope(&mut self.scope, &ast)?;
+
+        Ok(())
+    }
+
+    /// Lists names of script files.
+    pub fn list_files() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(cfg::script::DIRECTORY) else {
+            return vec![];
+        };
+
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == cfg::script::FILE_EXTENSION)
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+            .sorted()
+            .collect()
+    }
+
+    /// Syncs camera state and runs script files queued by `run_script`.
+    pub fn update(&mut self, camera: &Camera) {
+        *self.camera.write() = CameraState {
+            pos: camera.pos,
+            roll: camera.roll,
+            pitch: camera.pitch,
+            yaw: camera.yaw,
+        };
+
+        let queued_files = mem::take(&mut *self.queued_files.lock());
+
+        for name in queued_files {
+            self.run_file(&name)
+                .log_error("script", format!("failed to run '{name}'"));
+        }
+    }
+
+    /// Spawns window with list of script files.
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Scripts")
+            .always_auto_resize(true)
+            .build(|| {
+                ui.text(format!("Scripts from '{}'", cfg::script::DIRECTORY));
+
+                let mut to_run = None;
+
+                for name in self.file_names.iter() {
+                    if ui.button(name) {
+                        to_run = Some(name.clone());
+                    }
+                }
+
+                if let Some(name) = to_run {
+                    self.run_file(&name)
+                        .log_error("script", format!("failed to run '{name}'"));
+                }
+
+                if ui.button("Refresh") {
+                    self.file_names = Self::list_files();
+                }
+
+                if ui.button("Reset variables") {
+                    self.scope.clear();
+                }
+            });
+    }
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ScriptEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptEngine")
+            .field("scope", &self.scope)
+            .field("file_names", &self.file_names)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("{0}")]
+    Eval(#[from] Box<EvalAltResult>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_persist_between_lines() {
+        let mut engine = ScriptEngine::new();
+
+        assert!(engine.try_eval("let a = 21;").unwrap().is_unit());
+        let result = engine.try_eval("a * 2").unwrap();
+
+        assert_eq!(result.as_int(), Ok(42));
+    }
+
+    #[test]
+    fn voxel_ids_by_name_and_number() {
+        let mut engine = ScriptEngine::new();
+
+        let stone = engine.try_eval(r#"voxel_id("stone")"#).unwrap();
+        assert_eq!(stone.as_int(), Ok(voxels::STONE_VOXEL_DATA.id as INT));
+
+        assert!(engine.try_eval(r#"voxel_id("no such voxel")"#).is_err());
+        assert!(ScriptEngine::voxel_id(Dynamic::from(INT::MAX)).is_err());
+    }
+
+    #[test]
+    fn camera_state_is_visible() {
+        let mut engine = ScriptEngine::new();
+
+        engine.update(&Camera::new().with_position(1.0, 2.0, 3.0));
+        let pos = engine.try_eval("camera_pos()[1]").unwrap();
+
+        assert_eq!(pos.as_float(), Ok(2.0));
+    }
+
+    #[test]
+    fn example_script_compiles() {
+        let engine = ScriptEngine::new();
+        let path = PathBuf::from("../..").join(ScriptEngine::file_path("pillar"));
+
+        engine.engine.compile_file(path).unwrap();
+    }
+
+    #[test]
+    fn infinite_loop_is_stopped() {
+        let mut engine = ScriptEngine::new();
+        assert!(engine.try_eval("loop {}").is_err());
+    }
+}
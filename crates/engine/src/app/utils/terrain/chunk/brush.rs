@@ -0,0 +1,630 @@
+//!
+//! Brushes for terrain sculpting. Each [brush][Brush] computes [voxel changes][VoxelChange]
+//! that are applied through [`Command::Brush`][super::commands::Command::Brush].
+//!
+
+use {
+    super::history::VoxelChange,
+    crate::{
+        prelude::*,
+        terrain::voxel::{
+            self,
+            voxel_data::{Id, data::*},
+        },
+    },
+    noise::{NoiseFn, Perlin},
+};
+
+/// Shape with a voxel operation inside of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brush {
+    Sphere {
+        center: Int3,
+        radius: f32,
+        new_id: Id,
+    },
+
+    /// Vertical cylinder centered at `center`.
+    Cylinder {
+        center: Int3,
+        radius: f32,
+        height: f32,
+        new_id: Id,
+    },
+
+    Ellipsoid {
+        center: Int3,
+        radii: vec3,
+        new_id: Id,
+    },
+
+    /// Capsule around the segment `from..=to`.
+    Line {
+        from: Int3,
+        to: Int3,
+        radius: f32,
+        new_id: Id,
+    },
+
+    /// Walls of the box `pos_from..pos_to`.
+    HollowBox {
+        pos_from: Int3,
+        pos_to: Int3,
+        thickness: i32,
+        new_id: Id,
+    },
+
+    /// Sphere that replaces only voxels with `old_id`.
+    Replace {
+        center: Int3,
+        radius: f32,
+        old_id: Id,
+        new_id: Id,
+    },
+
+    /// Sphere that removes lonely voxels and fills holes. `strength` is in `0.0..=1.0`.
+    Smooth {
+        center: Int3,
+        radius: f32,
+        strength: f32,
+    },
+
+    /// Sphere that moves voxels by a noise field. `strength` is a maximal offset in voxels.
+    NoiseDisplace {
+        center: Int3,
+        radius: f32,
+        strength: f32,
+        seed: u32,
+    },
+}
+
+impl Brush {
+    /// Noise frequency of [`Brush::NoiseDisplace`].
+    pub const NOISE_FREQUENCY: f64 = 0.15;
+
+    /// Gives range of positions that brush can affect.
+    pub fn bounds(&self) -> (Int3, Int3) {
+        match *self {
+            Self::Sphere { center, radius, .. }
+            | Self::Replace { center, radius, .. }
+            | Self::Smooth { center, radius, .. }
+            | Self::NoiseDisplace { center, radius, .. } => {
+                Self::radius_bounds(center, vec3::all(radius))
+            }
+
+            Self::Cylinder {
+                center,
+                radius,
+                height,
+                ..
+            } => Self::radius_bounds(center, vec3::new(radius, 0.5 * height, radius)),
+
+            Self::Ellipsoid { center, radii, .. } => Self::radius_bounds(center, radii),
+
+            Self::Line {
+                from, to, radius, ..
+            } => {
+                let extent = Int3::all(radius.ceil() as i32);
+                let min = Int3::new(from.x.min(to.x), from.y.min(to.y), from.z.min(to.z));
+                let max = Int3::new(from.x.max(to.x), from.y.max(to.y), from.z.max(to.z));
+
+                (min - extent, max + extent + Int3::ONE)
+            }
+
+            Self::HollowBox {
+                pos_from, pos_to, ..
+            } => (pos_from, pos_to),
+        }
+    }
+
+    fn radius_bounds(center: Int3, radii: vec3) -> (Int3, Int3) {
+        let extent = Int3::new(
+            radii.x.ceil() as i32,
+            radii.y.ceil() as i32,
+            radii.z.ceil() as i32,
+        );
+
+        (center - extent, center + extent + Int3::ONE)
+    }
+
+    /// Checks that `pos` is inside of brush shape.
+    pub fn contains(&self, pos: Int3) -> bool {
+        match *self {
+            Self::Sphere { center, radius, .. }
+            | Self::Replace { center, radius, .. }
+            | Self::Smooth { center, radius, .. }
+            | Self::NoiseDisplace { center, radius, .. } => {
+                vec3::from(pos - center).sqr() <= radius * radius
+            }
+
+            Self::Cylinder {
+                center,
+                radius,
+                height,
+                ..
+            } => {
+                let offset = vec3::from(pos - center);
+                offset.xz().sqr() <= radius * radius && offset.y.abs() <= 0.5 * height
+            }
+
+            Self::Ellipsoid { center, radii, .. } => {
+                let offset = vec3::from(pos - center);
+                let scaled = vec3::new(offset.x / radii.x, offset.y / radii.y, offset.z / radii.z);
+                scaled.sqr() <= 1.0
+            }
+
+            Self::Line {
+                from, to, radius, ..
+            } => {
+                let (from, to, pos) = (vec3::from(from), vec3::from(to), vec3::from(pos));
+                let dir = to - from;
+
+                let param = match dir.sqr() {
+                    0.0 => 0.0,
+                    sqr_len => ((pos - from).dot(dir) / sqr_len).clamp(0.0, 1.0),
+                };
+
+                (from + dir * param - pos).sqr() <= radius * radius
+            }
+
+            Self::HollowBox {
+                pos_from,
+                pos_to,
+                thickness,
+                ..
+            } => {
+                let inner_from = pos_from + Int3::all(thickness);
+                let inner_to = pos_to - Int3::all(thickness);
+
+                let is_in = |from: Int3, to: Int3| {
+                    from.x <= pos.x
+                        && pos.x < to.x
+                        && from.y <= pos.y
+                        && pos.y < to.y
+                        && from.z <= pos.z
+                        && pos.z < to.z
+                };
+
+                is_in(pos_from, pos_to) && !is_in(inner_from, inner_to)
+            }
+        }
+    }
+
+    /// Computes changes made by the brush. `get_id` gives current voxel ids or [`None`]
+    /// if position is outside of the world.
+    pub fn changes(&self, get_id: impl Fn(Int3) -> Option<Id>) -> Vec<VoxelChange> {
+        let (pos_from, pos_to) = self.bounds();
+        let noise = match *self {
+            Self::NoiseDisplace { seed, .. } => Some(Perlin::new(seed)),
+            _ => None,
+        };
+
+        SpaceIter::new(pos_from..pos_to)
+            .filter(|&pos| self.contains(pos))
+            .filter_map(|pos| {
+                let old_id = get_id(pos)?;
+                let new_id = self.new_id(pos, old_id, &get_id, noise.as_ref());
+
+                // Block entities are taken by the chunk array when changes are applied.
+                (old_id != new_id).then_some(VoxelChange {
+                    pos,
+                    old_id,
+                    new_id,
+                    old_block_entity: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Gives new id of voxel at `pos` that is inside of brush shape.
+    fn new_id(
+        &self,
+        pos: Int3,
+        old_id: Id,
+        get_id: &impl Fn(Int3) -> Option<Id>,
+        noise: Option<&Perlin>,
+    ) -> Id {
+        match *self {
+            Self::Sphere { new_id, .. }
+            | Self::Cylinder { new_id, .. }
+            | Self::Ellipsoid { new_id, .. }
+            | Self::Line { new_id, .. }
+            | Self::HollowBox { new_id, .. } => new_id,
+
+            Self::Replace {
+                old_id: target_id,
+                new_id,
+                ..
+            } => match old_id == target_id {
+                true => new_id,
+                false => old_id,
+            },
+
+            Self::Smooth { strength, .. } => {
+                let mut solid_freqs = HashMap::<Id, usize>::new();
+
+                for neighbor in SpaceIter::new(pos - Int3::ONE..=pos + Int3::ONE) {
+                    match get_id(neighbor) {
+                        Some(id) if id != AIR_VOXEL_DATA.id => {
+                            *solid_freqs.entry(id).or_default() += 1
+                        }
+                        _ => (),
+                    }
+                }
+
+                let n_solid = solid_freqs.values().sum::<usize>();
+                let solid_ratio = n_solid as f32 / 27.0;
+                let threashold = 0.5 * strength.clamp(0.0, 1.0);
+
+                if old_id != AIR_VOXEL_DATA.id && solid_ratio < threashold {
+                    AIR_VOXEL_DATA.id
+                } else if old_id == AIR_VOXEL_DATA.id && 1.0 - threashold < solid_ratio {
+                    solid_freqs
+                        .into_iter()
+                        .max_by_key(|&(id, freq)| (freq, id))
+                        .map_or(old_id, |(id, _)| id)
+                } else {
+                    old_id
+                }
+            }
+
+            Self::NoiseDisplace {
+                center,
+                radius,
+                strength,
+                ..
+            } => {
+                let noise = noise.expect("noise should be given to displace brush");
+                let falloff = 1.0 - vec3::from(pos - center).len() / radius.max(f32::EPSILON);
+
+                let sample = |shift: f64| {
+                    let point = [
+                        pos.x as f64 * Self::NOISE_FREQUENCY + shift,
+                        pos.y as f64 * Self::NOISE_FREQUENCY,
+                        pos.z as f64 * Self::NOISE_FREQUENCY,
+                    ];
+
+                    (noise.get(point) as f32 * strength * falloff).round() as i32
+                };
+
+                let offset = Int3::new(sample(0.0), sample(100.0), sample(200.0));
+
+                get_id(pos + offset)
+                    .filter(|&id| voxel::is_id_valid(id))
+                    .unwrap_or(AIR_VOXEL_DATA.id)
+            }
+        }
+    }
+}
+
+/// Kind of [brush][Brush] selected in the brush panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BrushKind {
+    #[default]
+    Sphere,
+    Cylinder,
+    Ellipsoid,
+    Line,
+    HollowBox,
+    Replace,
+    Smooth,
+    NoiseDisplace,
+}
+
+impl BrushKind {
+    pub const ALL: [Self; 8] = [
+        Self::Sphere,
+        Self::Cylinder,
+        Self::Ellipsoid,
+        Self::Line,
+        Self::HollowBox,
+        Self::Replace,
+        Self::Smooth,
+        Self::NoiseDisplace,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sphere => "Sphere",
+            Self::Cylinder => "Cylinder",
+            Self::Ellipsoid => "Ellipsoid",
+            Self::Line => "Line",
+            Self::HollowBox => "Hollow box",
+            Self::Replace => "Replace",
+            Self::Smooth => "Smooth",
+            Self::NoiseDisplace => "Noise displace",
+        }
+    }
+}
+
+/// Brush settings controlled by the brush panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BrushTool {
+    pub is_enabled: bool,
+    pub kind: BrushKind,
+    pub radius: f32,
+    pub strength: f32,
+    pub height: f32,
+    pub radii: vec3,
+    pub thickness: i32,
+    pub old_id: Id,
+    pub new_id: Id,
+    pub seed: u32,
+
+    /// First end of [`Brush::Line`] set by the previous click.
+    pub line_start: Option<Int3>,
+}
+
+impl Default for BrushTool {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            kind: BrushKind::default(),
+            radius: 4.0,
+            strength: 0.5,
+            height: 8.0,
+            radii: vec3::new(6.0, 3.0, 4.0),
+            thickness: 1,
+            old_id: STONE_VOXEL_DATA.id,
+            new_id: STONE_VOXEL_DATA.id,
+            seed: 0,
+            line_start: None,
+        }
+    }
+}
+
+impl BrushTool {
+    pub const MAX_RADIUS: f32 = 32.0;
+
+    /// Builds [brush][Brush] aimed at `target`.
+    pub fn brush_at(&self, target: Int3) -> Brush {
+        let (center, radius, new_id) = (target, self.radius, self.new_id);
+
+        match self.kind {
+            BrushKind::Sphere => Brush::Sphere {
+                center,
+                radius,
+                new_id,
+            },
+
+            BrushKind::Cylinder => Brush::Cylinder {
+                center,
+                radius,
+                height: self.height,
+                new_id,
+            },
+
+            BrushKind::Ellipsoid => Brush::Ellipsoid {
+                center,
+                radii: self.radii,
+                new_id,
+            },
+
+            BrushKind::Line => Brush::Line {
+                from: self.line_start.unwrap_or(target),
+                to: target,
+                radius,
+                new_id,
+            },
+
+            BrushKind::HollowBox => {
+                let extent = Int3::all(radius.round() as i32);
+
+                Brush::HollowBox {
+                    pos_from: center - extent,
+                    pos_to: center + extent + Int3::ONE,
+                    thickness: self.thickness,
+                    new_id,
+                }
+            }
+
+            BrushKind::Replace => Brush::Replace {
+                center,
+                radius,
+                old_id: self.old_id,
+                new_id,
+            },
+
+            BrushKind::Smooth => Brush::Smooth {
+                center,
+                radius,
+                strength: self.strength,
+            },
+
+            BrushKind::NoiseDisplace => Brush::NoiseDisplace {
+                center,
+                radius,
+                strength: self.strength * radius,
+                seed: self.seed,
+            },
+        }
+    }
+
+    /// Gives brush to apply on click. [`Brush::Line`] needs two clicks so
+    /// the first one only remembers the start.
+    pub fn click(&mut self, target: Int3) -> Option<Brush> {
+        if self.kind == BrushKind::Line && self.line_start.is_none() {
+            self.line_start = Some(target);
+            return None;
+        }
+
+        let brush = self.brush_at(target);
+        self.line_start = None;
+
+        Some(brush)
+    }
+
+    /// Gives brush to preview at voxel `cursor`.
+    pub fn preview(&self, cursor: Option<Int3>) -> Option<Brush> {
+        self.is_enabled
+            .then_some(cursor)
+            .flatten()
+            .map(|target| self.brush_at(target))
+    }
+
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Brush").always_auto_resize(true).build(|| {
+            ui.checkbox("Enabled", &mut self.is_enabled);
+
+            let mut kind_idx = BrushKind::ALL
+                .iter()
+                .position(|&kind| kind == self.kind)
+                .unwrap_or_default();
+
+            if ui.combo("Kind", &mut kind_idx, &BrushKind::ALL, |kind| {
+                kind.name().into()
+            }) {
+                self.kind = BrushKind::ALL[kind_idx];
+                self.line_start = None;
+            }
+
+            ui.slider("Radius", 0.5, Self::MAX_RADIUS, &mut self.radius);
+
+            match self.kind {
+                BrushKind::Cylinder => {
+                    ui.slider("Height", 1.0, 2.0 * Self::MAX_RADIUS, &mut self.height);
+                }
+
+                BrushKind::Ellipsoid => {
+                    let mut radii = self.radii.as_array();
+                    if ui.input_float3("Radii", &mut radii).build() {
+                        self.radii = vec3::from(radii.map(|r| r.clamp(0.5, Self::MAX_RADIUS)));
+                    }
+                }
+
+                BrushKind::HollowBox => {
+                    ui.slider("Thickness", 1, Self::MAX_RADIUS as i32, &mut self.thickness);
+                }
+
+                BrushKind::Smooth | BrushKind::NoiseDisplace => {
+                    ui.slider("Strength", 0.0, 1.0, &mut self.strength);
+                }
+
+                BrushKind::Replace => {
+                    voxel::id_combo(ui, "Replace", &mut self.old_id);
+                }
+
+                BrushKind::Sphere | BrushKind::Line => (),
+            }
+
+            if self.kind == BrushKind::NoiseDisplace {
+                ui.input_scalar("Seed", &mut self.seed).build();
+            }
+
+            if !matches!(self.kind, BrushKind::Smooth | BrushKind::NoiseDisplace) {
+                voxel::id_combo(ui, "Voxel", &mut self.new_id);
+            }
+
+            if let Some(start) = self.line_start {
+                ui.text(format!("Line starts at {start}"));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(brush: Brush) -> usize {
+        brush
+            .changes(|_| Some(AIR_VOXEL_DATA.id))
+            .into_iter()
+            .filter(|change| change.new_id == STONE_VOXEL_DATA.id)
+            .count()
+    }
+
+    #[test]
+    fn shapes() {
+        let new_id = STONE_VOXEL_DATA.id;
+
+        assert_eq!(
+            count(Brush::Sphere {
+                center: Int3::ZERO,
+                radius: 1.0,
+                new_id,
+            }),
+            7,
+        );
+
+        assert_eq!(
+            count(Brush::Cylinder {
+                center: Int3::ZERO,
+                radius: 1.0,
+                height: 2.0,
+                new_id,
+            }),
+            15,
+        );
+
+        assert_eq!(
+            count(Brush::Line {
+                from: Int3::ZERO,
+                to: Int3::new(5, 0, 0),
+                radius: 0.0,
+                new_id,
+            }),
+            6,
+        );
+
+        assert_eq!(
+            count(Brush::HollowBox {
+                pos_from: Int3::ZERO,
+                pos_to: Int3::all(4),
+                thickness: 1,
+                new_id,
+            }),
+            64 - 8,
+        );
+    }
+
+    #[test]
+    fn replace_keeps_other_ids() {
+        let brush = Brush::Replace {
+            center: Int3::ZERO,
+            radius: 2.0,
+            old_id: DIRT_VOXEL_DATA.id,
+            new_id: STONE_VOXEL_DATA.id,
+        };
+
+        let changes = brush.changes(|pos| {
+            Some(match pos.y < 0 {
+                true => DIRT_VOXEL_DATA.id,
+                false => AIR_VOXEL_DATA.id,
+            })
+        });
+
+        assert!(!changes.is_empty());
+        assert!(changes.iter().all(|change| change.pos.y < 0
+            && change.old_id == DIRT_VOXEL_DATA.id
+            && change.new_id == STONE_VOXEL_DATA.id));
+    }
+
+    #[test]
+    fn smooth_removes_lonely_voxel() {
+        let brush = Brush::Smooth {
+            center: Int3::ZERO,
+            radius: 3.0,
+            strength: 1.0,
+        };
+
+        let changes = brush.changes(|pos| {
+            Some(match pos == Int3::ZERO {
+                true => STONE_VOXEL_DATA.id,
+                false => AIR_VOXEL_DATA.id,
+            })
+        });
+
+        assert_eq!(
+            changes,
+            vec![VoxelChange {
+                pos: Int3::ZERO,
+                old_id: STONE_VOXEL_DATA.id,
+                new_id: AIR_VOXEL_DATA.id,
+                old_block_entity: None,
+            }],
+        );
+    }
+}
@@ -1,20 +1,44 @@
 use {
     crate::{
-        graphics::camera::Camera,
+        app::utils::cfg::local_light as local_light_cfg,
+        entity::{
+            EntityWorld,
+            components::{EntityKind, EntityRecord},
+        },
+        graphics::{
+            camera::Camera, light::LocalLight, render_graph::PassKind, shadow::ShadowCascades,
+        },
+        inventory::{GameMode, Inventory},
+        physics::{collision, timestep::FixedTimestep},
         prelude::*,
         saves::Save,
         terrain::{
             chunk::{
                 EditError, Id, Sides,
+                brush::BrushTool,
+                history::{Edit, EditHistory, VoxelChange},
+                light::{self, ChunkLightSpace, Light, LightSpace},
                 mesh::ChunkMesh,
                 prelude::*,
+                raycast::{self, RayHit},
+                schedule::UpdateQueue,
+                schematic::{RegionTool, Schematic},
                 tasks::{FullTask, GenTask, LowTask, PartitionTask, Task},
+                visibility::{self, CullingStats},
+            },
+            voxel::{
+                self, Voxel,
+                block_entity::{BlockEntities, BlockEntity, Spawner},
+                fluid::{Fluid, FluidKind, FluidSpace},
+                voxel_data::data::*,
+                world_tick::{self, WorldSpace},
             },
-            voxel::{self, Voxel, voxel_data::data::*},
         },
+        time::clock::WorldClock,
     },
     glium::{self as gl, backend::Facade},
     math_linear::math::ray::space_3d::Line,
+    rand::{Rng, SeedableRng, rngs::StdRng},
     std::{io, mem, sync::Mutex},
     tokio::task::{JoinError, JoinHandle},
 };
@@ -25,6 +49,11 @@ pub static GENERATOR_SIZES: Mutex<[usize; 3]> = Mutex::new(USize3::ZERO.as_array
 enum ChunkArrSaveType {
     Sizes,
     Array,
+    BlockEntities,
+    Entities,
+    Inventory,
+    Clock,
+    ScheduledUpdates,
 }
 
 impl From<ChunkArrSaveType> for u64 {
@@ -33,7 +62,19 @@ impl From<ChunkArrSaveType> for u64 {
     }
 }
 
-pub type ReadingHandle = JoinHandle<io::Result<(USize3, Vec<(Vec<Atomic<Id>>, FillType)>)>>;
+pub type ReadingHandle = JoinHandle<io::Result<SaveData>>;
+
+/// Data of [chunk array][ChunkArray] read from a save.
+#[derive(Debug)]
+pub struct SaveData {
+    pub sizes: USize3,
+    pub chunks: Vec<(Vec<Atomic<Id>>, FillType)>,
+    pub block_entities: Vec<BlockEntities>,
+    pub entities: Vec<Vec<EntityRecord>>,
+    pub inventory: Option<Inventory>,
+    pub clock: Option<WorldClock>,
+    pub scheduled_updates: Option<UpdateQueue>,
+}
 
 /// Represents 3d array of [`Chunk`]s. Can control their mesh generation, etc.
 #[derive(Debug)]
@@ -49,6 +90,43 @@ pub struct ChunkArray {
 
     pub lod_threashold: f32,
 
+    /// Chunks hidden behind terrain are not drawn, see [`visibility`].
+    pub is_occlusion_culling_enabled: bool,
+    pub culling_stats: CullingStats,
+    /// Indices of [chunks][Chunk] ready to be drawn, nearest to the camera first.
+    pub draw_order: Vec<usize>,
+    /// [Chunks][Chunk] seen from the camera in the current frame by index.
+    pub is_visible: Vec<bool>,
+
+    pub history: EditHistory,
+    pub brush_tool: BrushTool,
+    pub region_tool: RegionTool,
+
+    /// Voxel hit by the camera ray.
+    pub ray_hit: Option<RayHit>,
+    /// Voxels placed by right click.
+    pub inventory: Inventory,
+
+    /// Mobs, dropped items and projectiles.
+    pub entities: EntityWorld,
+
+    /// Time of day.
+    pub clock: WorldClock,
+
+    /// Voxels which light has changed since the last remesh.
+    pub light_changes: HashSet<Int3>,
+    /// Emissive voxels, they spawn [local lights][LocalLight].
+    pub emitters: HashSet<Int3>,
+
+    /// Scheduled [voxel updates][world_tick].
+    pub scheduled_updates: UpdateQueue,
+    /// Splits frame time into world ticks.
+    pub tick_timestep: FixedTimestep,
+    /// Voxels changed by world ticks since the last remesh.
+    pub tick_changes: HashSet<Int3>,
+    /// Picks voxels for random ticks.
+    pub tick_rng: StdRng,
+
     pub reading_handle: Option<ReadingHandle>,
     pub saving_handle: Option<JoinHandle<io::Result<()>>>,
 }
@@ -64,6 +142,23 @@ impl Default for ChunkArray {
             partition_tasks: Default::default(),
             voxels_gen_tasks: Default::default(),
             lod_threashold: 5.8,
+            is_occlusion_culling_enabled: true,
+            culling_stats: Default::default(),
+            draw_order: Default::default(),
+            is_visible: Default::default(),
+            history: Default::default(),
+            brush_tool: Default::default(),
+            region_tool: Default::default(),
+            ray_hit: None,
+            inventory: Default::default(),
+            entities: Default::default(),
+            clock: Default::default(),
+            light_changes: Default::default(),
+            emitters: Default::default(),
+            scheduled_updates: Default::default(),
+            tick_timestep: FixedTimestep::new(Self::TICK_DURATION, Self::MAX_TICKS_PER_FRAME),
+            tick_changes: Default::default(),
+            tick_rng: StdRng::seed_from_u64(Self::TICK_RNG_SEED),
             reading_handle: None,
             saving_handle: None,
         }
@@ -71,7 +166,35 @@ impl Default for ChunkArray {
 }
 
 impl ChunkArray {
-    const MAX_TRACE_STEPS: usize = 1024;
+    const MAX_TRACE_DISTANCE: f32 = 128.0;
+
+    /// Spawners stop spawning if there are that many entities in their chunk.
+    const MAX_SPAWNER_ENTITIES_PER_CHUNK: usize = 8;
+
+    /// Distance below the array where entities are despawned.
+    const ENTITY_FALL_DEPTH: f32 = 64.0;
+
+    /// Duration of one world tick in seconds.
+    const TICK_DURATION: f32 = 0.05;
+
+    /// World ticks run in one frame at most, the rest is skipped.
+    const MAX_TICKS_PER_FRAME: usize = 4;
+
+    /// Voxels are updated in chunks that far from the camera chunk.
+    const TICK_ACTIVE_DISTANCE: i32 = 2;
+
+    /// Voxels randomly ticked in each active chunk per world tick,
+    /// 3 per 16³ voxels so that ticks hit a voxel as often for any chunk size.
+    const RANDOM_TICKS_PER_CHUNK: usize = 3 * Chunk::VOLUME / 4096;
+
+    /// Seed of random ticks, so that same world evolves the same way.
+    const TICK_RNG_SEED: u64 = 39;
+
+    /// Upwards speed of items dropped from broken voxels.
+    const ITEM_DROP_SPEED: f32 = 4.0;
+
+    /// Distance from the player to items it collects.
+    const ITEM_PICKUP_RADIUS: f32 = 2.0;
 
     /// Generates new chunks.
     ///
@@ -112,12 +235,16 @@ impl ChunkArray {
             .map(|_| Rc::new(RefCell::new(ChunkMesh::default())))
             .collect();
 
-        Ok(Self {
+        let mut result = Self {
             chunks,
             sizes,
             meshes,
             ..Default::default()
-        })
+        };
+
+        result.light_all_chunks();
+
+        Ok(result)
     }
 
     /// Constructs [`ChunkArray`] with empty chunks.
@@ -164,13 +291,18 @@ impl ChunkArray {
         Self::default()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_to_file(
         sizes: USize3,
         chunks: Vec<ChunkRef>,
+        entities: Vec<Vec<EntityRecord>>,
+        inventory: Inventory,
+        clock: WorldClock,
+        scheduled_updates: UpdateQueue,
         save_name: impl Into<String>,
-        save_path: &'static str,
+        save_path: impl Into<String>,
     ) -> io::Result<()> {
-        let save_name = save_name.into();
+        let (save_name, save_path) = (save_name.into(), save_path.into());
 
         let _work_guard = logger::work(
             "chunk-array",
@@ -203,7 +335,7 @@ impl ChunkArray {
         let loading = loading::start_new("Chunks saving");
 
         Save::builder(save_name.clone())
-            .create(save_path)
+            .create(&save_path)
             .await?
             .write(&sizes, ChunkArrSaveType::Sizes)
             .await
@@ -217,16 +349,32 @@ impl ChunkArray {
                 }
             })
             .await
+            .pointer_array(volume, ChunkArrSaveType::BlockEntities, |i| {
+                let chunks = &chunks;
+                async move { chunks[i].block_entities.read().as_bytes() }
+            })
+            .await
+            .pointer_array(volume, ChunkArrSaveType::Entities, |i| {
+                let entities = &entities;
+                async move { entities[i].as_bytes() }
+            })
+            .await
+            .pointer(inventory.as_bytes(), ChunkArrSaveType::Inventory)
+            .await
+            .pointer(clock.as_bytes(), ChunkArrSaveType::Clock)
+            .await
+            .pointer(
+                scheduled_updates.as_bytes(),
+                ChunkArrSaveType::ScheduledUpdates,
+            )
+            .await
             .save()
             .await?;
 
         Ok(())
     }
 
-    pub async fn read_from_file(
-        save_name: &str,
-        save_path: &str,
-    ) -> io::Result<(USize3, Vec<(Vec<Atomic<Id>>, FillType)>)> {
+    pub async fn read_from_file(save_name: &str, save_path: &str) -> io::Result<SaveData> {
         let _work_guard = logger::work(
             "chunk-array",
             format!("reading chunks from {save_name} in {save_path}"),
@@ -249,7 +397,68 @@ impl ChunkArray {
             })
             .await;
 
-        Ok((sizes, chunks))
+        // Saves made before block entities were introduced have no such data.
+        let block_entities = if save.contains(ChunkArrSaveType::BlockEntities) {
+            save.read_pointer_array(ChunkArrSaveType::BlockEntities, |_, bytes| async move {
+                BlockEntities::from_bytes(&bytes).expect("failed to read block entities")
+            })
+            .await
+        } else {
+            vec![BlockEntities::new(); Self::volume(sizes)]
+        };
+
+        // So do saves made before entities were introduced.
+        let entities = if save.contains(ChunkArrSaveType::Entities) {
+            save.read_pointer_array(ChunkArrSaveType::Entities, |_, bytes| async move {
+                Vec::<EntityRecord>::from_bytes(&bytes).expect("failed to read entities")
+            })
+            .await
+        } else {
+            vec![vec![]; Self::volume(sizes)]
+        };
+
+        let inventory = if save.contains(ChunkArrSaveType::Inventory) {
+            let inventory = save
+                .read_from_pointer(ChunkArrSaveType::Inventory, Inventory::from_bytes)
+                .await
+                .expect("failed to read inventory");
+
+            Some(inventory)
+        } else {
+            None
+        };
+
+        let clock = if save.contains(ChunkArrSaveType::Clock) {
+            let clock = save
+                .read_from_pointer(ChunkArrSaveType::Clock, WorldClock::from_bytes)
+                .await
+                .expect("failed to read world clock");
+
+            Some(clock)
+        } else {
+            None
+        };
+
+        let scheduled_updates = if save.contains(ChunkArrSaveType::ScheduledUpdates) {
+            let queue = save
+                .read_from_pointer(ChunkArrSaveType::ScheduledUpdates, UpdateQueue::from_bytes)
+                .await
+                .expect("failed to read scheduled updates");
+
+            Some(queue)
+        } else {
+            None
+        };
+
+        Ok(SaveData {
+            sizes,
+            chunks,
+            block_entities,
+            entities,
+            inventory,
+            clock,
+            scheduled_updates,
+        })
     }
 
     /// Reinterprets [chunk][Chunk] as bytes. It uses Huffman's compresstion.
@@ -347,9 +556,147 @@ impl ChunkArray {
         // We know that `chunk_idx` is valid so we can get-by-index.
         let old_id = chunk.set_voxel(pos, new_id)?;
 
+        if old_id != new_id {
+            self.relight([pos]);
+        }
+
         Ok(old_id)
     }
 
+    /// Updates [light][light::Light] after voxels at `positions` have changed.
+    pub fn relight(&mut self, positions: impl IntoIterator<Item = Int3>) {
+        let positions = positions.into_iter().collect_vec();
+
+        for &pos in &positions {
+            let is_emissive = self
+                .get_voxel(pos)
+                .is_some_and(|voxel| voxel.data.emission != 0);
+
+            match is_emissive {
+                true => self.emitters.insert(pos),
+                false => self.emitters.remove(&pos),
+            };
+        }
+
+        let mut space = ChunkLightSpace::new(&self.chunks, self.sizes);
+        light::relight(&mut space, positions);
+
+        self.light_changes.extend(space.changed);
+    }
+
+    /// Lights generated [chunk][Chunk] at `chunk_pos` from scratch. Its light also
+    /// spreads into lit neighbors and light of neighbors spreads into it.
+    pub fn light_chunk(&mut self, chunk_pos: Int3) {
+        let Some(idx) = Self::pos_to_idx(self.sizes, chunk_pos) else {
+            return;
+        };
+
+        if !self.chunks[idx].is_generated() {
+            return;
+        }
+
+        // * Safety:
+        // * Safe, because the chunk is lit right after it's replaced
+        // * and reader tasks of it are dropped then.
+        let chunk = unsafe {
+            Arc::as_ptr(&self.chunks[idx])
+                .cast_mut()
+                .as_mut()
+                .unwrap_unchecked()
+        };
+
+        chunk.reset_light();
+
+        self.emitters
+            .retain(|&pos| Chunk::local_pos(pos) != chunk_pos);
+
+        // Neither neighbor nor sky light gets into it.
+        if chunk.is_dark_filled() {
+            return;
+        }
+
+        let mut space = ChunkLightSpace::new(&self.chunks, self.sizes);
+        space.untracked_chunk = Some(chunk_pos);
+
+        let mut sources = vec![];
+
+        let has_emissive_voxels = chunk
+            .fill_id()
+            .is_none_or(|id| VOXEL_DATA[id as usize].emission != 0);
+
+        if has_emissive_voxels {
+            for pos in Chunk::global_pos_iter(chunk_pos) {
+                let emission = space.emission(pos);
+
+                if emission != 0 {
+                    space.set_light(pos, light::Light::new(0, emission));
+                    sources.push(pos);
+                }
+            }
+
+            self.emitters.extend(sources.iter().copied());
+        }
+
+        // Light comes from neighbors and the sky through the chunk border.
+        for local_pos in iterator::CubeBorder::new(Chunk::SIZE as i32) {
+            let pos = Chunk::local_to_global_pos(chunk_pos, local_pos);
+
+            for offset in
+                iterator::offsets_from_border(local_pos, Int3::ZERO..Int3::from(Chunk::SIZES))
+            {
+                if space
+                    .get_light(pos + offset)
+                    .is_some_and(|light| light != light::Light::DARK)
+                {
+                    sources.push(pos + offset);
+                }
+            }
+        }
+
+        light::propagate_from(&mut space, sources);
+
+        self.light_changes.extend(space.changed);
+    }
+
+    /// Lights all generated [chunks][Chunk] from the top to the bottom.
+    pub fn light_all_chunks(&mut self) {
+        self.emitters.clear();
+
+        let chunk_poses = Self::pos_iter(self.sizes)
+            .sorted_by_key(|pos| -pos.y)
+            .collect_vec();
+
+        for chunk_pos in chunk_poses {
+            self.light_chunk(chunk_pos);
+        }
+
+        // There are no meshes to update yet.
+        self.light_changes.clear();
+    }
+
+    /// Gives point [lights][LocalLight] of emissive voxels near `cam_pos`.
+    pub fn emitter_lights(&self, cam_pos: vec3) -> impl Iterator<Item = LocalLight> + '_ {
+        self.emitters.iter().filter_map(move |&pos| {
+            let center = vec3::from(pos);
+
+            if (center - cam_pos).len() > local_light_cfg::EMITTER_DISTANCE {
+                return None;
+            }
+
+            let data = self.get_voxel(pos)?.data;
+            let emission = data.emission as f32;
+
+            (emission != 0.0).then(|| {
+                LocalLight::point(
+                    center,
+                    data.avarage_color,
+                    local_light_cfg::EMITTER_INTENSITY * emission / Light::MAX_LEVEL as f32,
+                    emission,
+                )
+            })
+        })
+    }
+
     /// Gives voxel if it is in the [array][ChunkArray].
     pub fn get_voxel(&self, pos: Int3) -> Option<Voxel> {
         let chunk_pos = Chunk::local_pos(pos);
@@ -362,12 +709,39 @@ impl ChunkArray {
         }
     }
 
+    /// Checks that voxel at `pos` is either generated or outside of the array.
+    pub fn is_voxel_ready(&self, pos: Int3) -> bool {
+        Self::pos_to_idx(self.sizes, Chunk::local_pos(pos))
+            .is_none_or(|idx| self.chunks[idx].is_generated())
+    }
+
+    /// Checks if voxel at `pos` blocks movement.
+    pub fn is_voxel_solid(&self, pos: Int3) -> bool {
+        self.get_voxel(pos)
+            .is_some_and(|voxel| !voxel.is_air() && voxel.data.fluid.is_none())
+    }
+
     /// Fills volume of voxels to same [id][Id] and returnes `is_changed`.
     pub fn fill_voxels(
         &mut self,
         pos_from: Int3,
         pos_to: Int3,
         new_id: Id,
+    ) -> Result<bool, EditError> {
+        if !voxel::is_id_valid(new_id) {
+            return Err(EditError::InvalidId(new_id));
+        }
+
+        self.fill_voxels_with(pos_from, pos_to, |_| new_id)
+    }
+
+    /// Fills volume of voxels to [ids][Id] given by `new_id` from voxel position
+    /// and returnes `is_changed`. Ids given by `new_id` should be valid.
+    pub fn fill_voxels_with(
+        &mut self,
+        pos_from: Int3,
+        pos_to: Int3,
+        mut new_id: impl FnMut(Int3) -> Id,
     ) -> Result<bool, EditError> {
         let chunk_pos_from = Chunk::local_pos(pos_from);
         let chunk_pos_to = Chunk::local_pos(pos_to + Int3::from(Chunk::SIZES) - Int3::ONE);
@@ -405,11 +779,13 @@ impl ChunkArray {
                     .unwrap_unchecked()
             };
 
-            let chunk_changed = chunk.fill_voxels(pos_from, pos_to, new_id)?;
+            let chunk_changed = chunk.fill_voxels_with(pos_from, pos_to, &mut new_id)?;
 
             if chunk_changed {
                 is_changed = true;
 
+                self.relight(SpaceIter::new(pos_from..pos_to));
+
                 for idx in Self::get_adj_chunks_idxs(self.sizes, chunk_pos)
                     .as_array()
                     .into_iter()
@@ -423,6 +799,388 @@ impl ChunkArray {
         Ok(is_changed)
     }
 
+    /// Gives a copy of [block entity][BlockEntity] at `pos` if there is one.
+    pub fn get_block_entity(&self, pos: Int3) -> Option<BlockEntity> {
+        let chunk_idx = Self::pos_to_idx(self.sizes, Chunk::local_pos(pos))?;
+        self.chunks[chunk_idx].get_block_entity(pos)
+    }
+
+    /// Gives copies of [block entities][BlockEntity] in `pos_from..pos_to` by their positions.
+    pub fn get_block_entities(&self, pos_from: Int3, pos_to: Int3) -> Vec<(Int3, BlockEntity)> {
+        let chunk_pos_from = Chunk::local_pos(pos_from);
+        let chunk_pos_to = Chunk::local_pos(pos_to - Int3::ONE) + Int3::ONE;
+
+        SpaceIter::new(chunk_pos_from..chunk_pos_to)
+            .filter_map(|chunk_pos| Self::pos_to_idx(self.sizes, chunk_pos))
+            .flat_map(|idx| {
+                let chunk_pos = self.chunks[idx].pos.load(Relaxed);
+
+                self.chunks[idx]
+                    .block_entities
+                    .read()
+                    .iter()
+                    .map(|(&local_pos, block_entity)| {
+                        (
+                            Chunk::local_to_global_pos(chunk_pos, local_pos),
+                            block_entity.clone(),
+                        )
+                    })
+                    .filter(|(pos, _)| {
+                        pos_from.x <= pos.x
+                            && pos_from.y <= pos.y
+                            && pos_from.z <= pos.z
+                            && pos.x < pos_to.x
+                            && pos.y < pos_to.y
+                            && pos.z < pos_to.z
+                    })
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// Replaces [block entity][BlockEntity] at `pos` and returns the old one.
+    /// # Error
+    /// Returns [`Err`] if `pos` is not in this [chunk array][ChunkArray] or
+    /// voxel at `pos` can't carry such block entity.
+    pub fn set_block_entity(
+        &self,
+        pos: Int3,
+        block_entity: BlockEntity,
+    ) -> Result<Option<BlockEntity>, EditError> {
+        let chunk_idx = Self::pos_to_idx(self.sizes, Chunk::local_pos(pos))
+            .ok_or(EditError::PosIdConversion(pos))?;
+
+        self.chunks[chunk_idx].set_block_entity(pos, block_entity)
+    }
+
+    /// Advances [block entities][BlockEntity] of all [chunks][Chunk] by `dt` seconds.
+    pub fn tick_block_entities(&self, dt: f32) {
+        for chunk in self.chunks.iter() {
+            chunk.tick_block_entities(dt);
+        }
+    }
+
+    /// Spawns mobs requested by [spawners][Spawner] and runs entity systems.
+    pub fn tick_entities(&mut self, dt: f32) {
+        if self.sizes == USize3::ZERO {
+            return;
+        }
+
+        self.spawn_requested_mobs();
+
+        let (start_pos, _) = Self::pos_bounds(self.sizes);
+        let min_height =
+            Chunk::global_pos(start_pos).y as f32 * Voxel::SIZE - Self::ENTITY_FALL_DEPTH;
+
+        // Entities wait on ungenerated chunks instead of falling through them.
+        let mut entities = mem::take(&mut self.entities);
+        entities.update(dt, min_height, |pos| {
+            !self.is_voxel_ready(pos) || self.is_voxel_solid(pos)
+        });
+        self.entities = entities;
+    }
+
+    /// Gives [light][Light] of voxel at `pos`. Missing or unlit voxels are lit by the sky.
+    pub fn get_light(&self, pos: Int3) -> Light {
+        let Some(chunk) = self.get_chunk_by_pos(Chunk::local_pos(pos)) else {
+            return Light::SKY;
+        };
+
+        match chunk.get_light_global(pos) {
+            ChunkOption::Voxel(light) => light,
+            ChunkOption::OutsideChunk | ChunkOption::Failed => Light::SKY,
+        }
+    }
+
+    /// Runs [world ticks][world_tick] for `dt` seconds. Only voxels near `cam_pos` change.
+    pub fn tick_world(&mut self, dt: f32, cam_pos: vec3) {
+        let n_ticks = self.tick_timestep.advance(dt);
+
+        if n_ticks == 0 || self.sizes == USize3::ZERO {
+            return;
+        }
+
+        let active_chunk = Chunk::local_pos(Voxel::pos_of(cam_pos));
+
+        // Only chunks with voxels that can change are randomly ticked.
+        let distance = Int3::all(Self::TICK_ACTIVE_DISTANCE);
+        let random_chunks =
+            SpaceIter::new(active_chunk - distance..active_chunk + distance + Int3::ONE)
+                .filter_map(|chunk_pos| self.get_chunk_by_pos(chunk_pos))
+                .filter(|chunk| {
+                    chunk.is_generated()
+                        && chunk
+                            .fill_id()
+                            .is_none_or(|id| world_tick::Behavior::of(id).is_some())
+                })
+                .map(|chunk| Chunk::global_pos(chunk.pos.load(Relaxed)))
+                .collect_vec();
+
+        let mut queue = mem::take(&mut self.scheduled_updates);
+        let mut rng = mem::replace(&mut self.tick_rng, StdRng::seed_from_u64(0));
+        let mut space = ChunkTickSpace {
+            chunks: self,
+            active_chunk,
+        };
+
+        for _ in 0..n_ticks {
+            world_tick::tick(&mut space, &mut queue);
+
+            for &chunk_start in random_chunks.iter() {
+                for _ in 0..Self::RANDOM_TICKS_PER_CHUNK {
+                    let offset = Int3::from(Chunk::SIZES)
+                        .as_array()
+                        .map(|size| rng.gen_range(0..size));
+                    let pos = chunk_start + Int3::from(offset);
+
+                    let changed = world_tick::random_tick(&mut space, pos, &mut rng);
+                    world_tick::schedule_near(&space, &mut queue, changed);
+                }
+            }
+        }
+
+        self.scheduled_updates = queue;
+        self.tick_rng = rng;
+    }
+
+    /// Schedules [voxel updates][world_tick] next to changed voxels.
+    pub fn schedule_updates_near(&mut self, positions: impl IntoIterator<Item = Int3>) {
+        let mut queue = mem::take(&mut self.scheduled_updates);
+        let space = ChunkTickSpace {
+            chunks: self,
+            active_chunk: Int3::ZERO,
+        };
+
+        world_tick::schedule_near(&space, &mut queue, positions);
+        self.scheduled_updates = queue;
+    }
+
+    /// Puts items near `pos` into the [inventory][Inventory] in survival mode.
+    pub fn collect_items(&mut self, pos: vec3) {
+        if self.inventory.mode != GameMode::Survival {
+            return;
+        }
+
+        for entity in self.entities.near(pos, Self::ITEM_PICKUP_RADIUS) {
+            let Some(&EntityKind::Item { id }) = self.entities.kinds.get(entity) else {
+                continue;
+            };
+
+            if self.inventory.add(id, 1) == 0 {
+                self.entities.despawn(entity);
+            }
+        }
+    }
+
+    /// Spawns a mob on top of each [spawner][Spawner] with pending spawns.
+    fn spawn_requested_mobs(&mut self) {
+        for chunk in self.chunks.iter() {
+            let chunk_pos = chunk.pos.load(Relaxed);
+
+            for (local_pos, block_entity) in chunk.block_entities.write().iter_mut() {
+                let BlockEntity::Spawner(Spawner { n_pending, .. }) = block_entity else {
+                    continue;
+                };
+
+                if *n_pending == 0 {
+                    continue;
+                }
+
+                *n_pending = 0;
+
+                if Self::MAX_SPAWNER_ENTITIES_PER_CHUNK <= self.entities.in_chunk(chunk_pos).len() {
+                    continue;
+                }
+
+                let voxel_pos = Chunk::local_to_global_pos(chunk_pos, *local_pos);
+                let height = 0.5 * Voxel::SIZE + EntityKind::MOB_HALF_SIZE.y + collision::SKIN;
+                let pos = vec3::from(voxel_pos) * Voxel::SIZE + vec3::new(0.0, height, 0.0);
+
+                self.entities.spawn(EntityKind::Mob, pos, vec3::zero());
+            }
+        }
+    }
+
+    /// Sets voxels to their ids chunk by chunk and tracks changed ones.
+    /// # Error
+    /// Returns [`Err`] if some position is not in this [chunk array][ChunkArray] or
+    /// some id is invalid. All of them are checked before any voxel is set,
+    /// so nothing is set then.
+    pub fn set_voxels(
+        &mut self,
+        voxels: impl IntoIterator<Item = (Int3, Id)>,
+        change_tracker: &mut ChangeTracker,
+    ) -> Result<(), EditError> {
+        let mut chunk_voxels = HashMap::<usize, Vec<(Int3, Id)>>::new();
+
+        for (pos, new_id) in voxels {
+            if !voxel::is_id_valid(new_id) {
+                return Err(EditError::InvalidId(new_id));
+            }
+
+            let chunk_idx = Self::pos_to_idx(self.sizes, Chunk::local_pos(pos))
+                .ok_or(EditError::PosIdConversion(pos))?;
+
+            chunk_voxels
+                .entry(chunk_idx)
+                .or_default()
+                .push((pos, new_id));
+        }
+
+        for (chunk_idx, voxels) in chunk_voxels {
+            // * Safety:
+            // * Safe, because voxel ids are atomic and chunk tasks only read them,
+            // * block entities are not read by tasks. The same is done in `set_voxel`.
+            let chunk = unsafe {
+                Arc::as_ptr(&self.chunks[chunk_idx])
+                    .cast_mut()
+                    .as_mut()
+                    .unwrap_unchecked()
+            };
+
+            chunk
+                .set_voxels(voxels.iter().copied())
+                .expect("voxels are grouped by their chunks");
+            self.relight(voxels.iter().map(|&(pos, _)| pos));
+
+            for (pos, _) in voxels {
+                change_tracker.track_voxel(pos);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies [changes][VoxelChange] and records them into [history][EditHistory].
+    /// Block entities destroyed by the changes are recorded too.
+    pub fn apply_changes(
+        &mut self,
+        mut changes: Vec<VoxelChange>,
+        change_tracker: &mut ChangeTracker,
+    ) -> Result<(), EditError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        for change in &mut changes {
+            change.old_block_entity = self.get_block_entity(change.pos);
+        }
+
+        let voxels = changes.iter().map(|change| (change.pos, change.new_id));
+        self.set_voxels(voxels, change_tracker)?;
+
+        self.history.record(Edit::SetVoxels { changes });
+
+        Ok(())
+    }
+
+    /// Gives [ids][Id] of voxels in `pos_from..pos_to` in [`SpaceIter`] order.
+    /// Voxels outside of the [array][ChunkArray] are treated as air.
+    pub fn get_voxel_ids(&self, pos_from: Int3, pos_to: Int3) -> Vec<Id> {
+        SpaceIter::new(pos_from..pos_to)
+            .map(|pos| {
+                self.get_voxel(pos)
+                    .map_or(AIR_VOXEL_DATA.id, |voxel| voxel.data.id)
+            })
+            .collect()
+    }
+
+    /// Reverts last edit transaction from [history][EditHistory].
+    /// Destroyed [block entities][BlockEntity] are restored with their data.
+    pub fn undo(&mut self, change_tracker: &mut ChangeTracker) {
+        let Some(transaction) = self.history.undo().cloned() else {
+            logger::log!(Info, from = "chunk-array", "nothing to undo");
+            return;
+        };
+
+        for edit in transaction.iter().rev() {
+            let result = match *edit {
+                Edit::SetVoxel {
+                    pos,
+                    old_id,
+                    ref old_block_entity,
+                    ..
+                } => self.set_voxel(pos, old_id).and_then(|_| {
+                    change_tracker.track_voxel(pos);
+                    self.restore_block_entities(old_block_entity.iter().map(|entity| (pos, entity)))
+                }),
+
+                Edit::FillVoxels {
+                    pos_from,
+                    pos_to,
+                    ref old_ids,
+                    ref old_block_entities,
+                    ..
+                } => self
+                    .fill_voxels_with(pos_from, pos_to, |pos| {
+                        Edit::region_old_id(pos_from, pos_to, old_ids, pos)
+                    })
+                    .and_then(|_| {
+                        self.restore_block_entities(
+                            old_block_entities
+                                .iter()
+                                .map(|(pos, entity)| (*pos, entity)),
+                        )
+                    }),
+
+                Edit::SetVoxels { ref changes } => self
+                    .set_voxels(
+                        changes.iter().map(|change| (change.pos, change.old_id)),
+                        change_tracker,
+                    )
+                    .and_then(|()| {
+                        self.restore_block_entities(changes.iter().filter_map(|change| {
+                            Some((change.pos, change.old_block_entity.as_ref()?))
+                        }))
+                    }),
+            };
+
+            result.log_error("chunk-array", "failed to undo edit");
+        }
+    }
+
+    /// Puts copies of recorded [block entities][BlockEntity] back into their voxels.
+    fn restore_block_entities<'e>(
+        &self,
+        block_entities: impl IntoIterator<Item = (Int3, &'e BlockEntity)>,
+    ) -> Result<(), EditError> {
+        for (pos, block_entity) in block_entities {
+            self.set_block_entity(pos, block_entity.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reapplies last reverted edit transaction from [history][EditHistory].
+    pub fn redo(&mut self, change_tracker: &mut ChangeTracker) {
+        let Some(transaction) = self.history.redo().cloned() else {
+            logger::log!(Info, from = "chunk-array", "nothing to redo");
+            return;
+        };
+
+        for edit in transaction.iter() {
+            let result = match *edit {
+                Edit::SetVoxel { pos, new_id, .. } => self.set_voxel(pos, new_id).map(|_| {
+                    change_tracker.track_voxel(pos);
+                }),
+
+                Edit::FillVoxels {
+                    pos_from,
+                    pos_to,
+                    new_id,
+                    ..
+                } => self.fill_voxels(pos_from, pos_to, new_id).map(drop),
+
+                Edit::SetVoxels { ref changes } => self.set_voxels(
+                    changes.iter().map(|change| (change.pos, change.new_id)),
+                    change_tracker,
+                ),
+            };
+
+            result.log_error("chunk-array", "failed to redo edit");
+        }
+    }
+
     /// Drops all meshes from each [chunk][Chunk].
     pub fn drop_all_meshes(&self) {
         for mesh in self.meshes.iter() {
@@ -443,31 +1201,45 @@ impl ChunkArray {
         result
     }
 
-    pub fn apply_new(
-        &mut self,
-        sizes: USize3,
-        chunk_arr: Vec<(Vec<Atomic<Id>>, FillType)>,
-    ) -> Result<(), UserFacingError> {
-        if Self::volume(sizes) != chunk_arr.len() {
+    pub fn apply_new(&mut self, data: SaveData) -> Result<(), UserFacingError> {
+        let SaveData {
+            sizes,
+            chunks,
+            block_entities,
+            entities,
+            inventory,
+            clock,
+            scheduled_updates,
+        } = data;
+
+        if Self::volume(sizes) != chunks.len() || chunks.len() != block_entities.len() {
             return Err(UserFacingError::new(
                 "chunk-array should have same len as sizes",
             ));
         }
 
-        let chunks = chunk_arr
+        let chunks = chunks
             .into_iter()
+            .zip(block_entities)
             .enumerate()
-            .map(|(idx, (voxel_ids, fill_type))| {
+            .map(|(idx, ((voxel_ids, fill_type), block_entities))| {
                 let chunk_pos = Self::idx_to_pos(idx, sizes);
-                match fill_type {
+                let mut chunk = match fill_type {
                     FillType::Default => Chunk::from_voxels(voxel_ids, chunk_pos),
                     FillType::AllSame(id) => Chunk::new_same_filled(chunk_pos, id),
-                }
+                };
+
+                *chunk.block_entities.get_mut() = block_entities;
+                chunk
             })
             .map(Arc::new)
             .collect();
 
-        let new_chunks = ChunkArray::from_chunks(sizes, chunks)?;
+        let mut new_chunks = ChunkArray::from_chunks(sizes, chunks)?;
+        new_chunks.entities = EntityWorld::from_records(entities.into_iter().flatten());
+        new_chunks.inventory = inventory.unwrap_or_else(|| mem::take(&mut self.inventory));
+        new_chunks.clock = clock.unwrap_or(self.clock);
+        new_chunks.scheduled_updates = scheduled_updates.unwrap_or_default();
         self.drop_tasks();
         let _ = mem::replace(self, new_chunks);
 
@@ -656,28 +1428,27 @@ impl ChunkArray {
         result
     }
 
-    /// Renders all [chunk][Chunk]s. If [chunk][Chunk] should have another
+    /// Updates all [chunk][Chunk]s meshes. If [chunk][Chunk] should have another
     /// [LOD][Lod] then it will start async task that generates desired mesh.
-    /// If task is incomplete then it will render active [LOD][Lod]
-    /// of concrete [chunk][Chunk]. If it can't then it will do nothing.
-    pub async fn render(
-        &mut self,
-        target: &mut impl gl::Surface,
-        draw_bundle: &ChunkDrawBundle<'_>,
-        uniforms: &impl gl::uniforms::Uniforms,
-        facade: &dyn gl::backend::Facade,
-        cam: &mut Camera,
-    ) -> Result<(), ChunkRenderError> {
+    /// If task is incomplete then active [LOD][Lod] of concrete [chunk][Chunk]
+    /// will be drawn. Then picks chunks seen from `cam`.
+    /// Called once per frame before [rendering][ChunkArray::render].
+    pub async fn update_meshes(&mut self, facade: &dyn gl::backend::Facade, cam: &mut Camera) {
         #![allow(clippy::await_holding_refcell_ref)]
 
+        self.draw_order.clear();
+        self.is_visible.clear();
+        self.culling_stats = CullingStats::default();
+
         let sizes = self.sizes;
         if sizes == USize3::ZERO {
-            return Ok(());
+            return;
         }
 
         self.try_finish_all_tasks(facade).await;
 
-        let targets = self.get_targets_sorted(cam.pos);
+        let cam_pos = cam.pos;
+        let targets = self.get_targets_sorted(cam_pos);
 
         for (chunk, chunk_adj, mesh, lod) in targets {
             let chunk_pos = chunk.pos.load(Relaxed);
@@ -696,14 +1467,11 @@ impl ChunkArray {
 
                         // * Safety:
                         // * Safe, because there's no chunk readers due to tasks drop above
-                        let chunk = unsafe {
-                            Arc::as_ptr(&chunk)
-                                .cast_mut()
-                                .as_mut()
-                                .unwrap_unchecked()
-                        };
+                        let chunk =
+                            unsafe { Arc::as_ptr(&chunk).cast_mut().as_mut().unwrap_unchecked() };
 
                         *chunk = new_chunk;
+                        self.light_chunk(chunk_pos);
                     }
                 } else if self.can_start_tasks() {
                     Self::start_task_gen_voxels(&mut self.voxels_gen_tasks, chunk_pos, sizes);
@@ -716,7 +1484,7 @@ impl ChunkArray {
             const CHUNK_MESH_PARTITION_DIST: f32 = 128.0;
 
             let chunk_is_close_to_be_partitioned = vec3::len(
-                vec3::from(Chunk::global_pos(chunk_pos)) - cam.pos + vec3::from(Chunk::SIZES / 2),
+                vec3::from(Chunk::global_pos(chunk_pos)) - cam_pos + vec3::from(Chunk::SIZES / 2),
             ) <= CHUNK_MESH_PARTITION_DIST;
 
             if chunk_is_close_to_be_partitioned
@@ -769,22 +1537,86 @@ impl ChunkArray {
                 chunk.try_set_best_fit_lod(&mesh.borrow(), lod);
             }
 
-            // FIXME: make cam vis-check for light.
-            if chunk.can_render_active_lod(&mesh.borrow()) && chunk.is_visible_by_camera(cam) {
-                let active_lod = chunk.info.load(Relaxed).active_lod.unwrap();
-                chunk.render(
-                    &mut mesh.borrow_mut(),
-                    target,
-                    draw_bundle,
-                    uniforms,
-                    active_lod,
-                )?
+            if chunk.can_render_active_lod(&mesh.borrow()) {
+                self.draw_order.extend(Self::pos_to_idx(sizes, chunk_pos));
             }
         }
 
+        self.update_visibility(cam);
+    }
+
+    /// Culls [chunks][Chunk] of the draw order not seen from `cam` and counts them.
+    fn update_visibility(&mut self, cam: &mut Camera) {
+        let is_reachable = self.reachable_chunks(cam);
+        let mut stats = CullingStats::default();
+
+        self.is_visible = vec![false; self.chunks.len()];
+
+        for &idx in self.draw_order.iter() {
+            if !self.chunks[idx].is_visible_by_camera(cam) {
+                stats.n_frustum_culled += 1;
+            } else if !is_reachable[idx] {
+                stats.n_occlusion_culled += 1;
+            } else {
+                self.is_visible[idx] = true;
+                stats.n_rendered += 1;
+            }
+        }
+
+        self.culling_stats = stats;
+    }
+
+    /// Renders [chunks][Chunk] with [LOD][Lod] picked by the last
+    /// [mesh update][ChunkArray::update_meshes]. Geometry passes draw chunks seen
+    /// from the camera, shadow passes draw chunks which may cast shadows into
+    /// their cascade of `cascades`, those are not occlusion culled.
+    pub fn render(
+        &self,
+        target: &mut impl gl::Surface,
+        draw_bundle: &ChunkDrawBundle<'_>,
+        uniforms: &impl gl::uniforms::Uniforms,
+        pass: PassKind,
+        cascades: &ShadowCascades,
+    ) -> Result<(), ChunkRenderError> {
+        for &idx in self.draw_order.iter() {
+            let (chunk, mesh) = (&self.chunks[idx], &self.meshes[idx]);
+
+            let is_visible = match pass {
+                PassKind::Shadow(cascade) => cascades.is_aabb_in_cascade(cascade, chunk.aabb()),
+                _ => self.is_visible[idx],
+            };
+
+            if !is_visible {
+                continue;
+            }
+
+            let active_lod = chunk.info.load(Relaxed).active_lod.unwrap();
+            chunk.render(
+                &mut mesh.borrow_mut(),
+                target,
+                draw_bundle,
+                uniforms,
+                active_lod,
+            )?;
+        }
+
         Ok(())
     }
 
+    /// Gives which [chunks][Chunk] by index are not hidden behind terrain from `cam`.
+    fn reachable_chunks(&self, cam: &mut Camera) -> Vec<bool> {
+        if !self.is_occlusion_culling_enabled {
+            return vec![true; self.chunks.len()];
+        }
+
+        visibility::traverse(
+            self.sizes,
+            Chunk::local_pos(Voxel::pos_of(cam.pos)),
+            |idx| self.chunks[idx].connections.load(Relaxed),
+            |idx| self.chunks[idx].is_visible_by_camera(cam),
+        )
+    }
+
     pub fn drop_all_useless_tasks(
         full_tasks: &mut HashMap<Int3, FullTask>,
         low_tasks: &mut HashMap<(Int3, Lod), LowTask>,
@@ -867,14 +1699,10 @@ impl ChunkArray {
 
             // * Safety:
             // * Safe, because there's no chunk readers due to tasks drop above.
-            let chunk = unsafe {
-                Arc::as_ptr(&chunk)
-                    .cast_mut()
-                    .as_mut()
-                    .unwrap_unchecked()
-            };
+            let chunk = unsafe { Arc::as_ptr(&chunk).cast_mut().as_mut().unwrap_unchecked() };
 
             *chunk = Chunk::from_voxels(voxels, pos);
+            self.light_chunk(pos);
         }
     }
 
@@ -986,6 +1814,7 @@ impl ChunkArray {
         assert!(prev_value.is_none(), "there should be only one task");
     }
 
+    #[allow(clippy::collapsible_if)]
     pub async fn try_finish_voxels_gen_task(
         tasks: &mut HashMap<Int3, GenTask>,
         pos: Int3,
@@ -1078,7 +1907,10 @@ impl ChunkArray {
     }
 
     pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
-        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+        use {
+            super::commands::{Command, command},
+            crate::app::utils::graphics::ui::imgui_constructor::make_window,
+        };
 
         make_window(ui, "Chunk array")
             .always_auto_resize(true)
@@ -1098,6 +1930,16 @@ impl ChunkArray {
                     n = self.partition_tasks.len(),
                 ));
 
+                ui.checkbox("Occlusion culling", &mut self.is_occlusion_culling_enabled);
+
+                let stats = self.culling_stats;
+                ui.text(format!(
+                    "{rendered} chunks drawn, {frustum} outside of view, {occluded} occluded.",
+                    rendered = stats.n_rendered,
+                    frustum = stats.n_frustum_culled,
+                    occluded = stats.n_occlusion_culled,
+                ));
+
                 ui.slider(
                     "Chunks lod threashold",
                     0.01,
@@ -1105,6 +1947,16 @@ impl ChunkArray {
                     &mut self.lod_threashold,
                 );
 
+                match self.ray_hit {
+                    Some(hit) => ui.text(format!(
+                        "Looking at {pos} through {normal} face, {distance:.2} away.",
+                        pos = hit.pos,
+                        normal = hit.normal,
+                        distance = hit.distance,
+                    )),
+                    None => ui.text("Looking at nothing."),
+                }
+
                 ui.separator();
 
                 ui.text("Generate new");
@@ -1114,17 +1966,77 @@ impl ChunkArray {
                 ui.input_scalar_n("Sizes", &mut *sizes).build();
 
                 if ui.button("Generate") {
-                    self.drop_tasks();
-                    match Self::new_empty_chunks(USize3::from(*sizes)) {
-                        Ok(new_chunks) => {
-                            let _ = mem::replace(self, new_chunks);
-                        }
-                        Err(err) => logger::log!(Error, from = "chunk-array", "{err}"),
-                    }
+                    command(Command::Generate {
+                        sizes: USize3::from(*sizes),
+                    });
                 }
             });
     }
 
+    /// Replaces all chunks with new ungenerated ones of `sizes`.
+    pub fn regenerate(&mut self, sizes: USize3) -> Result<(), UserFacingError> {
+        let mut new_chunks = Self::new_empty_chunks(sizes)?;
+        new_chunks.inventory = mem::take(&mut self.inventory);
+
+        *GENERATOR_SIZES.lock().unwrap() = sizes.as_array();
+        voxel::generator::rebuild();
+
+        self.drop_tasks();
+        let _ = mem::replace(self, new_chunks);
+
+        Ok(())
+    }
+
+    /// Starts saving task into directory `name`.
+    pub fn start_saving(&mut self, name: String) {
+        if self.saving_handle.is_some() {
+            logger::log!(Error, from = "chunk-array", "world is already being saved");
+            return;
+        }
+
+        if !self.chunks.iter().all(|chunk| chunk.is_generated()) {
+            logger::log!(
+                Error,
+                from = "chunk-array",
+                "chunks should be generated to save them"
+            );
+            return;
+        }
+
+        let chunks: Vec<_> = self.chunks.iter().map(Arc::clone).collect();
+
+        // Entities outside of the array are not saved.
+        let entities = (0..chunks.len())
+            .map(|idx| {
+                self.entities
+                    .records_in_chunk(Self::idx_to_pos(idx, self.sizes))
+            })
+            .collect();
+
+        let handle = tokio::spawn(ChunkArray::save_to_file(
+            self.sizes,
+            chunks,
+            entities,
+            self.inventory.clone(),
+            self.clock,
+            self.scheduled_updates.clone(),
+            name.clone(),
+            name,
+        ));
+        self.saving_handle = Some(handle);
+    }
+
+    /// Starts reading task from directory `name`.
+    pub fn start_loading(&mut self, name: String) {
+        if self.reading_handle.is_some() {
+            logger::log!(Error, from = "chunk-array", "world is already being loaded");
+            return;
+        }
+
+        let handle = tokio::spawn(async move { ChunkArray::read_from_file(&name, &name).await });
+        self.reading_handle = Some(handle);
+    }
+
     pub async fn process_commands(&mut self, facade: &dyn Facade) {
         #![allow(clippy::await_holding_lock)]
 
@@ -1137,13 +2049,22 @@ impl ChunkArray {
         while let Ok(command) = commands.receiver.try_recv() {
             match command {
                 SetVoxel { pos, new_id } => {
-                    let old_id = self.set_voxel(pos, new_id).unwrap_or_else(|err| {
-                        logger::log!(Error, from = "chunk-array", "failed to set voxel: {err}");
-                        0
-                    });
-
-                    if old_id != new_id {
-                        change_tracker.track_voxel(pos);
+                    let old_block_entity = self.get_block_entity(pos);
+
+                    match self.set_voxel(pos, new_id) {
+                        Ok(old_id) if old_id != new_id => {
+                            change_tracker.track_voxel(pos);
+                            self.history.record(Edit::SetVoxel {
+                                pos,
+                                old_id,
+                                new_id,
+                                old_block_entity,
+                            });
+                        }
+                        Ok(_) => (),
+                        Err(err) => {
+                            logger::log!(Error, from = "chunk-array", "failed to set voxel: {err}")
+                        }
                     }
                 }
 
@@ -1152,31 +2073,113 @@ impl ChunkArray {
                     pos_to,
                     new_id,
                 } => {
-                    let _is_changed =
-                        self.fill_voxels(pos_from, pos_to, new_id)
-                            .unwrap_or_else(|err| {
-                                logger::log!(
-                                    Error,
-                                    from = "chunk-array",
-                                    "failed to fill voxels: {err}"
-                                );
-                                false
-                            });
+                    let old_ids = self.get_voxel_ids(pos_from, pos_to);
+                    let old_block_entities = self.get_block_entities(pos_from, pos_to);
+
+                    match self.fill_voxels(pos_from, pos_to, new_id) {
+                        Ok(true) => self.history.record(Edit::FillVoxels {
+                            pos_from,
+                            pos_to,
+                            old_ids,
+                            new_id,
+                            old_block_entities,
+                        }),
+                        Ok(false) => (),
+                        Err(err) => logger::log!(
+                            Error,
+                            from = "chunk-array",
+                            "failed to fill voxels: {err}"
+                        ),
+                    }
+                }
+
+                Brush(brush) => {
+                    let changes =
+                        brush.changes(|pos| self.get_voxel(pos).map(|voxel| voxel.data.id));
+
+                    self.apply_changes(changes, &mut change_tracker)
+                        .log_error("chunk-array", "failed to apply brush");
+                }
+
+                Copy { pos_from, pos_to } => {
+                    let sizes = USize3::from(pos_to - pos_from);
+                    let schematic = Schematic::new(sizes, self.get_voxel_ids(pos_from, pos_to));
+
+                    logger::log!(Info, from = "chunk-array", "copied region of {sizes}");
+                    self.region_tool.clipboard = Some(Arc::new(schematic));
+                }
+
+                Paste {
+                    pos,
+                    schematic,
+                    skip_air,
+                } => {
+                    let changes = schematic
+                        .paste_voxels(pos, skip_air)
+                        .filter_map(|(pos, new_id)| {
+                            let old_id = self.get_voxel(pos)?.data.id;
+                            (old_id != new_id).then_some(VoxelChange {
+                                pos,
+                                old_id,
+                                new_id,
+                                old_block_entity: None,
+                            })
+                        })
+                        .collect();
+
+                    self.apply_changes(changes, &mut change_tracker)
+                        .log_error("chunk-array", "failed to paste schematic");
                 }
 
                 DropAllMeshes => self.drop_all_meshes(),
+
+                Undo => self.undo(&mut change_tracker),
+
+                Redo => self.redo(&mut change_tracker),
+
+                Save { name } => self.start_saving(name),
+
+                Load { name } => self.start_loading(name),
+
+                SetLodThreshold(threshold) => self.lod_threashold = threshold,
+
+                Generate { sizes } => match self.regenerate(sizes) {
+                    Ok(()) => change_tracker = ChangeTracker::new(self.sizes),
+                    Err(err) => logger::log!(Error, from = "chunk-array", "{err}"),
+                },
+
+                SpawnEntity {
+                    kind,
+                    pos,
+                    velocity,
+                } => drop(self.entities.spawn(kind, pos, velocity)),
+
+                SetGameMode(mode) => {
+                    self.inventory.mode = mode;
+                    logger::log!(Info, from = "chunk-array", "game mode is set to {mode}");
+                }
             }
         }
 
         drop(commands);
 
+        // All edits of one frame are undone as a whole.
+        self.history.commit();
+
+        self.schedule_updates_near(change_tracker.voxel_poses.iter().copied());
+        change_tracker
+            .voxel_poses
+            .extend(mem::take(&mut self.tick_changes));
+
         let idxs_to_reload = change_tracker.idxs_to_reload_partitioning();
         let n_changed = idxs_to_reload.len();
-        for (idx, partition_idx) in idxs_to_reload {
+        for &(idx, partition_idx) in idxs_to_reload.iter() {
             self.reload_chunk_partitioning(idx, partition_idx, facade)
                 .await;
         }
 
+        self.reload_lit_chunks(&idxs_to_reload, facade).await;
+
         if n_changed != 0 {
             logger::log!(
                 Info,
@@ -1186,6 +2189,37 @@ impl ChunkArray {
         }
     }
 
+    /// Remeshes partitions which light has changed except `reloaded` ones.
+    /// Chunks without detailed meshes are meshed later with the new light.
+    pub async fn reload_lit_chunks(
+        &mut self,
+        reloaded: &HashSet<(usize, usize)>,
+        facade: &dyn Facade,
+    ) {
+        if self.light_changes.is_empty() {
+            return;
+        }
+
+        let light_tracker = ChangeTracker {
+            sizes: self.sizes,
+            voxel_poses: mem::take(&mut self.light_changes),
+        };
+
+        for (idx, partition_idx) in light_tracker.idxs_to_reload_partitioning() {
+            let chunk_pos = Self::idx_to_pos(idx, self.sizes);
+
+            // Meshes being made may hold the old light.
+            Self::drop_task(&mut self.full_tasks, &mut self.low_tasks, chunk_pos, 0);
+            drop(self.partition_tasks.remove(&chunk_pos));
+
+            let has_mesh = self.meshes[idx].borrow().detailed_mesh.is_some();
+            if has_mesh && !reloaded.contains(&(idx, partition_idx)) {
+                self.reload_chunk_partitioning(idx, partition_idx, facade)
+                    .await;
+            }
+        }
+    }
+
     pub async fn reload_chunk(&self, idx: usize, facade: &dyn Facade) {
         let chunk_pos = Self::idx_to_pos(idx, self.sizes);
         let adj = self.get_adj_chunks(chunk_pos);
@@ -1216,32 +2250,94 @@ impl ChunkArray {
         }
     }
 
-    pub fn trace_ray(&self, ray: Line, max_steps: usize) -> impl Iterator<Item = Voxel> + '_ {
-        (0..max_steps).filter_map(move |i| {
-            let pos = ray.point_along(i as f32 * 0.125);
-            let pos = Int3::new(
-                pos.x.round() as i32,
-                pos.y.round() as i32,
-                pos.z.round() as i32,
-            );
+    /// Casts `ray` and gives first non-air voxel hit.
+    pub fn trace_ray(&self, ray: Line, max_distance: f32) -> Option<RayHit> {
+        raycast::cast_ray(ray, max_distance, |pos| self.is_voxel_solid(pos))
+    }
 
-            self.get_voxel(pos)
-        })
+    /// Gives position of voxel under the cursor.
+    pub fn cursor(&self) -> Option<Int3> {
+        self.ray_hit.map(|hit| hit.pos)
     }
 
     pub async fn proccess_camera_input(&mut self, cam: &Camera) {
         use super::commands::{Command, command};
 
-        let first_voxel = self
-            .trace_ray(Line::new(cam.pos, cam.front), Self::MAX_TRACE_STEPS)
-            .find(|voxel| !voxel.is_air());
+        self.ray_hit = self.trace_ray(Line::new(cam.pos, cam.front), Self::MAX_TRACE_DISTANCE);
+        let cursor = self.cursor();
+
+        if keyboard::just_pressed(cfg::key_bindings::SELECT_FIRST_CORNER) {
+            self.region_tool.corners[0] = cursor;
+        }
+
+        if keyboard::just_pressed(cfg::key_bindings::SELECT_SECOND_CORNER) {
+            self.region_tool.corners[1] = cursor;
+        }
+
+        if keyboard::just_pressed_combo(cfg::key_bindings::COPY)
+            && let Some((pos_from, pos_to)) = self.region_tool.selection()
+        {
+            command(Command::Copy { pos_from, pos_to });
+        }
+
+        if keyboard::just_pressed_combo(cfg::key_bindings::PASTE)
+            && let (Some(pos), Some(schematic)) = (cursor, self.region_tool.clipboard.clone())
+        {
+            command(Command::Paste {
+                pos,
+                schematic,
+                skip_air: self.region_tool.skip_air,
+            });
+        }
+
+        self.inventory.process_input(cam.grabbes_cursor);
+
+        if !cam.grabbes_cursor {
+            return;
+        }
+
+        if keyboard::just_pressed(cfg::key_bindings::THROW_PROJECTILE) {
+            let direction = cam.front / cam.front.len();
+
+            command(Command::SpawnEntity {
+                kind: EntityKind::Projectile,
+                pos: cam.pos + direction,
+                velocity: direction * EntityKind::PROJECTILE_SPEED,
+            });
+        }
+
+        match self.ray_hit {
+            Some(hit) if mouse::just_left_pressed() && self.brush_tool.is_enabled => {
+                if let Some(brush) = self.brush_tool.click(hit.pos) {
+                    command(Command::Brush(brush))
+                }
+            }
 
-        match first_voxel {
-            Some(voxel) if mouse::just_left_pressed() && cam.grabbes_cursor => {
+            Some(hit) if mouse::just_left_pressed() => {
                 command(Command::SetVoxel {
-                    pos: voxel.pos,
+                    pos: hit.pos,
                     new_id: AIR_VOXEL_DATA.id,
-                })
+                });
+
+                // Broken voxel drops as an item to collect in survival mode.
+                if self.inventory.mode == GameMode::Survival
+                    && let Some(voxel) = self.get_voxel(hit.pos)
+                {
+                    command(Command::SpawnEntity {
+                        kind: EntityKind::Item { id: voxel.data.id },
+                        pos: vec3::from(hit.pos) * Voxel::SIZE,
+                        velocity: vec3::new(0.0, Self::ITEM_DROP_SPEED, 0.0),
+                    });
+                }
+            }
+
+            Some(RayHit {
+                prev_pos: Some(pos),
+                ..
+            }) if mouse::just_right_pressed() && self.get_voxel(pos).is_some() => {
+                if let Some(new_id) = self.inventory.take_selected() {
+                    command(Command::SetVoxel { pos, new_id });
+                }
             }
 
             _ => (),
@@ -1249,15 +2345,15 @@ impl ChunkArray {
     }
 
     pub async fn update(&mut self, facade: &dyn Facade, cam: &Camera) -> Result<(), UpdateError> {
+        use super::commands::{Command, command};
+
         self.proccess_camera_input(cam).await;
         self.process_commands(facade).await;
 
-        if keyboard::just_pressed_combo([Key::ControlLeft, Key::KeyS]) {
-            let chunks: Vec<_> = self.chunks.iter().map(Arc::clone).collect();
-            let handle = tokio::spawn(ChunkArray::save_to_file(
-                self.sizes, chunks, "world", "world",
-            ));
-            self.saving_handle = Some(handle);
+        if keyboard::just_pressed_combo(cfg::key_bindings::SAVE) {
+            command(Command::Save {
+                name: cfg::save::WORLD_NAME.into(),
+            });
         }
 
         if self.saving_handle.is_some() && self.saving_handle.as_ref().unwrap().is_finished() {
@@ -1265,15 +2361,24 @@ impl ChunkArray {
             handle.await??;
         }
 
-        if keyboard::just_pressed_combo([Key::ControlLeft, Key::KeyO]) {
-            let handle = tokio::spawn(ChunkArray::read_from_file("world", "world"));
-            self.reading_handle = Some(handle);
+        if keyboard::just_pressed_combo(cfg::key_bindings::UNDO) {
+            command(Command::Undo);
+        }
+
+        if keyboard::just_pressed_combo(cfg::key_bindings::REDO) {
+            command(Command::Redo);
+        }
+
+        if keyboard::just_pressed_combo(cfg::key_bindings::LOAD) {
+            command(Command::Load {
+                name: cfg::save::WORLD_NAME.into(),
+            });
         }
 
         if self.reading_handle.is_some() && self.reading_handle.as_ref().unwrap().is_finished() {
             let handle = self.reading_handle.take().unwrap();
-            let (sizes, arr) = handle.await??;
-            self.apply_new(sizes, arr)?;
+            let data = handle.await??;
+            self.apply_new(data)?;
         }
 
         Ok(())
@@ -1301,6 +2406,76 @@ pub enum TaskError {
     TaskNotFound { lod: Lod, pos: Int3 },
 }
 
+/// [World space][WorldSpace] over the [chunk array][ChunkArray].
+struct ChunkTickSpace<'a> {
+    chunks: &'a mut ChunkArray,
+    /// Chunk in the center of active region.
+    active_chunk: Int3,
+}
+
+impl FluidSpace for ChunkTickSpace<'_> {
+    fn get_id(&self, pos: Int3) -> Option<Id> {
+        if !self.chunks.is_voxel_ready(pos) {
+            return None;
+        }
+
+        self.chunks.get_voxel(pos).map(|voxel| voxel.data.id)
+    }
+
+    fn get_level(&self, pos: Int3) -> u8 {
+        match self.chunks.get_block_entity(pos) {
+            Some(BlockEntity::Fluid(fluid)) => fluid.level,
+            _ => Fluid::SOURCE_LEVEL,
+        }
+    }
+
+    fn set(&mut self, pos: Int3, id: Id, level: u8) {
+        // Changing level only keeps the voxel and its light.
+        let result = match self.get_id(pos) == Some(id) {
+            true => Ok(id),
+            false => self.chunks.set_voxel(pos, id),
+        };
+
+        if let Err(err) = result {
+            logger::log!(Error, from = "chunk-array", "failed to tick voxel: {err}");
+            return;
+        }
+
+        if FluidKind::of(id).is_some() {
+            self.chunks
+                .set_block_entity(pos, BlockEntity::Fluid(Fluid { level }))
+                .log_error("chunk-array", "failed to set fluid level");
+        }
+
+        self.chunks.tick_changes.insert(pos);
+    }
+
+    fn is_active(&self, pos: Int3) -> bool {
+        let offset = Chunk::local_pos(pos) - self.active_chunk;
+
+        offset
+            .as_array()
+            .into_iter()
+            .all(|coord| coord.abs() <= ChunkArray::TICK_ACTIVE_DISTANCE)
+    }
+}
+
+impl WorldSpace for ChunkTickSpace<'_> {
+    fn get_light(&self, pos: Int3) -> Light {
+        self.chunks.get_light(pos)
+    }
+
+    fn get_block_entity(&self, pos: Int3) -> Option<BlockEntity> {
+        self.chunks.get_block_entity(pos)
+    }
+
+    fn set_block_entity(&mut self, pos: Int3, block_entity: BlockEntity) {
+        self.chunks
+            .set_block_entity(pos, block_entity)
+            .log_error("chunk-array", "failed to set block entity on tick");
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ChangeTracker {
     pub sizes: USize3,
@@ -1396,3 +2571,52 @@ impl ChangeTracker {
 pub type ChunkRef = Arc<Chunk>;
 pub type MeshRef = Rc<RefCell<ChunkMesh>>;
 pub type ChunkAdj = Sides<Option<Arc<Chunk>>>;
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::terrain::voxel::block_entity::{Container, ContainerSlot},
+    };
+
+    #[test]
+    fn undo_restores_broken_chest() {
+        let chunks = vec![Arc::new(Chunk::new_same_filled(
+            Int3::ZERO,
+            AIR_VOXEL_DATA.id,
+        ))];
+        let mut chunks = ChunkArray::from_chunks(USize3::ONE, chunks).unwrap();
+        let mut change_tracker = ChangeTracker::new(chunks.sizes);
+        let pos = Int3::new(1, 2, 3);
+
+        chunks.set_voxel(pos, CHEST_VOXEL_DATA.id).unwrap();
+
+        let mut container = Container::default();
+        container.slots[0] = Some(ContainerSlot {
+            id: STONE_VOXEL_DATA.id,
+            count: 16,
+        });
+        let chest = BlockEntity::Container(container);
+        chunks.set_block_entity(pos, chest.clone()).unwrap();
+
+        let changes = vec![VoxelChange {
+            pos,
+            old_id: CHEST_VOXEL_DATA.id,
+            new_id: AIR_VOXEL_DATA.id,
+            old_block_entity: None,
+        }];
+        chunks.apply_changes(changes, &mut change_tracker).unwrap();
+        chunks.history.commit();
+        assert_eq!(chunks.get_block_entity(pos), None);
+
+        chunks.undo(&mut change_tracker);
+        assert_eq!(chunks.get_voxel(pos).unwrap().data.id, CHEST_VOXEL_DATA.id);
+        assert_eq!(chunks.get_block_entity(pos), Some(chest.clone()));
+
+        chunks.redo(&mut change_tracker);
+        assert_eq!(chunks.get_block_entity(pos), None);
+
+        chunks.undo(&mut change_tracker);
+        assert_eq!(chunks.get_block_entity(pos), Some(chest));
+    }
+}
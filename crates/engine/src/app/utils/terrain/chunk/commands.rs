@@ -1,15 +1,19 @@
 use {
-    crate::app::utils::{concurrency::channel::Channel, terrain::voxel::voxel_data::Id},
+    super::{brush::Brush, schematic::Schematic},
+    crate::app::utils::{
+        concurrency::channel::Channel, entity::components::EntityKind, inventory::GameMode,
+        terrain::voxel::voxel_data::Id,
+    },
     lazy_static::lazy_static,
     math_linear::prelude::*,
-    std::sync::Mutex,
+    std::sync::{Arc, Mutex},
 };
 
 lazy_static! {
     pub(super) static ref COMMAND_CHANNEL: Mutex<Channel<Command>> = Mutex::new(Channel::default());
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
     SetVoxel {
         pos: Int3,
@@ -22,7 +26,57 @@ pub enum Command {
         new_id: Id,
     },
 
+    /// Applies [brush][Brush] to the terrain.
+    Brush(Brush),
+
+    /// Copies region `pos_from..pos_to` into the clipboard.
+    Copy {
+        pos_from: Int3,
+        pos_to: Int3,
+    },
+
+    /// Pastes [schematic][Schematic] with minimal corner at `pos`.
+    Paste {
+        pos: Int3,
+        schematic: Arc<Schematic>,
+        skip_air: bool,
+    },
+
     DropAllMeshes,
+
+    /// Reverts last edit transaction.
+    Undo,
+
+    /// Reapplies last reverted edit transaction.
+    Redo,
+
+    /// Saves the world into directory `name`.
+    Save {
+        name: String,
+    },
+
+    /// Loads the world from directory `name`.
+    Load {
+        name: String,
+    },
+
+    /// Sets distance threshold between chunk LODs.
+    SetLodThreshold(f32),
+
+    /// Replaces the world with newly generated one of `sizes` chunks.
+    Generate {
+        sizes: USize3,
+    },
+
+    /// Spawns an entity of `kind` centered at `pos`.
+    SpawnEntity {
+        kind: EntityKind,
+        pos: vec3,
+        velocity: vec3,
+    },
+
+    /// Switches between creative and survival [inventory][crate::inventory::Inventory].
+    SetGameMode(GameMode),
 }
 
 pub fn command(command: Command) {
@@ -0,0 +1,264 @@
+//!
+//! Edit history of [chunk array][super::chunk_array::ChunkArray]. Stores inverse
+//! information for each applied [command][super::commands::Command] so that edits can be
+//! undone and redone.
+//!
+
+use {
+    crate::{
+        prelude::*,
+        terrain::voxel::{block_entity::BlockEntity, voxel_data::Id},
+    },
+    std::mem,
+};
+
+/// Change of a single voxel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelChange {
+    pub pos: Int3,
+    pub old_id: Id,
+    pub new_id: Id,
+    /// [Block entity][BlockEntity] destroyed by the change.
+    pub old_block_entity: Option<BlockEntity>,
+}
+
+/// Single applied edit with enough information to invert it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    SetVoxel {
+        pos: Int3,
+        old_id: Id,
+        new_id: Id,
+        old_block_entity: Option<BlockEntity>,
+    },
+
+    FillVoxels {
+        pos_from: Int3,
+        pos_to: Int3,
+        /// Previous ids in [`SpaceIter`] order of `pos_from..pos_to`.
+        old_ids: Vec<Id>,
+        new_id: Id,
+        /// Previous [block entities][BlockEntity] by their positions.
+        old_block_entities: Vec<(Int3, BlockEntity)>,
+    },
+
+    /// Scattered changes, e.g. made by a [brush][super::brush::Brush].
+    SetVoxels { changes: Vec<VoxelChange> },
+}
+
+impl Edit {
+    /// Gives number of stored voxel ids and [block entities][BlockEntity].
+    /// Used to limit history memory.
+    pub fn n_stored_ids(&self) -> usize {
+        match self {
+            Self::SetVoxel {
+                old_block_entity, ..
+            } => 1 + old_block_entity.iter().len(),
+            Self::FillVoxels {
+                old_ids,
+                old_block_entities,
+                ..
+            } => old_ids.len() + old_block_entities.len(),
+            Self::SetVoxels { changes } => changes
+                .iter()
+                .map(|change| 1 + change.old_block_entity.iter().len())
+                .sum(),
+        }
+    }
+
+    /// Gives old id of voxel at `pos` in the filled region.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `pos` is not in `pos_from..pos_to`.
+    pub fn region_old_id(pos_from: Int3, pos_to: Int3, old_ids: &[Id], pos: Int3) -> Id {
+        let sizes = USize3::from(pos_to - pos_from);
+        let coord_idx = USize3::from(pos - pos_from);
+        let idx = (coord_idx.x * sizes.y + coord_idx.y) * sizes.z + coord_idx.z;
+
+        old_ids[idx]
+    }
+}
+
+/// Edits applied during one frame. They are undone and redone together.
+pub type Transaction = Vec<Edit>;
+
+/// Bounded undo/redo history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditHistory {
+    undo_stack: VecDeque<Transaction>,
+    redo_stack: Vec<Transaction>,
+    current: Transaction,
+    n_stored_ids: usize,
+    max_stored_ids: usize,
+}
+
+impl EditHistory {
+    /// Constructs empty history that stores at most `max_stored_ids` voxel ids.
+    pub fn new(max_stored_ids: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            current: vec![],
+            n_stored_ids: 0,
+            max_stored_ids,
+        }
+    }
+
+    /// Records applied edit into current transaction.
+    pub fn record(&mut self, edit: Edit) {
+        self.current.push(edit);
+    }
+
+    /// Finishes current transaction. Drops redo history if there was any edit
+    /// and forgets the oldest transactions if memory limit is exceeded.
+    pub fn commit(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+
+        let transaction = mem::take(&mut self.current);
+
+        for transaction in self.redo_stack.drain(..) {
+            self.n_stored_ids -= Self::transaction_size(&transaction);
+        }
+
+        self.n_stored_ids += Self::transaction_size(&transaction);
+        self.undo_stack.push_back(transaction);
+
+        while self.max_stored_ids < self.n_stored_ids {
+            let Some(oldest) = self.undo_stack.pop_front() else {
+                break;
+            };
+
+            self.n_stored_ids -= Self::transaction_size(&oldest);
+        }
+    }
+
+    /// Takes last transaction to undo. It moves to redo history.
+    pub fn undo(&mut self) -> Option<&Transaction> {
+        self.commit();
+
+        let transaction = self.undo_stack.pop_back()?;
+        self.redo_stack.push(transaction);
+        self.redo_stack.last()
+    }
+
+    /// Takes last undone transaction to redo. It moves back to undo history.
+    pub fn redo(&mut self) -> Option<&Transaction> {
+        self.commit();
+
+        let transaction = self.redo_stack.pop()?;
+        self.undo_stack.push_back(transaction);
+        self.undo_stack.back()
+    }
+
+    /// Forgets everything.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.max_stored_ids);
+    }
+
+    pub fn n_undoable(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn n_redoable(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    fn transaction_size(transaction: &Transaction) -> usize {
+        transaction.iter().map(Edit::n_stored_ids).sum()
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(cfg::terrain::MAX_HISTORY_VOXELS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_voxel(x: i32, new_id: Id) -> Edit {
+        Edit::SetVoxel {
+            pos: Int3::new(x, 0, 0),
+            old_id: 0,
+            new_id,
+            old_block_entity: None,
+        }
+    }
+
+    #[test]
+    fn frame_is_one_transaction() {
+        let mut history = EditHistory::new(100);
+
+        history.record(set_voxel(0, 1));
+        history.record(set_voxel(1, 1));
+        history.commit();
+        history.commit();
+
+        assert_eq!(history.n_undoable(), 1);
+        assert_eq!(history.undo().map(Vec::len), Some(2));
+        assert_eq!(history.n_redoable(), 1);
+        assert_eq!(history.redo().map(Vec::len), Some(2));
+        assert_eq!(history.n_redoable(), 0);
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut history = EditHistory::new(100);
+
+        history.record(set_voxel(0, 1));
+        history.commit();
+        history.undo();
+
+        history.record(set_voxel(0, 2));
+        history.commit();
+
+        assert_eq!(history.n_redoable(), 0);
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn memory_is_bounded() {
+        let mut history = EditHistory::new(3);
+
+        for x in 0..5 {
+            history.record(set_voxel(x, 1));
+            history.commit();
+        }
+
+        assert_eq!(history.n_undoable(), 3);
+        assert_eq!(history.undo(), Some(&vec![set_voxel(4, 1)]),);
+    }
+
+    #[test]
+    fn block_entities_count_into_memory() {
+        let mut history = EditHistory::new(2);
+
+        history.record(set_voxel(0, 1));
+        history.commit();
+
+        history.record(Edit::SetVoxel {
+            pos: Int3::new(1, 0, 0),
+            old_id: 0,
+            new_id: 1,
+            old_block_entity: Some(BlockEntity::Sign(Default::default())),
+        });
+        history.commit();
+
+        assert_eq!(history.n_undoable(), 1);
+    }
+
+    #[test]
+    fn region_old_id_follows_space_iter() {
+        let (pos_from, pos_to) = (Int3::new(-1, 2, 3), Int3::new(2, 4, 7));
+        let old_ids: Vec<Id> = (0..SpaceIter::new(pos_from..pos_to).len() as Id).collect();
+
+        for (pos, id) in SpaceIter::new(pos_from..pos_to).zip(old_ids.iter().copied()) {
+            assert_eq!(Edit::region_old_id(pos_from, pos_to, &old_ids, pos), id);
+        }
+    }
+}
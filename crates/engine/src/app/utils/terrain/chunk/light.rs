@@ -0,0 +1,478 @@
+//!
+//! Flood-fill voxel lighting with sky and block light channels.
+//!
+
+use {
+    crate::{
+        prelude::*,
+        terrain::chunk::{
+            Chunk, ChunkOption,
+            chunk_array::{ChunkArray, ChunkRef},
+        },
+    },
+    std::collections::VecDeque,
+};
+
+/// Light of a voxel. Sky light is stored in the high half of the byte
+/// and block light is stored in the low one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Light(u8);
+
+impl Light {
+    /// Maximal light level of each channel.
+    pub const MAX_LEVEL: u8 = 15;
+
+    pub const DARK: Self = Self(0);
+
+    /// Light of voxels under the open sky.
+    pub const SKY: Self = Self::new(Self::MAX_LEVEL, 0);
+
+    /// Constructs light from levels. Levels are clamped to [`MAX_LEVEL`][Light::MAX_LEVEL].
+    pub const fn new(sky: u8, block: u8) -> Self {
+        let sky = if sky < Self::MAX_LEVEL {
+            sky
+        } else {
+            Self::MAX_LEVEL
+        };
+        let block = if block < Self::MAX_LEVEL {
+            block
+        } else {
+            Self::MAX_LEVEL
+        };
+
+        Self(sky << 4 | block)
+    }
+
+    pub const fn from_raw(raw: u8) -> Self {
+        Self(raw)
+    }
+
+    pub const fn into_raw(self) -> u8 {
+        self.0
+    }
+
+    pub const fn sky(self) -> u8 {
+        self.0 >> 4
+    }
+
+    pub const fn block(self) -> u8 {
+        self.0 & 0xF
+    }
+
+    /// Gives level of `channel`.
+    pub const fn get(self, channel: LightChannel) -> u8 {
+        match channel {
+            LightChannel::Sky => self.sky(),
+            LightChannel::Block => self.block(),
+        }
+    }
+
+    /// Gives light with level of `channel` replaced by `level`.
+    pub const fn with(self, channel: LightChannel, level: u8) -> Self {
+        match channel {
+            LightChannel::Sky => Self::new(level, self.block()),
+            LightChannel::Block => Self::new(self.sky(), level),
+        }
+    }
+}
+
+/// Independent light channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum LightChannel {
+    /// Light from the sky. It goes straight down without fading.
+    #[display("sky")]
+    Sky,
+    /// Light from emissive voxels.
+    #[display("block")]
+    Block,
+}
+
+impl LightChannel {
+    pub const ALL: [Self; 2] = [Self::Sky, Self::Block];
+
+    /// Gives level of light spread from voxel with `level` by `offset`.
+    pub fn spread(self, level: u8, offset: Int3) -> u8 {
+        match self {
+            Self::Sky if level == Light::MAX_LEVEL && offset == veci!(0, -1, 0) => level,
+            _ => level.saturating_sub(1),
+        }
+    }
+}
+
+/// Voxels light spreads through.
+pub trait LightSpace {
+    /// Gives light of voxel at `pos` or [`None`] if that voxel is not lit yet or missing.
+    fn get_light(&self, pos: Int3) -> Option<Light>;
+
+    /// Sets light of voxel at `pos`. Does nothing if that voxel is not lit yet or missing.
+    fn set_light(&mut self, pos: Int3, light: Light);
+
+    /// Checks if light passes through voxel at `pos`.
+    fn is_transparent(&self, pos: Int3) -> bool;
+
+    /// Gives block light level emitted by voxel at `pos`.
+    fn emission(&self, pos: Int3) -> u8;
+}
+
+/// [Light space][LightSpace] over [chunks][Chunk] of [chunk array][ChunkArray]
+/// with a layer of sky light on top of it.
+#[derive(Debug)]
+pub struct ChunkLightSpace<'c> {
+    chunks: &'c [ChunkRef],
+    sizes: USize3,
+    /// Height of the sky layer in voxels.
+    sky_height: i32,
+
+    /// Positions of voxels which light has changed.
+    pub changed: HashSet<Int3>,
+    /// Position of chunk which light changes are not tracked, e.g. the one lit first time.
+    pub untracked_chunk: Option<Int3>,
+}
+
+impl<'c> ChunkLightSpace<'c> {
+    pub fn new(chunks: &'c [ChunkRef], sizes: USize3) -> Self {
+        let (_, end_pos) = ChunkArray::pos_bounds(sizes);
+
+        Self {
+            chunks,
+            sizes,
+            sky_height: Chunk::global_pos(end_pos).y,
+            changed: HashSet::new(),
+            untracked_chunk: None,
+        }
+    }
+
+    fn chunk(&self, pos: Int3) -> Option<&'c Chunk> {
+        let idx = ChunkArray::pos_to_idx(self.sizes, Chunk::local_pos(pos))?;
+        Some(&self.chunks[idx])
+    }
+
+    fn is_sky(&self, pos: Int3) -> bool {
+        pos.y == self.sky_height && self.chunk(pos - veci!(0, 1, 0)).is_some()
+    }
+}
+
+impl LightSpace for ChunkLightSpace<'_> {
+    fn get_light(&self, pos: Int3) -> Option<Light> {
+        if self.is_sky(pos) {
+            return Some(Light::SKY);
+        }
+
+        match self.chunk(pos)?.get_light_global(pos) {
+            ChunkOption::Voxel(light) => Some(light),
+            ChunkOption::OutsideChunk | ChunkOption::Failed => None,
+        }
+    }
+
+    fn set_light(&mut self, pos: Int3, light: Light) {
+        let Some(chunk) = self.chunk(pos) else {
+            return;
+        };
+
+        chunk.set_light_global(pos, light);
+
+        if self.untracked_chunk != Some(Chunk::local_pos(pos)) {
+            self.changed.insert(pos);
+        }
+    }
+
+    fn is_transparent(&self, pos: Int3) -> bool {
+        self.is_sky(pos)
+            || self
+                .chunk(pos)
+                .is_some_and(|chunk| match chunk.get_voxel_global(pos) {
+                    ChunkOption::Voxel(voxel) => voxel.is_air(),
+                    ChunkOption::OutsideChunk | ChunkOption::Failed => false,
+                })
+    }
+
+    fn emission(&self, pos: Int3) -> u8 {
+        self.chunk(pos)
+            .and_then(|chunk| match chunk.get_voxel_global(pos) {
+                ChunkOption::Voxel(voxel) => Some(voxel.data.emission),
+                ChunkOption::OutsideChunk | ChunkOption::Failed => None,
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Spreads light of `channel` from voxels in `queue` with breadth-first search.
+pub fn propagate(space: &mut impl LightSpace, channel: LightChannel, queue: &mut VecDeque<Int3>) {
+    while let Some(pos) = queue.pop_front() {
+        let Some(level) = space.get_light(pos).map(|light| light.get(channel)) else {
+            continue;
+        };
+
+        if level <= 1 {
+            continue;
+        }
+
+        for offset in SpaceIter::adj_iter(Int3::ZERO) {
+            let adj_pos = pos + offset;
+
+            if !space.is_transparent(adj_pos) {
+                continue;
+            }
+
+            let Some(adj_light) = space.get_light(adj_pos) else {
+                continue;
+            };
+
+            let new_level = channel.spread(level, offset);
+            if adj_light.get(channel) < new_level {
+                space.set_light(adj_pos, adj_light.with(channel, new_level));
+                queue.push_back(adj_pos);
+            }
+        }
+    }
+}
+
+/// Spreads both light channels from `sources`.
+pub fn propagate_from(space: &mut impl LightSpace, sources: impl IntoIterator<Item = Int3>) {
+    let sources: VecDeque<_> = sources.into_iter().collect();
+
+    for channel in LightChannel::ALL {
+        propagate(space, channel, &mut sources.clone());
+    }
+}
+
+/// Removes light of `channel` coming through voxels at `positions` and gives voxels
+/// light should be spread from again. Emissive voxels are lit again on the way.
+fn remove(
+    space: &mut impl LightSpace,
+    channel: LightChannel,
+    positions: impl IntoIterator<Item = Int3>,
+) -> VecDeque<Int3> {
+    let mut removal = VecDeque::new();
+    let mut result = VecDeque::new();
+
+    for pos in positions {
+        let Some(light) = space.get_light(pos) else {
+            continue;
+        };
+
+        space.set_light(pos, light.with(channel, 0));
+        removal.push_back((pos, light.get(channel)));
+    }
+
+    let mut removed = removal.iter().map(|&(pos, _)| pos).collect_vec();
+
+    while let Some((pos, level)) = removal.pop_front() {
+        for offset in SpaceIter::adj_iter(Int3::ZERO) {
+            let adj_pos = pos + offset;
+
+            let Some(adj_light) = space.get_light(adj_pos) else {
+                continue;
+            };
+
+            let adj_level = adj_light.get(channel);
+            if adj_level == 0 {
+                continue;
+            }
+
+            // Light that could come from the removed voxel is removed too,
+            // brighter neighbors light the area again.
+            if adj_level <= channel.spread(level, offset) {
+                space.set_light(adj_pos, adj_light.with(channel, 0));
+                removal.push_back((adj_pos, adj_level));
+                removed.push(adj_pos);
+            } else {
+                result.push_back(adj_pos);
+            }
+        }
+    }
+
+    if channel == LightChannel::Block {
+        for pos in removed {
+            let emission = space.emission(pos);
+
+            if let Some(light) = space.get_light(pos)
+                && light.block() < emission
+            {
+                space.set_light(pos, light.with(channel, emission));
+                result.push_back(pos);
+            }
+        }
+    }
+
+    result
+}
+
+/// Updates light after voxels at `positions` have changed.
+pub fn relight(space: &mut impl LightSpace, positions: impl IntoIterator<Item = Int3>) {
+    let positions = positions.into_iter().collect_vec();
+
+    for channel in LightChannel::ALL {
+        let mut queue = remove(space, channel, positions.iter().copied());
+        propagate(space, channel, &mut queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Box of voxels with a sky layer on top.
+    struct TestSpace {
+        sizes: Int3,
+        lights: HashMap<Int3, Light>,
+        solid: HashSet<Int3>,
+        emissions: HashMap<Int3, u8>,
+    }
+
+    impl TestSpace {
+        fn new(sizes: Int3) -> Self {
+            let lights = SpaceIter::new(Int3::ZERO..sizes)
+                .map(|pos| (pos, Light::DARK))
+                .collect();
+
+            Self {
+                sizes,
+                lights,
+                solid: HashSet::new(),
+                emissions: HashMap::new(),
+            }
+        }
+
+        fn sky_layer(&self) -> impl Iterator<Item = Int3> + use<> {
+            let y = self.sizes.y;
+            SpaceIter::new(Int3::ZERO..Int3::new(self.sizes.x, 1, self.sizes.z))
+                .map(move |pos| pos + Int3::new(0, y, 0))
+        }
+
+        fn set_voxel(&mut self, pos: Int3, is_solid: bool, emission: u8) {
+            match is_solid {
+                true => self.solid.insert(pos),
+                false => self.solid.remove(&pos),
+            };
+
+            match emission {
+                0 => self.emissions.remove(&pos),
+                _ => self.emissions.insert(pos, emission),
+            };
+
+            relight(self, [pos]);
+        }
+
+        fn light(&self, pos: Int3) -> Light {
+            self.lights[&pos]
+        }
+    }
+
+    impl LightSpace for TestSpace {
+        fn get_light(&self, pos: Int3) -> Option<Light> {
+            let is_sky = pos.y == self.sizes.y
+                && (0..self.sizes.x).contains(&pos.x)
+                && (0..self.sizes.z).contains(&pos.z);
+
+            match is_sky {
+                true => Some(Light::SKY),
+                false => self.lights.get(&pos).copied(),
+            }
+        }
+
+        fn set_light(&mut self, pos: Int3, light: Light) {
+            if let Some(value) = self.lights.get_mut(&pos) {
+                *value = light;
+            }
+        }
+
+        fn is_transparent(&self, pos: Int3) -> bool {
+            !self.solid.contains(&pos)
+        }
+
+        fn emission(&self, pos: Int3) -> u8 {
+            self.emissions.get(&pos).copied().unwrap_or(0)
+        }
+    }
+
+    /// Lights the whole space from scratch.
+    fn light_all(space: &mut TestSpace) {
+        for pos in SpaceIter::new(Int3::ZERO..space.sizes) {
+            let light = Light::new(0, space.emission(pos));
+            space.set_light(pos, light);
+        }
+
+        let sources = space
+            .sky_layer()
+            .chain(space.emissions.keys().copied())
+            .collect_vec();
+
+        propagate_from(space, sources);
+    }
+
+    #[test]
+    fn packs_channels() {
+        let light = Light::new(12, 3);
+
+        assert_eq!((light.sky(), light.block()), (12, 3));
+        assert_eq!(
+            light.with(LightChannel::Block, 100).block(),
+            Light::MAX_LEVEL
+        );
+        assert_eq!(Light::from_raw(light.into_raw()), light);
+    }
+
+    #[test]
+    fn sky_light_goes_down_and_fades_aside() {
+        let mut space = TestSpace::new(Int3::new(8, 8, 1));
+
+        // Roof over x in 0..4 at height 5.
+        for x in 0..4 {
+            space.solid.insert(Int3::new(x, 5, 0));
+        }
+        light_all(&mut space);
+
+        assert_eq!(space.light(Int3::new(7, 0, 0)).sky(), 15);
+        assert_eq!(space.light(Int3::new(3, 6, 0)).sky(), 15);
+        assert_eq!(space.light(Int3::new(3, 4, 0)).sky(), 14);
+        assert_eq!(space.light(Int3::new(0, 0, 0)).sky(), 11);
+    }
+
+    #[test]
+    fn block_light_fades_with_distance() {
+        let mut space = TestSpace::new(Int3::new(32, 1, 1));
+        space.emissions.insert(Int3::ZERO, 14);
+        light_all(&mut space);
+
+        assert_eq!(space.light(Int3::new(0, 0, 0)).block(), 14);
+        assert_eq!(space.light(Int3::new(5, 0, 0)).block(), 9);
+        assert_eq!(space.light(Int3::new(13, 0, 0)).block(), 1);
+        assert_eq!(space.light(Int3::new(14, 0, 0)).block(), 0);
+    }
+
+    #[test]
+    fn placing_and_breaking_voxels_is_incremental() {
+        let sizes = Int3::new(6, 6, 6);
+        let mut space = TestSpace::new(sizes);
+        light_all(&mut space);
+
+        let lamp = Int3::new(2, 2, 2);
+        space.set_voxel(lamp, true, 10);
+        assert_eq!(space.light(lamp + veci!(1, 0, 0)).block(), 9);
+        assert_eq!(space.light(lamp + veci!(3, 0, 0)).block(), 7);
+
+        // Roof blocks the sky.
+        for pos in SpaceIter::new(Int3::new(0, 5, 0)..Int3::new(6, 6, 6)) {
+            space.set_voxel(pos, true, 0);
+        }
+        assert_eq!(space.light(Int3::new(0, 0, 0)).sky(), 0);
+        assert_eq!(space.light(Int3::new(0, 0, 0)).block(), 4);
+
+        // Incremental result matches lighting from scratch.
+        let incremental = space.lights.clone();
+        light_all(&mut space);
+        assert_eq!(space.lights, incremental);
+
+        // Breaking the lamp and a hole in the roof.
+        space.set_voxel(lamp, false, 0);
+        space.set_voxel(Int3::new(0, 5, 0), false, 0);
+        assert_eq!(space.light(lamp).block(), 0);
+        assert_eq!(space.light(Int3::new(0, 0, 0)).sky(), 15);
+        assert_eq!(space.light(Int3::new(1, 0, 0)).sky(), 14);
+
+        let incremental = space.lights.clone();
+        light_all(&mut space);
+        assert_eq!(space.lights, incremental);
+    }
+}
@@ -19,6 +19,12 @@ pub struct FullVertex {
     pub position: (f32, f32, f32),
     pub tex_coords: (f32, f32),
     pub face_idx: u8,
+    /// Layer of the block texture array.
+    pub layer: u16,
+    /// [Material][crate::terrain::voxel::voxel_data::Material] index, the voxel id.
+    pub material: u16,
+    /// Raw [light][super::light::Light] of the voxel in front of the face.
+    pub light: u8,
 }
 
 /// Low-detailed vertex.
@@ -30,7 +36,9 @@ pub struct LowVertex {
 }
 
 // Implement Vertex structs as glium intended
-glium::implement_vertex!(FullVertex, position, tex_coords, face_idx);
+glium::implement_vertex!(
+    FullVertex, position, tex_coords, face_idx, layer, material, light
+);
 glium::implement_vertex!(LowVertex, position, color, face_idx);
 
 #[derive(Debug)]
@@ -203,7 +211,7 @@ impl ChunkMesh {
                 if !mesh.is_empty() {
                     mesh.render(
                         target,
-                        &draw_info.full_shader,
+                        &draw_info.full_shader.get(),
                         &draw_info.draw_params,
                         uniforms,
                     )?;
@@ -220,7 +228,7 @@ impl ChunkMesh {
                 if !mesh.is_empty() {
                     mesh.render(
                         target,
-                        &draw_info.low_shader,
+                        &draw_info.low_shader.get(),
                         &draw_info.draw_params,
                         uniforms,
                     )?;
@@ -1,24 +1,38 @@
 // #![allow(dead_code)]
 
+pub mod brush;
 pub mod chunk_array;
 pub mod commands;
+pub mod history;
 pub mod iterator;
+pub mod light;
 pub mod mesh;
+pub mod query;
+pub mod raycast;
+pub mod schedule;
+pub mod schematic;
 pub mod tasks;
+pub mod visibility;
 
 use super::voxel::{
-    self, LoweredVoxel, Voxel, generator,
+    self, LoweredVoxel, Voxel,
+    block_entity::{BlockEntities, BlockEntity},
+    generator,
     shape::{CubeDetailed, CubeLowered},
     voxel_data::{Id, data::*},
 };
 use crate::{
+    assets::{AssetManager, Handle},
     graphics::{camera::Camera, shader::Shader},
     prelude::*,
 };
 use chunk_array::ChunkAdj;
 use glium::{self as gl, DrawError, uniforms::Uniforms};
 use iterator::{CubeBorder, Sides};
+use light::Light;
 use mesh::{ChunkMesh, FullVertex, LowVertex};
+use spin::RwLock;
+use visibility::FaceConnections;
 
 pub mod prelude {
     pub use super::{
@@ -34,6 +48,16 @@ pub struct Chunk {
     pub pos: Atomic<Int3>,
     pub voxel_ids: Vec<Atomic<Id>>,
     pub info: Atomic<Info>,
+
+    /// [Block entities][BlockEntity] by local voxel position.
+    pub block_entities: RwLock<BlockEntities>,
+
+    /// Raw [light][Light] of voxels. It's empty until the chunk is lit and holds
+    /// a single value for chunks light can't get into, see [`Chunk::is_dark_filled`].
+    pub light: Vec<AtomicU8>,
+
+    /// Faces connected through the chunk, updated on meshing.
+    pub connections: Atomic<FaceConnections>,
 }
 
 impl Default for Chunk {
@@ -46,6 +70,9 @@ impl Default for Chunk {
                 is_filled: true,
                 active_lod: None,
             }),
+            block_entities: Default::default(),
+            light: Default::default(),
+            connections: Default::default(),
         }
     }
 }
@@ -135,6 +162,8 @@ impl Chunk {
 
     /// Gives [`Vec`] with full detail vertices mesh of [`Chunk`].
     pub fn make_vertices_detailed(&self, chunk_adj: ChunkAdj) -> Vec<FullVertex> {
+        self.connections.store(self.compute_connections(), Relaxed);
+
         let is_filled_and_blocked = self.is_filled() && Self::is_adj_filled(&chunk_adj);
         if self.is_empty() || is_filled_and_blocked {
             return vec![];
@@ -203,7 +232,10 @@ impl Chunk {
 
                 let mesh_builder = CubeDetailed::new(voxel.data);
                 for offset in side_iter {
-                    mesh_builder.by_offset(offset, voxel.pos.into(), &mut vertices);
+                    let light = self.face_light(&chunk_adj, voxel.pos + offset, offset);
+                    mesh_builder
+                        .light(light)
+                        .by_offset(offset, voxel.pos.into(), &mut vertices);
                 }
 
                 vertices
@@ -211,6 +243,24 @@ impl Chunk {
             .collect()
     }
 
+    /// Gives light of voxel at `pos` lighting the face of its neighbor
+    /// in direction `offset`. Missing or unlit voxels are lit by the sky.
+    fn face_light(&self, chunk_adj: &ChunkAdj, pos: Int3, offset: Int3) -> Light {
+        match self.get_light_global(pos) {
+            ChunkOption::Voxel(light) => light,
+
+            ChunkOption::OutsideChunk => match chunk_adj.by_offset(offset) {
+                Some(chunk) => match chunk.get_light_global(pos) {
+                    ChunkOption::Voxel(light) => light,
+                    ChunkOption::OutsideChunk | ChunkOption::Failed => Light::SKY,
+                },
+                None => Light::SKY,
+            },
+
+            ChunkOption::Failed => Light::SKY,
+        }
+    }
+
     fn optimize_chunk_adj_for_partitioning(
         mut chunk_adj: ChunkAdj,
         partition_coord: USize3,
@@ -287,7 +337,10 @@ impl Chunk {
 
                 let mesh_builder = CubeDetailed::new(voxel.data);
                 for offset in offset_iter {
-                    mesh_builder.by_offset(offset, voxel.pos.into(), &mut vertices);
+                    let light = self.face_light(&chunk_adj, voxel.pos + offset, offset);
+                    mesh_builder
+                        .light(light)
+                        .by_offset(offset, voxel.pos.into(), &mut vertices);
                 }
 
                 vertices
@@ -319,6 +372,8 @@ impl Chunk {
             "There's a separate function for LOD = 0! Use .make_vertices_detailed() instead!"
         );
 
+        self.connections.store(self.compute_connections(), Relaxed);
+
         let is_filled_and_blocked = self.is_filled() && Self::is_adj_filled(&chunk_adj);
         if self.is_empty() || is_filled_and_blocked {
             return vec![];
@@ -420,6 +475,67 @@ impl Chunk {
         }
     }
 
+    /// Checks if [chunk][Chunk] [light][Light] is computed.
+    pub fn is_lit(&self) -> bool {
+        !self.light.is_empty()
+    }
+
+    /// Checks if [chunk][Chunk] is filled with a single opaque non-emissive voxel,
+    /// so all of it stays dark and its light is stored as a single value.
+    pub fn is_dark_filled(&self) -> bool {
+        self.fill_id()
+            .is_some_and(|id| id != AIR_VOXEL_DATA.id && VOXEL_DATA[id as usize].emission == 0)
+    }
+
+    /// Makes all voxels dark. Unlit [chunk][Chunk] becomes lit.
+    pub fn reset_light(&mut self) {
+        let len = match self.is_dark_filled() {
+            true => 1,
+            false => Self::VOLUME,
+        };
+
+        self.light = std::iter::repeat_with(|| AtomicU8::new(Light::DARK.into_raw()))
+            .take(len)
+            .collect();
+    }
+
+    /// Gives [light][Light] of voxel at global position.
+    /// Gives [`ChunkOption::Failed`] if [chunk][Chunk] is not lit.
+    pub fn get_light_global(&self, global_pos: Int3) -> ChunkOption<Light> {
+        let Ok(local_pos) = Self::global_to_local_pos_checked(self.pos.load(Relaxed), global_pos)
+        else {
+            return ChunkOption::OutsideChunk;
+        };
+
+        if !self.is_lit() {
+            return ChunkOption::Failed;
+        }
+
+        let raw = match self.light.as_slice() {
+            [uniform] => uniform,
+            light => &light[Self::voxel_pos_to_idx_unchecked(local_pos)],
+        };
+
+        ChunkOption::Voxel(Light::from_raw(raw.load(Relaxed)))
+    }
+
+    /// Sets [light][Light] of voxel at global position. Does nothing if [chunk][Chunk]
+    /// is not lit or [dark filled][Chunk::is_dark_filled] or `global_pos` is outside of it.
+    pub fn set_light_global(&self, global_pos: Int3, light: Light) {
+        let Ok(local_pos) = Self::global_to_local_pos_checked(self.pos.load(Relaxed), global_pos)
+        else {
+            return;
+        };
+
+        if self.light.len() != Self::VOLUME {
+            return;
+        }
+
+        if let Some(value) = self.light.get(Self::voxel_pos_to_idx_unchecked(local_pos)) {
+            value.store(light.into_raw(), Relaxed);
+        }
+    }
+
     /// Givex voxel from global position.
     pub fn get_voxel_global(&self, global_pos: Int3) -> ChunkOption<Voxel> {
         let local_pos = Chunk::global_to_local_pos(self.pos.load(Relaxed), global_pos);
@@ -459,15 +575,20 @@ impl Chunk {
         Some(Voxel::new(global_pos, &VOXEL_DATA[id as usize]))
     }
 
-    /// Tests that chunk is visible by camera.
-    pub fn is_visible_by_camera(&self, camera: &mut Camera) -> bool {
+    /// Gives bounding box of the chunk in world space.
+    pub fn aabb(&self) -> Aabb {
         let global_chunk_pos = Chunk::global_pos(self.pos.load(Relaxed));
         let global_chunk_pos = vec3::from(global_chunk_pos) * Voxel::SIZE;
 
         let lo = global_chunk_pos - 0.5 * vec3::all(Voxel::SIZE);
         let hi = lo + vec3::all(Chunk::GLOBAL_SIZE) - 0.5 * vec3::all(Voxel::SIZE);
 
-        camera.is_aabb_in_view(Aabb::from_float3(lo, hi))
+        Aabb::from_float3(lo, hi)
+    }
+
+    /// Tests that chunk is visible by camera.
+    pub fn is_visible_by_camera(&self, camera: &mut Camera) -> bool {
+        camera.is_aabb_in_view(self.aabb())
     }
 
     /// Checks if [`Chunk`] is not already generated.
@@ -538,6 +659,9 @@ impl Chunk {
             pos: Atomic::new(chunk_pos),
             voxel_ids,
             info: Default::default(),
+            block_entities: Default::default(),
+            light: Default::default(),
+            connections: Default::default(),
         }
         .as_optimized()
     }
@@ -609,11 +733,57 @@ impl Chunk {
         if old_id != new_id {
             self.set_id(idx, new_id)?;
             self.optimize();
+            Self::replace_block_entity(self.block_entities.get_mut(), local_pos, new_id);
         }
 
         Ok(old_id)
     }
 
+    /// Destroys [block entity][BlockEntity] at `local_pos` and creates
+    /// a new one if voxel with `new_id` carries it.
+    fn replace_block_entity(block_entities: &mut BlockEntities, local_pos: Int3, new_id: Id) {
+        block_entities.remove(&local_pos);
+
+        if let Some(kind) = VOXEL_DATA[new_id as usize].block_entity {
+            block_entities.insert(local_pos, kind.instantiate());
+        }
+    }
+
+    /// Gives a copy of [block entity][BlockEntity] at global position `pos`.
+    pub fn get_block_entity(&self, pos: Int3) -> Option<BlockEntity> {
+        let local_pos = Self::global_to_local_pos(self.pos.load(Relaxed), pos);
+        self.block_entities.read().get(&local_pos).cloned()
+    }
+
+    /// Replaces [block entity][BlockEntity] at global position `pos` and returns the old one.
+    ///
+    /// # Error
+    ///
+    /// Returns [`Err`] if `pos` is not in this [`Chunk`] or voxel at `pos`
+    /// can't carry a block entity of that kind.
+    pub fn set_block_entity(
+        &self,
+        pos: Int3,
+        block_entity: BlockEntity,
+    ) -> Result<Option<BlockEntity>, EditError> {
+        let local_pos = Self::global_to_local_pos_checked(self.pos.load(Relaxed), pos)?;
+        let idx = Self::voxel_pos_to_idx_unchecked(local_pos);
+        let id = self.get_id(idx).ok_or(EditError::PosIdConversion(pos))?;
+
+        if VOXEL_DATA[id as usize].block_entity != Some(block_entity.kind()) {
+            return Err(EditError::NoBlockEntity { pos, id });
+        }
+
+        Ok(self.block_entities.write().insert(local_pos, block_entity))
+    }
+
+    /// Advances all [block entities][BlockEntity] of this [chunk][Chunk] by `dt` seconds.
+    pub fn tick_block_entities(&self, dt: f32) {
+        for block_entity in self.block_entities.write().values_mut() {
+            block_entity.tick(dt);
+        }
+    }
+
     /// Sets voxel's ids in range `pos_from..pos_to` to index [`new_id`][Id].
     pub fn fill_voxels(
         &mut self,
@@ -625,6 +795,17 @@ impl Chunk {
             return Err(EditError::InvalidId(new_id));
         }
 
+        self.fill_voxels_with(pos_from, pos_to, |_| new_id)
+    }
+
+    /// Sets voxel's ids in range `pos_from..pos_to` to ids given by `new_id`
+    /// from global voxel position. Ids given by `new_id` should be valid.
+    pub fn fill_voxels_with(
+        &mut self,
+        pos_from: Int3,
+        pos_to: Int3,
+        mut new_id: impl FnMut(Int3) -> Id,
+    ) -> Result<bool, EditError> {
         let pos = self.pos.load(Relaxed);
         let local_pos_from = Self::global_to_local_pos_checked(pos, pos_from)?;
 
@@ -634,20 +815,27 @@ impl Chunk {
         self.unoptimize();
 
         let mut is_changed = false;
+        let block_entities = self.block_entities.get_mut();
 
         for local_pos in SpaceIter::new(local_pos_from..local_pos_to) {
             // We can safely not to check idx due to previous check.
             let idx = Self::voxel_pos_to_idx_unchecked(local_pos);
+            let new_id = new_id(Self::local_to_global_pos(pos, local_pos));
+            debug_assert!(voxel::is_id_valid(new_id), "invalid id {new_id}");
 
-            let old_id = self.get_id(idx).expect("idx should be valid");
+            // * Safety:
+            // * Safe, because `idx` is valid and `self` is unoptimized.
+            let old_id = unsafe { self.voxel_ids.get_unchecked(idx).load(Relaxed) };
             if old_id != new_id {
                 is_changed = true;
 
                 // * Safety:
                 // * Safe, because `idx` is valid and `self` is unoptimized.
                 unsafe {
-                    self.set_id_fast(idx, new_id);
+                    self.voxel_ids.get_unchecked(idx).store(new_id, Release);
                 }
+
+                Self::replace_block_entity(block_entities, local_pos, new_id);
             }
         }
 
@@ -656,6 +844,48 @@ impl Chunk {
         Ok(is_changed)
     }
 
+    /// Sets voxels by global positions to their ids and returns `is_changed`.
+    /// Ids should be valid.
+    ///
+    /// # Error
+    ///
+    /// Returns [`Err`] if some position is not in this [`Chunk`]. Voxels before it are set.
+    pub fn set_voxels(
+        &mut self,
+        voxels: impl IntoIterator<Item = (Int3, Id)>,
+    ) -> Result<bool, EditError> {
+        let chunk_pos = self.pos.load(Relaxed);
+
+        self.unoptimize();
+
+        let mut result = Ok(false);
+        let block_entities = self.block_entities.get_mut();
+
+        for (pos, new_id) in voxels {
+            debug_assert!(voxel::is_id_valid(new_id), "invalid id {new_id}");
+
+            let local_pos = match Self::global_to_local_pos_checked(chunk_pos, pos) {
+                Ok(local_pos) => local_pos,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+
+            let idx = Self::voxel_pos_to_idx_unchecked(local_pos);
+            let old_id = self.voxel_ids[idx].swap(new_id, AcqRel);
+
+            if old_id != new_id {
+                result = Ok(true);
+                Self::replace_block_entity(block_entities, local_pos, new_id);
+            }
+        }
+
+        self.optimize();
+
+        result
+    }
+
     /// Gives iterator over all id-vectors in chunk (or relative to chunk voxel positions).
     pub fn local_pos_iter() -> SpaceIter {
         SpaceIter::new(Int3::ZERO..Self::SIZES.into())
@@ -720,6 +950,14 @@ impl Chunk {
             }
         }
 
+        // Voxels are about to change so light can get into the chunk.
+        if let [uniform] = self.light.as_slice() {
+            let raw = uniform.load(Relaxed);
+            self.light = std::iter::repeat_with(|| AtomicU8::new(raw))
+                .take(Self::VOLUME)
+                .collect();
+        }
+
         info.fill_type = FillType::Default;
 
         self.info.store(info, Release);
@@ -914,13 +1152,13 @@ pub enum ChunkRenderError {
 
 #[derive(Debug)]
 pub struct ChunkDrawBundle<'s> {
-    full_shader: Shader,
-    low_shader: Shader,
+    full_shader: Handle<Shader>,
+    low_shader: Handle<Shader>,
     draw_params: gl::DrawParameters<'s>,
 }
 
 impl<'s> ChunkDrawBundle<'s> {
-    pub fn new(facade: &dyn gl::backend::Facade) -> ChunkDrawBundle<'s> {
+    pub fn new(assets: &mut AssetManager) -> ChunkDrawBundle<'s> {
         // Chunk draw parameters
         let draw_params = gl::DrawParameters {
             depth: gl::Depth {
@@ -932,15 +1170,9 @@ impl<'s> ChunkDrawBundle<'s> {
             ..Default::default()
         };
 
-        // Create shaders
-        let full_shader = Shader::new("full_detail", "full_detail", facade)
-            .expect("failed to make full detail shader for ChunkDrawBundle");
-        let low_shader = Shader::new("low_detail", "low_detail", facade)
-            .expect("failed to make low detail shader for ChunkDrawBundle");
-
         ChunkDrawBundle {
-            full_shader,
-            low_shader,
+            full_shader: assets.load(("full_detail", "full_detail")),
+            low_shader: assets.load(("low_detail", "low_detail")),
             draw_params,
         }
     }
@@ -1017,4 +1249,61 @@ pub enum EditError {
     IdxOutOfBounds { idx: usize, len: usize },
     #[error("invalid id {0}")]
     InvalidId(Id),
+    #[error("voxel with id {id} in {pos} can't carry such block entity")]
+    NoBlockEntity { pos: Int3, id: Id },
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::terrain::voxel::block_entity::BlockEntityKind};
+
+    #[test]
+    fn block_entity_follows_voxel() {
+        let mut chunk = Chunk::new_same_filled(Int3::ZERO, AIR_VOXEL_DATA.id);
+        let pos = Int3::new(1, 2, 3);
+
+        chunk.set_voxel(pos, CHEST_VOXEL_DATA.id).unwrap();
+        assert_eq!(
+            chunk.get_block_entity(pos).map(|entity| entity.kind()),
+            Some(BlockEntityKind::Container),
+        );
+
+        chunk.set_voxel(pos, AIR_VOXEL_DATA.id).unwrap();
+        assert_eq!(chunk.get_block_entity(pos), None);
+    }
+
+    #[test]
+    fn fill_creates_block_entities() {
+        let mut chunk = Chunk::new_same_filled(Int3::ZERO, AIR_VOXEL_DATA.id);
+
+        chunk
+            .fill_voxels(Int3::ZERO, Int3::new(2, 2, 2), SIGN_VOXEL_DATA.id)
+            .unwrap();
+        assert_eq!(chunk.block_entities.read().len(), 8);
+
+        chunk
+            .fill_voxels(Int3::ZERO, Int3::new(2, 2, 1), STONE_VOXEL_DATA.id)
+            .unwrap();
+        assert_eq!(chunk.block_entities.read().len(), 4);
+    }
+
+    #[test]
+    fn dark_filled_chunk_stores_single_light() {
+        let mut chunk = Chunk::new_same_filled(Int3::ZERO, STONE_VOXEL_DATA.id);
+        chunk.reset_light();
+        assert_eq!(chunk.light.len(), 1);
+
+        let pos = Int3::new(1, 2, 3);
+        assert_eq!(chunk.get_light_global(pos), ChunkOption::Voxel(Light::DARK));
+
+        chunk.set_voxel(pos, AIR_VOXEL_DATA.id).unwrap();
+        assert_eq!(chunk.light.len(), Chunk::VOLUME);
+
+        chunk.set_light_global(pos, Light::SKY);
+        assert_eq!(chunk.get_light_global(pos), ChunkOption::Voxel(Light::SKY));
+        assert_eq!(
+            chunk.get_light_global(Int3::ZERO),
+            ChunkOption::Voxel(Light::DARK),
+        );
+    }
 }
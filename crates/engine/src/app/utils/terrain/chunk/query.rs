@@ -0,0 +1,306 @@
+//!
+//! Spatial queries over voxels of the [chunk array][ChunkArray].
+//!
+//! Queries walk the region chunk by chunk and never read voxels of
+//! [same filled][FillType::AllSame] chunks. Ungenerated chunks are skipped.
+//!
+
+use {
+    super::{Chunk, ChunkOption, FillType, chunk_array::ChunkArray},
+    crate::{
+        physics::collision::{self, Movement},
+        prelude::*,
+        terrain::voxel::{
+            Voxel,
+            voxel_data::{Id, data::*},
+        },
+    },
+    std::ops::Range,
+};
+
+/// Part of a query region inside one generated [chunk][Chunk].
+#[derive(Clone, Debug)]
+struct ChunkRegion {
+    chunk: Arc<Chunk>,
+    range: Range<Int3>,
+}
+
+impl ChunkRegion {
+    fn volume(&self) -> usize {
+        let sizes = self.range.end - self.range.start;
+        (sizes.x * sizes.y * sizes.z) as usize
+    }
+
+    /// Gives voxels of the region. Same filled chunks are not read.
+    fn voxels(self) -> impl Iterator<Item = Voxel> {
+        let fill_id = self.chunk.fill_id();
+
+        SpaceIter::new(self.range).filter_map(move |pos| match fill_id {
+            Some(id) => Some(Voxel::new(pos, &VOXEL_DATA[id as usize])),
+            None => match self.chunk.get_voxel_global(pos) {
+                ChunkOption::Voxel(voxel) => Some(voxel),
+                ChunkOption::OutsideChunk | ChunkOption::Failed => None,
+            },
+        })
+    }
+
+    /// Gives the point of the region closest to `pos`.
+    fn closest_to(&self, pos: Int3) -> Int3 {
+        component_max(
+            self.range.start,
+            component_min(pos, self.range.end - Int3::ONE),
+        )
+    }
+}
+
+fn component_min(lhs: Int3, rhs: Int3) -> Int3 {
+    Int3::new(lhs.x.min(rhs.x), lhs.y.min(rhs.y), lhs.z.min(rhs.z))
+}
+
+fn component_max(lhs: Int3, rhs: Int3) -> Int3 {
+    Int3::new(lhs.x.max(rhs.x), lhs.y.max(rhs.y), lhs.z.max(rhs.z))
+}
+
+/// Checks if voxel with `id` blocks movement, see [`ChunkArray::is_voxel_solid`].
+fn is_solid_id(id: Id) -> bool {
+    id != AIR_VOXEL_DATA.id && VOXEL_DATA[id as usize].fluid.is_none()
+}
+
+impl ChunkArray {
+    /// Splits `range` of voxel positions into parts inside generated chunks.
+    fn chunk_regions(&self, range: Range<Int3>) -> impl Iterator<Item = ChunkRegion> + '_ {
+        let is_empty = range
+            .start
+            .as_array()
+            .into_iter()
+            .zip(range.end.as_array())
+            .any(|(start, end)| end <= start);
+
+        let chunk_range = match is_empty {
+            true => Int3::ZERO..Int3::ZERO,
+            false => {
+                Chunk::local_pos(range.start)..Chunk::local_pos(range.end - Int3::ONE) + Int3::ONE
+            }
+        };
+
+        SpaceIter::new(chunk_range).filter_map(move |chunk_pos| {
+            let chunk = self.get_chunk_by_pos(chunk_pos)?;
+
+            if !chunk.is_generated() {
+                return None;
+            }
+
+            let chunk_start = Chunk::global_pos(chunk_pos);
+            let chunk_end = chunk_start + Int3::from(Chunk::SIZES);
+
+            Some(ChunkRegion {
+                chunk,
+                range: component_max(range.start, chunk_start)..component_min(range.end, chunk_end),
+            })
+        })
+    }
+
+    /// Gives voxels with positions in `range`.
+    pub fn voxels_in_box(&self, range: Range<Int3>) -> impl Iterator<Item = Voxel> + '_ {
+        self.chunk_regions(range).flat_map(ChunkRegion::voxels)
+    }
+
+    /// Gives voxels overlapping `aabb` in world coordinates.
+    pub fn voxels_in_aabb(&self, aabb: Aabb) -> impl Iterator<Item = Voxel> + '_ {
+        self.voxels_in_box(collision::overlapping_range(aabb))
+    }
+
+    /// Gives voxels not further than `radius` voxels from `center`.
+    pub fn voxels_in_sphere(&self, center: Int3, radius: i32) -> impl Iterator<Item = Voxel> + '_ {
+        let radius_sqr = radius * radius;
+        let range = center - Int3::all(radius)..center + Int3::all(radius + 1);
+
+        self.chunk_regions(range)
+            .filter(move |region| (region.closest_to(center) - center).sqr() <= radius_sqr)
+            .flat_map(ChunkRegion::voxels)
+            .filter(move |voxel| (voxel.pos - center).sqr() <= radius_sqr)
+    }
+
+    /// Gives position of the highest solid voxel in column at `x` and `z`.
+    pub fn top_solid_voxel(&self, x: i32, z: i32) -> Option<Int3> {
+        let (start_pos, end_pos) = Self::pos_bounds(self.sizes);
+        let range = Int3::new(x, Chunk::global_pos(start_pos).y, z)
+            ..Int3::new(x + 1, Chunk::global_pos(end_pos).y, z + 1);
+
+        let regions = self.chunk_regions(range).collect_vec();
+
+        regions.into_iter().rev().find_map(|region| {
+            match region.chunk.info.load(Relaxed).fill_type {
+                FillType::AllSame(id) => is_solid_id(id).then_some(region.range.end - Int3::ONE),
+                FillType::Default => region
+                    .voxels()
+                    .filter(|voxel| is_solid_id(voxel.data.id))
+                    .last()
+                    .map(|voxel| voxel.pos),
+            }
+        })
+    }
+
+    /// Counts voxels with `id` in `range`.
+    pub fn count_voxels(&self, range: Range<Int3>, id: Id) -> usize {
+        self.chunk_regions(range)
+            .map(|region| match region.chunk.fill_id() {
+                Some(fill_id) if fill_id == id => region.volume(),
+                Some(_) => 0,
+                None => region.voxels().filter(|voxel| voxel.data.id == id).count(),
+            })
+            .sum()
+    }
+
+    /// Gives position of the nearest to `pos` voxel with `id`
+    /// not further than `max_distance` voxels.
+    pub fn nearest_voxel(&self, pos: Int3, id: Id, max_distance: i32) -> Option<Int3> {
+        let range = pos - Int3::all(max_distance)..pos + Int3::all(max_distance + 1);
+
+        let mut regions = self
+            .chunk_regions(range)
+            .filter(|region| region.chunk.fill_id().is_none_or(|fill_id| fill_id == id))
+            .map(|region| ((region.closest_to(pos) - pos).sqr(), region))
+            .collect_vec();
+        regions.sort_by_key(|&(distance_sqr, _)| distance_sqr);
+
+        let mut nearest: Option<(i32, Int3)> = None;
+
+        for (region_distance_sqr, region) in regions {
+            let best_sqr = nearest.map_or(max_distance * max_distance, |(sqr, _)| sqr);
+            if best_sqr < region_distance_sqr {
+                break;
+            }
+
+            let candidate = match region.chunk.fill_id() {
+                Some(_) => Some(region.closest_to(pos)),
+                None => region
+                    .voxels()
+                    .filter(|voxel| voxel.data.id == id)
+                    .map(|voxel| voxel.pos)
+                    .min_by_key(|&voxel_pos| (voxel_pos - pos).sqr()),
+            };
+
+            if let Some(candidate) = candidate {
+                let distance_sqr = (candidate - pos).sqr();
+
+                if distance_sqr <= best_sqr && nearest.is_none_or(|(sqr, _)| distance_sqr < sqr) {
+                    nearest = Some((distance_sqr, candidate));
+                }
+            }
+        }
+
+        nearest.map(|(_, pos)| pos)
+    }
+
+    /// Moves `aabb` by `motion` stopping at solid voxels. Ungenerated chunks block movement.
+    pub fn sweep_aabb(&self, aabb: Aabb, motion: vec3) -> Movement {
+        collision::move_aabb(aabb, motion, |pos| {
+            !self.is_voxel_ready(pos) || self.is_voxel_solid(pos)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Array of 2x2x2 chunks with stone below `y = 0`, air above and a few marked voxels.
+    fn test_array() -> ChunkArray {
+        let sizes = USize3::all(2);
+        let (start_pos, end_pos) = ChunkArray::pos_bounds(sizes);
+
+        let chunks = SpaceIter::new(start_pos..end_pos)
+            .map(|chunk_pos| {
+                let id = match chunk_pos.y < 0 {
+                    true => STONE_VOXEL_DATA.id,
+                    false => AIR_VOXEL_DATA.id,
+                };
+
+                Arc::new(Chunk::new_same_filled(chunk_pos, id))
+            })
+            .collect_vec();
+
+        // Chunks are left unlit as queries do not need light.
+        let mut array = ChunkArray {
+            meshes: (0..chunks.len()).map(|_| Default::default()).collect(),
+            chunks,
+            sizes,
+            ..Default::default()
+        };
+
+        for pos in [Int3::new(3, 0, 3), Int3::new(3, 1, 3), Int3::new(-5, 0, 2)] {
+            array.set_voxel(pos, LOG_VOXEL_DATA.id).unwrap();
+        }
+
+        array
+    }
+
+    #[test]
+    fn box_and_sphere_give_voxels_inside() {
+        let array = test_array();
+
+        let range = Int3::new(-1, -1, -1)..Int3::new(2, 2, 2);
+        let voxels = array.voxels_in_box(range.clone()).collect_vec();
+        assert_eq!(voxels.len(), 27);
+        assert!(voxels.iter().all(|voxel| {
+            let pos = voxel.pos;
+            let id = match pos.y < 0 {
+                true => STONE_VOXEL_DATA.id,
+                false => AIR_VOXEL_DATA.id,
+            };
+
+            SpaceIter::new(range.clone()).any(|other| other == pos) && voxel.data.id == id
+        }));
+
+        let sphere = array.voxels_in_sphere(Int3::ZERO, 2).collect_vec();
+        assert_eq!(sphere.len(), 33);
+        assert!(sphere.iter().all(|voxel| voxel.pos.sqr() <= 4));
+    }
+
+    #[test]
+    fn top_solid_voxel_finds_highest() {
+        let array = test_array();
+
+        assert_eq!(array.top_solid_voxel(3, 3), Some(Int3::new(3, 1, 3)));
+        assert_eq!(array.top_solid_voxel(0, 0), Some(Int3::new(0, -1, 0)));
+        assert_eq!(array.top_solid_voxel(100, 0), None);
+    }
+
+    #[test]
+    fn count_and_nearest_skip_uniform_chunks() {
+        let array = test_array();
+
+        let size = Chunk::SIZE as i32;
+        let range = Int3::all(-size)..Int3::all(size);
+        assert_eq!(array.count_voxels(range.clone(), LOG_VOXEL_DATA.id), 3);
+        assert_eq!(
+            array.count_voxels(range.clone(), STONE_VOXEL_DATA.id),
+            Chunk::VOLUME * 4
+        );
+
+        assert_eq!(
+            array.nearest_voxel(Int3::new(-4, 1, 2), LOG_VOXEL_DATA.id, 8),
+            Some(Int3::new(-5, 0, 2)),
+        );
+        assert_eq!(
+            array.nearest_voxel(Int3::new(0, 5, 0), STONE_VOXEL_DATA.id, 8),
+            Some(Int3::new(0, -1, 0)),
+        );
+        assert_eq!(
+            array.nearest_voxel(Int3::new(0, 5, 0), LOG_VOXEL_DATA.id, 2),
+            None
+        );
+    }
+
+    #[test]
+    fn aabb_sweeps_onto_ground() {
+        let array = test_array();
+
+        let aabb = Aabb::from_float3(vec3::new(-0.25, 3.0, -0.25), vec3::new(0.25, 3.5, 0.25));
+        let movement = array.sweep_aabb(aabb, vec3::new(0.0, -10.0, 0.0));
+
+        assert!(movement.is_blocked[1]);
+        assert!((movement.aabb.lo().y + 0.5 * Voxel::SIZE).abs() < 0.01);
+    }
+}
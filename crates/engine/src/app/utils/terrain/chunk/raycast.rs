@@ -0,0 +1,226 @@
+//!
+//! Exact voxel traversal along a ray by Amanatides and Woo.
+//!
+
+use {
+    crate::{prelude::*, terrain::voxel::Voxel},
+    math_linear::math::ray::space_3d::Line,
+};
+
+/// Voxel cell crossed by a ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayCell {
+    pub pos: Int3,
+    /// Normal of the face the ray entered through. Zero for the starting cell.
+    pub normal: Int3,
+    /// Distance from ray origin to the entry point.
+    pub distance: f32,
+}
+
+/// Iterator over all voxel cells crossed by a ray in order.
+#[derive(Clone, Debug)]
+pub struct RayCells {
+    cell: [i32; 3],
+    step: [i32; 3],
+    t_max: [f32; 3],
+    t_delta: [f32; 3],
+    max_distance: f32,
+    next: Option<RayCell>,
+}
+
+impl RayCells {
+    /// Constructs traversal of cells along `ray` up to `max_distance`.
+    pub fn new(ray: Line, max_distance: f32) -> Self {
+        let direction = match ray.direction.len() {
+            0.0 => vec3::zero(),
+            len => ray.direction / len,
+        };
+
+        // Voxel `pos` occupies `pos - 0.5..pos + 0.5` so shifted coordinates
+        // make cell bounds integer.
+        let origin = (ray.origin / Voxel::SIZE + vec3::all(0.5)).as_array();
+        let direction = (direction / Voxel::SIZE).as_array();
+
+        let cell = origin.map(|coord| coord.floor() as i32);
+        let step = direction.map(|coord| coord.signum() as i32 * (coord != 0.0) as i32);
+
+        let t_delta = direction.map(|coord| match coord {
+            0.0 => f32::INFINITY,
+            coord => coord.recip().abs(),
+        });
+
+        let t_max = array_init::array_init(|i| match step[i] {
+            0 => f32::INFINITY,
+            1 => (cell[i] as f32 + 1.0 - origin[i]) * t_delta[i],
+            _ => (origin[i] - cell[i] as f32) * t_delta[i],
+        });
+
+        Self {
+            cell,
+            step,
+            t_max,
+            t_delta,
+            max_distance,
+            next: Some(RayCell {
+                pos: Int3::from(cell),
+                normal: Int3::ZERO,
+                distance: 0.0,
+            }),
+        }
+    }
+
+    fn advance(&mut self) -> Option<RayCell> {
+        let axis = (0..3).min_by(|&a, &b| self.t_max[a].total_cmp(&self.t_max[b]))?;
+        let distance = self.t_max[axis];
+
+        if !distance.is_finite() || self.max_distance < distance {
+            return None;
+        }
+
+        self.cell[axis] += self.step[axis];
+        self.t_max[axis] += self.t_delta[axis];
+
+        let mut normal = [0; 3];
+        normal[axis] = -self.step[axis];
+
+        Some(RayCell {
+            pos: Int3::from(self.cell),
+            normal: Int3::from(normal),
+            distance,
+        })
+    }
+}
+
+impl Iterator for RayCells {
+    type Item = RayCell;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next.take()?;
+        self.next = self.advance();
+        Some(result)
+    }
+}
+
+/// First solid voxel hit by a ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub pos: Int3,
+    /// Normal of the hit face. Zero if ray starts inside of the voxel.
+    pub normal: Int3,
+    /// Distance from ray origin to the hit point.
+    pub distance: f32,
+    /// Last empty cell before the hit one.
+    pub prev_pos: Option<Int3>,
+}
+
+/// Casts `ray` up to `max_distance` and gives first cell where `is_solid` is true.
+pub fn cast_ray(
+    ray: Line,
+    max_distance: f32,
+    mut is_solid: impl FnMut(Int3) -> bool,
+) -> Option<RayHit> {
+    let mut prev_pos = None;
+
+    for cell in RayCells::new(ray, max_distance) {
+        if is_solid(cell.pos) {
+            return Some(RayHit {
+                pos: cell.pos,
+                normal: cell.normal,
+                distance: cell.distance,
+                prev_pos,
+            });
+        }
+
+        prev_pos = Some(cell.pos);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cast_at(origin: vec3, direction: vec3, solid: &[Int3]) -> Option<RayHit> {
+        cast_ray(Line::new(origin, direction), 100.0, |pos| {
+            solid.contains(&pos)
+        })
+    }
+
+    #[test]
+    fn straight_hit() {
+        let hit = cast_at(
+            vec3::zero(),
+            vec3::new(1.0, 0.0, 0.0),
+            &[Int3::new(5, 0, 0)],
+        )
+        .unwrap();
+
+        assert_eq!(hit.pos, Int3::new(5, 0, 0));
+        assert_eq!(hit.normal, Int3::new(-1, 0, 0));
+        assert_eq!(hit.prev_pos, Some(Int3::new(4, 0, 0)));
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn negative_direction() {
+        let hit = cast_at(
+            vec3::new(0.2, 0.3, 0.1),
+            vec3::new(0.0, -2.0, 0.0),
+            &[Int3::new(0, -3, 0)],
+        )
+        .unwrap();
+
+        assert_eq!(hit.normal, Int3::new(0, 1, 0));
+        assert_eq!(hit.prev_pos, Some(Int3::new(0, -2, 0)));
+        assert!((hit.distance - 2.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corners_are_not_skipped() {
+        let hit = cast_at(
+            vec3::zero(),
+            vec3::new(1.0, 0.9, 0.0),
+            &[Int3::new(1, 0, 0)],
+        )
+        .unwrap();
+        assert_eq!(hit.normal, Int3::new(-1, 0, 0));
+
+        let hit = cast_at(
+            vec3::zero(),
+            vec3::new(0.9, 1.0, 0.0),
+            &[Int3::new(0, 1, 0)],
+        )
+        .unwrap();
+        assert_eq!(hit.normal, Int3::new(0, -1, 0));
+    }
+
+    #[test]
+    fn cells_are_adjacent() {
+        let cells: Vec<_> =
+            RayCells::new(Line::new(vec3::all(0.3), vec3::new(0.7, -0.4, 1.1)), 30.0)
+                .map(|cell| cell.pos)
+                .collect();
+
+        for (a, b) in cells.iter().zip(cells.iter().skip(1)) {
+            let diff = (*b - *a).abs();
+            assert_eq!(diff.x + diff.y + diff.z, 1);
+        }
+    }
+
+    #[test]
+    fn start_inside_and_miss() {
+        let hit = cast_at(vec3::zero(), vec3::new(0.0, 0.0, 1.0), &[Int3::ZERO]).unwrap();
+        assert_eq!(hit.prev_pos, None);
+        assert_eq!(hit.normal, Int3::ZERO);
+
+        assert_eq!(
+            cast_at(
+                vec3::zero(),
+                vec3::new(0.0, 0.0, 1.0),
+                &[Int3::new(0, 0, 200)]
+            ),
+            None
+        );
+    }
+}
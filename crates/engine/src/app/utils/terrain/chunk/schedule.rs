@@ -0,0 +1,223 @@
+//!
+//! Queue of voxel updates scheduled for later ticks.
+//!
+
+use {
+    crate::{app::utils::reinterpreter::ReinterpretError, prelude::*},
+    std::collections::BTreeMap,
+};
+
+/// Order of updates due at the same tick, lower ones go first.
+pub type Priority = i8;
+
+/// Update of voxel at `pos` in `delay` ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScheduledUpdate {
+    pub pos: Int3,
+    pub delay: u64,
+    pub priority: Priority,
+}
+
+/// Voxel positions to update at given ticks. Each position is scheduled at most once,
+/// positions of one tick are updated by [priority][Priority] and then in order of scheduling.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpdateQueue {
+    /// Number of ticks passed.
+    pub tick: u64,
+    scheduled: BTreeMap<(u64, Priority), Vec<Int3>>,
+    positions: HashSet<Int3>,
+}
+
+impl UpdateQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules update of `pos` in `delay` ticks if it is not scheduled yet.
+    pub fn schedule(&mut self, pos: Int3, delay: u64, priority: Priority) {
+        if self.positions.insert(pos) {
+            self.scheduled
+                .entry((self.tick + delay.max(1), priority))
+                .or_default()
+                .push(pos);
+        }
+    }
+
+    pub fn is_scheduled(&self, pos: Int3) -> bool {
+        self.positions.contains(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Gives all pending updates in order they are going to happen.
+    pub fn pending(&self) -> impl Iterator<Item = ScheduledUpdate> + '_ {
+        self.scheduled
+            .iter()
+            .flat_map(move |(&(tick, priority), positions)| {
+                positions.iter().map(move |&pos| ScheduledUpdate {
+                    pos,
+                    delay: tick.saturating_sub(self.tick),
+                    priority,
+                })
+            })
+    }
+
+    /// Moves to the next tick and gives at most `limit` positions due at it.
+    /// Positions over the limit are left for the next tick.
+    pub fn advance(&mut self, limit: usize) -> Vec<Int3> {
+        self.tick += 1;
+
+        let mut result = Vec::new();
+
+        while result.len() < limit {
+            let Some(mut entry) = self.scheduled.first_entry() else {
+                break;
+            };
+
+            if self.tick < entry.key().0 {
+                break;
+            }
+
+            let positions = entry.get_mut();
+            let n_taken = positions.len().min(limit - result.len());
+            result.extend(positions.drain(..n_taken));
+
+            if positions.is_empty() {
+                entry.remove();
+            }
+        }
+
+        // Leftovers are moved to the next tick.
+        while let Some(entry) = self.scheduled.first_entry() {
+            let (tick, priority) = *entry.key();
+            if self.tick < tick {
+                break;
+            }
+
+            let leftovers = entry.remove();
+            let next = self.scheduled.entry((self.tick + 1, priority)).or_default();
+            next.splice(0..0, leftovers);
+        }
+
+        for pos in result.iter() {
+            self.positions.remove(pos);
+        }
+
+        result
+    }
+}
+
+impl AsBytes for ScheduledUpdate {
+    fn as_bytes(&self) -> Vec<u8> {
+        compose! {
+            self.pos.as_bytes(),
+            self.delay.as_bytes(),
+            self.priority.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for ScheduledUpdate {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        read! {
+            source,
+            let pos,
+            let delay,
+            let priority,
+        }
+
+        Ok(Self {
+            pos,
+            delay,
+            priority,
+        })
+    }
+}
+
+impl StaticSize for ScheduledUpdate {
+    fn static_size() -> usize {
+        Int3::static_size() + u64::static_size() + Priority::static_size()
+    }
+}
+
+impl AsBytes for UpdateQueue {
+    fn as_bytes(&self) -> Vec<u8> {
+        self.pending().collect_vec().as_bytes()
+    }
+}
+
+impl FromBytes for UpdateQueue {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        let mut result = Self::new();
+
+        for update in Vec::<ScheduledUpdate>::from_bytes(source)? {
+            result.schedule(update.pos, update.delay, update.priority);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_come_in_order() {
+        let mut queue = UpdateQueue::new();
+
+        queue.schedule(Int3::new(0, 0, 0), 2, 0);
+        queue.schedule(Int3::new(1, 0, 0), 1, 0);
+        queue.schedule(Int3::new(2, 0, 0), 2, 0);
+        queue.schedule(Int3::new(0, 0, 0), 1, 0);
+        queue.schedule(Int3::new(3, 0, 0), 2, -1);
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.advance(usize::MAX), vec![Int3::new(1, 0, 0)]);
+        assert_eq!(
+            queue.advance(usize::MAX),
+            vec![Int3::new(3, 0, 0), Int3::new(0, 0, 0), Int3::new(2, 0, 0)],
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn limited_updates_are_postponed() {
+        let mut queue = UpdateQueue::new();
+
+        for x in 0..5 {
+            queue.schedule(Int3::new(x, 0, 0), 1, 0);
+        }
+        queue.schedule(Int3::new(0, 1, 0), 2, 0);
+
+        assert_eq!(queue.advance(3).len(), 3);
+        assert_eq!(
+            queue.advance(usize::MAX),
+            vec![Int3::new(3, 0, 0), Int3::new(4, 0, 0), Int3::new(0, 1, 0)],
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn reinterpret_queue() {
+        let mut before = UpdateQueue::new();
+        before.schedule(Int3::new(1, 2, 3), 5, 0);
+        before.schedule(Int3::new(-4, 0, 7), 1, 2);
+        before.schedule(Int3::new(0, 0, 0), 5, -1);
+        before.advance(usize::MAX);
+
+        let after = UpdateQueue::from_bytes(&before.as_bytes()).unwrap();
+
+        assert_eq!(
+            before.pending().collect_vec(),
+            after.pending().collect_vec()
+        );
+    }
+}
@@ -0,0 +1,518 @@
+//!
+//! Schematics: voxel regions extracted from [chunk array][super::chunk_array::ChunkArray]
+//! that can be transformed, pasted and stored on disk.
+//!
+//! File layout: sizes, palette of voxel names and a body of run-length encoded palette
+//! indices. Run symbols are compressed with Huffman's codes.
+//!
+
+use {
+    crate::{
+        prelude::*,
+        terrain::voxel::{
+            self,
+            voxel_data::{Id, data::*},
+        },
+    },
+    std::{fs, io, path::Path},
+};
+
+/// Coordinate axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+}
+
+/// Axis-aligned box of voxel ids.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    pub sizes: USize3,
+    /// Voxel ids in [`SpaceIter`] order of `Int3::ZERO..sizes`.
+    pub voxel_ids: Vec<Id>,
+}
+
+impl Schematic {
+    pub const FILE_EXTENSION: &str = "schem";
+
+    /// Upper bound of voxels preallocated while reading, larger schematics grow as read.
+    const MAX_PREALLOCATED_VOLUME: usize = 1 << 20;
+
+    /// Constructs schematic from ids in [`SpaceIter`] order.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `voxel_ids` length is not equal to `sizes` volume.
+    pub fn new(sizes: USize3, voxel_ids: Vec<Id>) -> Self {
+        assert_eq!(
+            sizes.x * sizes.y * sizes.z,
+            voxel_ids.len(),
+            "voxel ids should have same length as sizes volume",
+        );
+
+        Self { sizes, voxel_ids }
+    }
+
+    /// Gives index of voxel with position `pos` relative to schematic.
+    pub fn pos_to_idx(sizes: USize3, pos: USize3) -> usize {
+        (pos.x * sizes.y + pos.y) * sizes.z + pos.z
+    }
+
+    /// Gives id of voxel with position `pos` relative to schematic.
+    pub fn get_id(&self, pos: USize3) -> Id {
+        self.voxel_ids[Self::pos_to_idx(self.sizes, pos)]
+    }
+
+    /// Iterates over voxel positions relative to schematic and their ids.
+    pub fn voxels(&self) -> impl Iterator<Item = (Int3, Id)> + '_ {
+        SpaceIter::new(Int3::ZERO..Int3::from(self.sizes)).zip(self.voxel_ids.iter().copied())
+    }
+
+    /// Gives voxels to set to paste schematic with minimal corner at `pos`.
+    pub fn paste_voxels(&self, pos: Int3, skip_air: bool) -> impl Iterator<Item = (Int3, Id)> + '_ {
+        self.voxels()
+            .filter(move |&(_, id)| !skip_air || id != AIR_VOXEL_DATA.id)
+            .map(move |(offset, id)| (pos + offset, id))
+    }
+
+    /// Makes new schematic by moving each voxel by `map` from its old position
+    /// into a box of `sizes`.
+    fn remapped(&self, sizes: USize3, map: impl Fn(USize3) -> USize3) -> Self {
+        let mut voxel_ids = vec![AIR_VOXEL_DATA.id; self.voxel_ids.len()];
+
+        for (pos, id) in self.voxels() {
+            voxel_ids[Self::pos_to_idx(sizes, map(USize3::from(pos)))] = id;
+        }
+
+        Self::new(sizes, voxel_ids)
+    }
+
+    /// Rotates schematic by 90 degrees around `axis` `n_quarters` times.
+    pub fn rotated(&self, axis: Axis, n_quarters: u32) -> Self {
+        let mut result = self.clone();
+
+        for _ in 0..n_quarters % 4 {
+            let s = result.sizes;
+
+            result = match axis {
+                Axis::X => result.remapped(USize3::new(s.x, s.z, s.y), |p| {
+                    USize3::new(p.x, p.z, s.y - 1 - p.y)
+                }),
+                Axis::Y => result.remapped(USize3::new(s.z, s.y, s.x), |p| {
+                    USize3::new(p.z, p.y, s.x - 1 - p.x)
+                }),
+                Axis::Z => result.remapped(USize3::new(s.y, s.x, s.z), |p| {
+                    USize3::new(s.y - 1 - p.y, p.x, p.z)
+                }),
+            };
+        }
+
+        result
+    }
+
+    /// Mirrors schematic along `axis`.
+    pub fn mirrored(&self, axis: Axis) -> Self {
+        let s = self.sizes;
+
+        self.remapped(s, |p| match axis {
+            Axis::X => USize3::new(s.x - 1 - p.x, p.y, p.z),
+            Axis::Y => USize3::new(p.x, s.y - 1 - p.y, p.z),
+            Axis::Z => USize3::new(p.x, p.y, s.z - 1 - p.z),
+        })
+    }
+
+    /// Saves schematic to file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SchematicError> {
+        let path = path.as_ref();
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, self.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Loads schematic from file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchematicError> {
+        let bytes = fs::read(path)?;
+        Ok(Self::from_bytes(&bytes)?)
+    }
+
+    /// Splits ids into runs of palette indices.
+    fn runs(&self, palette: &[Id]) -> Vec<(u16, u32)> {
+        let mut runs: Vec<(u16, u32)> = vec![];
+
+        for &id in self.voxel_ids.iter() {
+            let idx = palette
+                .iter()
+                .position(|&palette_id| palette_id == id)
+                .expect("palette should contain all ids") as u16;
+
+            match runs.last_mut() {
+                Some((last, len)) if *last == idx => *len += 1,
+                _ => runs.push((idx, 1)),
+            }
+        }
+
+        runs
+    }
+}
+
+impl AsBytes for Schematic {
+    fn as_bytes(&self) -> Vec<u8> {
+        use {bit_vec::BitVec, huffman_compress as hc};
+
+        let palette: Vec<Id> = self.voxel_ids.iter().copied().unique().collect();
+        let runs = self.runs(&palette);
+
+        let mut freqs = HashMap::<u16, usize>::new();
+        for &(idx, _) in runs.iter() {
+            *freqs.entry(idx).or_default() += 1;
+        }
+
+        // Huffman's code of single symbol is empty so palette index is implied.
+        let mut bits = BitVec::new();
+        if 1 < palette.len() {
+            let (book, _) =
+                hc::CodeBuilder::from_iter(freqs.iter().map(|(&k, &v)| (k, v))).finish();
+
+            for (idx, _) in runs.iter() {
+                book.encode(&mut bits, idx)
+                    .expect("palette index should be in the book");
+            }
+        }
+
+        let names = palette
+            .iter()
+            .flat_map(|&id| VOXEL_DATA[id as usize].name.as_bytes().to_vec().as_bytes());
+
+        compose! {
+            self.sizes.as_bytes(),
+            palette.len().as_bytes(),
+            names,
+            runs.iter().map(|&(_, len)| len).collect::<Vec<_>>().as_bytes(),
+            freqs.as_bytes(),
+            bits.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for Schematic {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        use {bit_vec::BitVec, huffman_compress as hc};
+
+        let mut reader = ByteReader::new(source);
+
+        let sizes: USize3 = reader.read()?;
+        let volume = sizes
+            .x
+            .checked_mul(sizes.y)
+            .and_then(|area| area.checked_mul(sizes.z))
+            .ok_or_else(|| {
+                ReinterpretError::Conversion(format!("schematic sizes {sizes} are too large"))
+            })?;
+
+        let palette_len: usize = reader.read()?;
+
+        if palette_len > u16::MAX as usize + 1 {
+            return Err(ReinterpretError::Conversion(format!(
+                "palette of {palette_len} voxels can not be indexed by u16",
+            )));
+        }
+
+        let mut palette = Vec::with_capacity(palette_len.min(VOXEL_DATA.len()));
+        for _ in 0..palette_len {
+            let name = String::from_utf8(reader.read()?)
+                .map_err(|err| ReinterpretError::Conversion(err.to_string()))?;
+
+            let id = voxel::id_by_name(&name).ok_or_else(|| {
+                ReinterpretError::Conversion(format!("unknown voxel name '{name}'"))
+            })?;
+
+            palette.push(id);
+        }
+
+        let run_lens: Vec<u32> = reader.read()?;
+        let freqs: HashMap<u16, usize> = reader.read()?;
+        let bits: BitVec = reader.read()?;
+
+        let run_idxs: Vec<u16> = match palette_len {
+            0 => vec![],
+            1 => vec![0; run_lens.len()],
+            _ => {
+                let (_, tree) = hc::CodeBuilder::from_iter(freqs).finish();
+                tree.decoder(bits, run_lens.len()).collect()
+            }
+        };
+
+        if run_idxs.len() != run_lens.len() {
+            return Err(ReinterpretError::Conversion(
+                "schematic body is truncated".into(),
+            ));
+        }
+
+        let mut voxel_ids = Vec::with_capacity(volume.min(Self::MAX_PREALLOCATED_VOLUME));
+        for (idx, len) in run_idxs.into_iter().zip(run_lens) {
+            let id = *palette.get(idx as usize).ok_or_else(|| {
+                ReinterpretError::Conversion(format!("palette index {idx} is out of bounds"))
+            })?;
+
+            if volume - voxel_ids.len() < len as usize {
+                return Err(ReinterpretError::Conversion(format!(
+                    "schematic has more voxels than sizes {sizes} allow",
+                )));
+            }
+
+            voxel_ids.extend(std::iter::repeat_n(id, len as usize));
+        }
+
+        if voxel_ids.len() != volume {
+            return Err(ReinterpretError::Conversion(format!(
+                "schematic has {} voxels but sizes are {sizes}",
+                voxel_ids.len(),
+            )));
+        }
+
+        Ok(Self { sizes, voxel_ids })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SchematicError {
+    #[error("failed to access schematic file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("failed to read schematic: {0}")]
+    Reinterpret(#[from] ReinterpretError),
+}
+
+/// Region selection and clipboard controlled by the schematic panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionTool {
+    pub corners: [Option<Int3>; 2],
+    pub clipboard: Option<Arc<Schematic>>,
+    pub skip_air: bool,
+    pub file_name: String,
+}
+
+impl Default for RegionTool {
+    fn default() -> Self {
+        Self {
+            corners: [None; 2],
+            clipboard: None,
+            skip_air: true,
+            file_name: "schematic".into(),
+        }
+    }
+}
+
+impl RegionTool {
+    pub const DIRECTORY: &str = "schematics";
+
+    /// Gives selected region as `pos_from..pos_to` if both corners are set.
+    pub fn selection(&self) -> Option<(Int3, Int3)> {
+        let [Some(a), Some(b)] = self.corners else {
+            return None;
+        };
+
+        Some((
+            Int3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            Int3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) + Int3::ONE,
+        ))
+    }
+
+    pub fn file_path(&self) -> String {
+        format!(
+            "{dir}/{name}.{ext}",
+            dir = Self::DIRECTORY,
+            name = self.file_name,
+            ext = Schematic::FILE_EXTENSION,
+        )
+    }
+
+    /// Transforms clipboard content.
+    pub fn transform_clipboard(&mut self, transform: impl FnOnce(&Schematic) -> Schematic) {
+        if let Some(schematic) = self.clipboard.as_deref() {
+            self.clipboard = Some(Arc::new(transform(schematic)));
+        }
+    }
+
+    pub fn save_clipboard(&self) {
+        let Some(schematic) = self.clipboard.as_deref() else {
+            logger::log!(Error, from = "schematic", "clipboard is empty");
+            return;
+        };
+
+        let path = self.file_path();
+        match schematic.save(&path) {
+            Ok(()) => logger::log!(Info, from = "schematic", "saved to {path}"),
+            Err(err) => logger::log!(Error, from = "schematic", "failed to save {path}: {err}"),
+        }
+    }
+
+    pub fn load_clipboard(&mut self) {
+        let path = self.file_path();
+        match Schematic::load(&path) {
+            Ok(schematic) => {
+                logger::log!(Info, from = "schematic", "loaded {path}");
+                self.clipboard = Some(Arc::new(schematic));
+            }
+            Err(err) => logger::log!(Error, from = "schematic", "failed to load {path}: {err}"),
+        }
+    }
+
+    /// Spawns schematic panel. Emits [commands][super::commands::Command] for
+    /// edits at voxel `cursor`.
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui, cursor: Option<Int3>) {
+        use {
+            super::commands::{Command, command},
+            crate::app::utils::graphics::ui::imgui_constructor::make_window,
+        };
+
+        make_window(ui, "Schematic")
+            .always_auto_resize(true)
+            .build(|| {
+                for (i, corner) in self.corners.iter_mut().enumerate() {
+                    match corner {
+                        Some(pos) => ui.text(format!("Corner {i}: {pos}")),
+                        None => ui.text(format!("Corner {i}: none")),
+                    }
+
+                    ui.same_line();
+                    if ui.button(format!("Set to cursor##{i}")) && cursor.is_some() {
+                        *corner = cursor;
+                    }
+                }
+
+                if ui.button("Copy")
+                    && let Some((pos_from, pos_to)) = self.selection()
+                {
+                    command(Command::Copy { pos_from, pos_to });
+                }
+
+                ui.same_line();
+                if ui.button("Clear selection") {
+                    self.corners = [None; 2];
+                }
+
+                ui.separator();
+
+                match self.clipboard.as_deref() {
+                    Some(schematic) => ui.text(format!("Clipboard: {}", schematic.sizes)),
+                    None => ui.text("Clipboard is empty"),
+                }
+
+                for axis in Axis::ALL {
+                    if ui.button(format!("Rotate {axis:?}")) {
+                        self.transform_clipboard(|schematic| schematic.rotated(axis, 1));
+                    }
+
+                    ui.same_line();
+                    if ui.button(format!("Mirror {axis:?}")) {
+                        self.transform_clipboard(|schematic| schematic.mirrored(axis));
+                    }
+                }
+
+                ui.checkbox("Skip air", &mut self.skip_air);
+
+                if ui.button("Paste at cursor")
+                    && let (Some(pos), Some(schematic)) = (cursor, self.clipboard.clone())
+                {
+                    command(Command::Paste {
+                        pos,
+                        schematic,
+                        skip_air: self.skip_air,
+                    });
+                }
+
+                ui.separator();
+
+                ui.input_text("File", &mut self.file_name).build();
+
+                if ui.button("Save") {
+                    self.save_clipboard();
+                }
+
+                ui.same_line();
+                if ui.button("Load") {
+                    self.load_clipboard();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(sizes: USize3) -> Schematic {
+        let volume = sizes.x * sizes.y * sizes.z;
+        Schematic::new(sizes, (0..volume).map(|i| (i % 5) as Id).collect())
+    }
+
+    #[test]
+    fn reinterpret() {
+        let mut schematic = numbered(USize3::new(3, 4, 5));
+        schematic.voxel_ids[10..40].fill(STONE_VOXEL_DATA.id);
+
+        let after = Schematic::from_bytes(&schematic.as_bytes()).unwrap();
+
+        assert_eq!(schematic, after);
+    }
+
+    #[test]
+    fn reinterpret_same_filled() {
+        let schematic = Schematic::new(USize3::all(4), vec![DIRT_VOXEL_DATA.id; 64]);
+        let after = Schematic::from_bytes(&schematic.as_bytes()).unwrap();
+
+        assert_eq!(schematic, after);
+    }
+
+    #[test]
+    fn oversized_schematics_are_rejected() {
+        let schematic = Schematic::new(USize3::all(2), vec![DIRT_VOXEL_DATA.id; 8]);
+
+        let mut overflowing = schematic.clone();
+        overflowing.sizes = USize3::new(usize::MAX, 2, 2);
+        assert!(Schematic::from_bytes(&overflowing.as_bytes()).is_err());
+
+        let mut long_run = Schematic::new(USize3::all(4), vec![DIRT_VOXEL_DATA.id; 64]);
+        long_run.sizes = USize3::all(2);
+        assert!(Schematic::from_bytes(&long_run.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rotations() {
+        let schematic = numbered(USize3::new(2, 3, 4));
+
+        for axis in Axis::ALL {
+            assert_ne!(schematic.rotated(axis, 1), schematic);
+            assert_eq!(
+                schematic.rotated(axis, 2),
+                schematic.rotated(axis, 1).rotated(axis, 1)
+            );
+            assert_eq!(schematic.rotated(axis, 4), schematic);
+            assert_eq!(schematic.mirrored(axis).mirrored(axis), schematic);
+        }
+
+        assert_eq!(schematic.rotated(Axis::Y, 1).sizes, USize3::new(4, 3, 2));
+    }
+
+    #[test]
+    fn rotation_moves_corner() {
+        let mut schematic = Schematic::new(USize3::new(2, 1, 3), vec![AIR_VOXEL_DATA.id; 6]);
+        schematic.voxel_ids[0] = STONE_VOXEL_DATA.id;
+
+        let rotated = schematic.rotated(Axis::Y, 1);
+
+        assert_eq!(rotated.get_id(USize3::new(0, 0, 1)), STONE_VOXEL_DATA.id);
+    }
+}
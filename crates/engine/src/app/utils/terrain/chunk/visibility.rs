@@ -0,0 +1,285 @@
+//!
+//! Cave culling of [chunks][Chunk] hidden behind terrain.
+//!
+//! Meshing records which pairs of chunk faces are connected through passable
+//! voxels. Each frame chunks are traversed from the camera one, a neighbor is
+//! entered only if the face we came in through is connected to the face we
+//! leave through. Chunks that are never reached can not be seen.
+//!
+
+use {
+    super::{Chunk, chunk_array::ChunkArray, iterator},
+    crate::{
+        prelude::*,
+        terrain::voxel::voxel_data::{Id, data::*},
+    },
+    cfg::terrain::{BACK_IDX, BOTTOM_IDX, FRONT_IDX, LEFT_IDX, RIGHT_IDX, TOP_IDX},
+    std::collections::VecDeque,
+};
+
+/// Outward offsets of chunk faces by their side index.
+pub const FACE_OFFSETS: [Int3; 6] = {
+    let mut offsets = [Int3::ZERO; 6];
+    offsets[BACK_IDX] = Int3::new(1, 0, 0);
+    offsets[FRONT_IDX] = Int3::new(-1, 0, 0);
+    offsets[TOP_IDX] = Int3::new(0, 1, 0);
+    offsets[BOTTOM_IDX] = Int3::new(0, -1, 0);
+    offsets[RIGHT_IDX] = Int3::new(0, 0, 1);
+    offsets[LEFT_IDX] = Int3::new(0, 0, -1);
+    offsets
+};
+
+/// Gives index of the face opposite to `face`.
+pub const fn opposite_face(face: usize) -> usize {
+    face ^ 1
+}
+
+/// Pairs of chunk faces connected through passable voxels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FaceConnections(u64);
+
+impl FaceConnections {
+    /// Nothing can be seen through.
+    pub const NONE: Self = Self(0);
+
+    /// Everything can be seen through, e.g. air.
+    pub const ALL: Self = Self((1 << 36) - 1);
+
+    /// Checks that `from` and `to` faces are connected.
+    pub const fn connects(self, from: usize, to: usize) -> bool {
+        self.0 & Self::bit(from, to) != 0
+    }
+
+    /// Connects each pair of `faces` given by a bit mask.
+    pub fn connect_all(&mut self, faces: u8) {
+        for from in (0..6).filter(|&face| faces & (1 << face) != 0) {
+            for to in (0..6).filter(|&face| faces & (1 << face) != 0) {
+                self.0 |= Self::bit(from, to);
+            }
+        }
+    }
+
+    const fn bit(from: usize, to: usize) -> u64 {
+        1 << (from * 6 + to)
+    }
+
+    /// Finds connections of a cube of `size` voxels by flood-filling regions
+    /// of voxels for which `is_passable` holds.
+    pub fn compute(size: i32, is_passable: impl Fn(Int3) -> bool) -> Self {
+        let idx_of = |pos: Int3| ((pos.x * size + pos.y) * size + pos.z) as usize;
+
+        let touched_faces = |pos: Int3| {
+            let mut faces = 0_u8;
+
+            for (face, offset) in FACE_OFFSETS.iter().enumerate() {
+                let next = pos + *offset;
+                let is_outside = [next.x, next.y, next.z]
+                    .into_iter()
+                    .any(|coord| !(0..size).contains(&coord));
+
+                if is_outside {
+                    faces |= 1 << face;
+                }
+            }
+
+            faces
+        };
+
+        let mut is_visited = vec![false; size.pow(3) as usize];
+        let mut connections = Self::NONE;
+        let mut stack = vec![];
+
+        // Regions not touching the border do not connect faces.
+        for start in iterator::CubeBorder::new(size) {
+            if is_visited[idx_of(start)] || !is_passable(start) {
+                continue;
+            }
+
+            is_visited[idx_of(start)] = true;
+            stack.push(start);
+
+            let mut faces = 0_u8;
+
+            while let Some(pos) = stack.pop() {
+                faces |= touched_faces(pos);
+
+                for offset in FACE_OFFSETS {
+                    let next = pos + offset;
+                    let is_inside = [next.x, next.y, next.z]
+                        .into_iter()
+                        .all(|coord| (0..size).contains(&coord));
+
+                    if is_inside && !is_visited[idx_of(next)] && is_passable(next) {
+                        is_visited[idx_of(next)] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+
+            connections.connect_all(faces);
+        }
+
+        connections
+    }
+}
+
+impl Default for FaceConnections {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Counts of chunks drawn and culled in the last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CullingStats {
+    pub n_rendered: usize,
+    pub n_frustum_culled: usize,
+    pub n_occlusion_culled: usize,
+}
+
+/// Gives which chunks of array with `sizes` can be seen from chunk at `start`
+/// by array index. `connections` gives face connections of a chunk and
+/// `is_in_view` tells if the chunk is in the camera frustum, both by index.
+///
+/// Every chunk is visible if `start` is outside of the array.
+pub fn traverse(
+    sizes: USize3,
+    start: Int3,
+    connections: impl Fn(usize) -> FaceConnections,
+    mut is_in_view: impl FnMut(usize) -> bool,
+) -> Vec<bool> {
+    let volume = sizes.x * sizes.y * sizes.z;
+
+    let Some(start_idx) = ChunkArray::pos_to_idx(sizes, start) else {
+        return vec![true; volume];
+    };
+
+    let mut is_visible = vec![false; volume];
+    is_visible[start_idx] = true;
+
+    // Chunk position, index, face it was entered through and directions moved in.
+    let mut queue = VecDeque::from([(start, start_idx, None::<usize>, 0_u8)]);
+
+    while let Some((pos, idx, entered_face, directions)) = queue.pop_front() {
+        let chunk_connections = connections(idx);
+
+        for (face, offset) in FACE_OFFSETS.iter().enumerate() {
+            // Going back can not reveal anything new.
+            if directions & (1 << opposite_face(face)) != 0 {
+                continue;
+            }
+
+            if entered_face.is_some_and(|entered| !chunk_connections.connects(entered, face)) {
+                continue;
+            }
+
+            let next = pos + *offset;
+            let Some(next_idx) = ChunkArray::pos_to_idx(sizes, next) else {
+                continue;
+            };
+
+            if is_visible[next_idx] || !is_in_view(next_idx) {
+                continue;
+            }
+
+            is_visible[next_idx] = true;
+            queue.push_back((
+                next,
+                next_idx,
+                Some(opposite_face(face)),
+                directions | (1 << face),
+            ));
+        }
+    }
+
+    is_visible
+}
+
+impl Chunk {
+    /// Computes [face connections][FaceConnections] of the chunk, voxels
+    /// which are air or fluid are passable.
+    pub fn compute_connections(&self) -> FaceConnections {
+        if !self.is_generated() {
+            return FaceConnections::ALL;
+        }
+
+        if let Some(id) = self.fill_id() {
+            return match Self::is_passable_data(id) {
+                true => FaceConnections::ALL,
+                false => FaceConnections::NONE,
+            };
+        }
+
+        FaceConnections::compute(Chunk::SIZE as i32, |pos| {
+            Chunk::voxel_pos_to_idx(pos)
+                .and_then(|idx| self.get_id(idx))
+                .is_some_and(Self::is_passable_data)
+        })
+    }
+
+    fn is_passable_data(id: Id) -> bool {
+        let data = &VOXEL_DATA[id as usize];
+        data.id == AIR_VOXEL_DATA.id || data.fluid.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opposite_faces_have_opposite_offsets() {
+        for (face, offset) in FACE_OFFSETS.iter().enumerate() {
+            assert_eq!(FACE_OFFSETS[opposite_face(face)], -*offset);
+        }
+    }
+
+    #[test]
+    fn tunnel_connects_only_its_ends() {
+        // Tunnel along x axis through solid cube.
+        let connections = FaceConnections::compute(8, |pos| pos.y == 3 && pos.z == 4);
+
+        assert!(connections.connects(BACK_IDX, FRONT_IDX));
+        assert!(connections.connects(FRONT_IDX, BACK_IDX));
+        assert!(!connections.connects(BACK_IDX, TOP_IDX));
+        assert!(!connections.connects(TOP_IDX, BOTTOM_IDX));
+        assert!(!connections.connects(LEFT_IDX, RIGHT_IDX));
+
+        let solid = FaceConnections::compute(8, |_| false);
+        assert_eq!(solid, FaceConnections::NONE);
+
+        let air = FaceConnections::compute(8, |_| true);
+        assert_eq!(air, FaceConnections::ALL);
+    }
+
+    #[test]
+    fn chunks_behind_walls_are_culled() {
+        let sizes = USize3::new(5, 1, 1);
+        let (start, _) = ChunkArray::pos_bounds(sizes);
+
+        // The third chunk is a solid wall, the others are air.
+        let wall_idx = ChunkArray::pos_to_idx(sizes, start + Int3::new(2, 0, 0)).unwrap();
+        let connections = |idx| match idx == wall_idx {
+            true => FaceConnections::NONE,
+            false => FaceConnections::ALL,
+        };
+
+        let is_visible = traverse(sizes, start, connections, |_| true);
+        let is_visible = (0..5)
+            .map(|x| {
+                let idx = ChunkArray::pos_to_idx(sizes, start + Int3::new(x, 0, 0)).unwrap();
+                is_visible[idx]
+            })
+            .collect_vec();
+
+        // The wall itself is visible, chunks behind it are not.
+        assert_eq!(is_visible, vec![true, true, true, false, false]);
+
+        let outside = start - Int3::new(10, 0, 0);
+        assert!(
+            traverse(sizes, outside, connections, |_| true)
+                .into_iter()
+                .all(|is| is)
+        );
+    }
+}
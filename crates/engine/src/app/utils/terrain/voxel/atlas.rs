@@ -1,8 +1,11 @@
 //!
 //! Tools for dealing with texture atlases.
 //!
+//! Blocks are drawn with a texture array made of atlas items, see [`split_items`].
+//! The padded atlas itself is only used for item icons.
+//!
 
-use {crate::prelude::*, cfg::texture::atlas::*};
+use {crate::prelude::*, cfg::texture::atlas::*, image::RgbaImage};
 
 /// The size of texture atlas row in pixels
 pub const ATLAS_ROW_SIZE_IN_PIXELS: usize =
@@ -14,6 +17,34 @@ pub const TEXTURE_SIZE_F: f32 = 1.0 / ITEMS_COUNT_IN_ROW as f32;
 /// Padding to not hit neighbor textures
 pub const ATLAS_PADDING_F: f32 = ITEM_PADDING_IN_PIXELS as f32 / ATLAS_ROW_SIZE_IN_PIXELS as f32;
 
+/// Number of items in the atlas, also the number of texture array layers.
+pub const ITEMS_COUNT: usize = ITEMS_COUNT_IN_ROW * ITEMS_COUNT_IN_ROW;
+
+/// Cuts atlas `image` into its items without padding, item `id` is at index `id`.
+///
+/// # Panic
+///
+/// Panics if `image` is smaller than [`ATLAS_ROW_SIZE_IN_PIXELS`].
+pub fn split_items(image: &RgbaImage) -> Vec<RgbaImage> {
+    assert!(
+        image.width() as usize >= ATLAS_ROW_SIZE_IN_PIXELS
+            && image.height() as usize >= ATLAS_ROW_SIZE_IN_PIXELS,
+        "atlas should be at least {ATLAS_ROW_SIZE_IN_PIXELS} pixels wide and high",
+    );
+
+    let cell_size = ITEM_SIZE_IN_PIXELS + 2 * ITEM_PADDING_IN_PIXELS;
+
+    (0..ITEMS_COUNT)
+        .map(|id| {
+            let x = (id % ITEMS_COUNT_IN_ROW * cell_size + ITEM_PADDING_IN_PIXELS) as u32;
+            let y = (id / ITEMS_COUNT_IN_ROW * cell_size + ITEM_PADDING_IN_PIXELS) as u32;
+            let size = ITEM_SIZE_IN_PIXELS as u32;
+
+            image::imageops::crop_imm(image, x, y, size, size).to_image()
+        })
+        .collect()
+}
+
 /// Handles UV information.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct UV {
@@ -44,6 +75,15 @@ impl UV {
         Self { lo, hi }.inversed()
     }
 
+    /// UVs of a whole texture array layer. Greater ones repeat the texture.
+    pub fn layer() -> Self {
+        Self {
+            lo: vec2::zero(),
+            hi: vec2::all(1.0),
+        }
+        .inversed()
+    }
+
     /// Useful if texture is inverted
     pub fn inversed(mut self) -> Self {
         self.lo.y = 1.0 - self.lo.y;
@@ -51,3 +91,42 @@ impl UV {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, image::Rgba};
+
+    #[test]
+    fn items_are_split_without_padding() {
+        let size = ATLAS_ROW_SIZE_IN_PIXELS as u32;
+        let cell_size = (ITEM_SIZE_IN_PIXELS + 2 * ITEM_PADDING_IN_PIXELS) as u32;
+        let padding = ITEM_PADDING_IN_PIXELS as u32;
+
+        // Item pixels hold the item id, padding pixels are transparent.
+        let atlas = RgbaImage::from_fn(size, size, |x, y| {
+            let (local_x, local_y) = (x % cell_size, y % cell_size);
+            let inner = padding..cell_size - padding;
+
+            if !inner.contains(&local_x) || !inner.contains(&local_y) {
+                return Rgba([0, 0, 0, 0]);
+            }
+
+            let id = (y / cell_size) * ITEMS_COUNT_IN_ROW as u32 + x / cell_size;
+            Rgba([(id % 256) as u8, (id / 256) as u8, 0, 255])
+        });
+
+        let items = split_items(&atlas);
+        assert_eq!(items.len(), ITEMS_COUNT);
+
+        let item_size = ITEM_SIZE_IN_PIXELS as u32;
+
+        for (id, item) in items.iter().enumerate() {
+            assert_eq!(item.dimensions(), (item_size, item_size));
+            assert!(
+                item.pixels()
+                    .all(|&pixel| pixel == Rgba([(id % 256) as u8, (id / 256) as u8, 0, 255])),
+                "item {id} has pixels of padding or other items",
+            );
+        }
+    }
+}
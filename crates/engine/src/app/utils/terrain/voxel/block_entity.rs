@@ -0,0 +1,365 @@
+//!
+//! Block entities: typed data attached to voxels that doesn't fit into a voxel [`Id`].
+//!
+
+use {
+    super::{fluid::Fluid, voxel_data::Id},
+    crate::prelude::*,
+};
+
+/// Per-chunk map from local voxel position to its [block entity][BlockEntity].
+pub type BlockEntities = HashMap<Int3, BlockEntity>;
+
+/// Kind of [block entity][BlockEntity] that voxel type carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockEntityKind {
+    Container,
+    Sign,
+    Spawner,
+    Fluid,
+    Crop,
+}
+
+impl BlockEntityKind {
+    /// Constructs default payload of this kind.
+    pub fn instantiate(self) -> BlockEntity {
+        match self {
+            Self::Container => BlockEntity::Container(Container::default()),
+            Self::Sign => BlockEntity::Sign(Sign::default()),
+            Self::Spawner => BlockEntity::Spawner(Spawner::default()),
+            Self::Fluid => BlockEntity::Fluid(Fluid::default()),
+            Self::Crop => BlockEntity::Crop(Crop::default()),
+        }
+    }
+}
+
+/// Data attached to a voxel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockEntity {
+    Container(Container),
+    Sign(Sign),
+    Spawner(Spawner),
+    Fluid(Fluid),
+    Crop(Crop),
+}
+
+impl BlockEntity {
+    /// Gives kind of this block entity.
+    pub fn kind(&self) -> BlockEntityKind {
+        match self {
+            Self::Container(_) => BlockEntityKind::Container,
+            Self::Sign(_) => BlockEntityKind::Sign,
+            Self::Spawner(_) => BlockEntityKind::Spawner,
+            Self::Fluid(_) => BlockEntityKind::Fluid,
+            Self::Crop(_) => BlockEntityKind::Crop,
+        }
+    }
+
+    /// Advances block entity state by `dt` seconds.
+    pub fn tick(&mut self, dt: f32) {
+        match self {
+            Self::Container(_) | Self::Sign(_) | Self::Fluid(_) | Self::Crop(_) => (),
+            Self::Spawner(spawner) => spawner.tick(dt),
+        }
+    }
+}
+
+/// Stack of voxels stored in a [container][Container] slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ContainerSlot {
+    pub id: Id,
+    pub count: u32,
+}
+
+/// Voxel storage, e.g. a chest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Container {
+    pub slots: Vec<Option<ContainerSlot>>,
+}
+
+impl Container {
+    pub const N_SLOTS: usize = 27;
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; Self::N_SLOTS],
+        }
+    }
+}
+
+/// Voxel with a text on it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sign {
+    pub text: String,
+}
+
+/// Voxel that periodically requests a spawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spawner {
+    /// Time between spawns in seconds.
+    pub delay: f32,
+    /// Time left until next spawn in seconds.
+    pub timer: f32,
+    /// Spawns requested but not yet handled.
+    pub n_pending: u32,
+}
+
+impl Spawner {
+    pub const DEFAULT_DELAY: f32 = 20.0;
+
+    pub fn tick(&mut self, dt: f32) {
+        self.timer -= dt;
+
+        if self.timer <= 0.0 {
+            let n_spawns = (-self.timer / self.delay).floor() + 1.0;
+
+            self.timer += n_spawns * self.delay;
+            self.n_pending = self.n_pending.saturating_add(n_spawns as u32);
+        }
+    }
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Self {
+            delay: Self::DEFAULT_DELAY,
+            timer: Self::DEFAULT_DELAY,
+            n_pending: 0,
+        }
+    }
+}
+
+/// Growing plant, see [world ticks][super::world_tick].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Crop {
+    pub stage: u8,
+}
+
+impl Crop {
+    /// Crop becomes ripe at that stage.
+    pub const RIPE_STAGE: u8 = 4;
+}
+
+impl AsBytes for ContainerSlot {
+    fn as_bytes(&self) -> Vec<u8> {
+        compose! {
+            self.id.as_bytes(),
+            self.count.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for ContainerSlot {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        read! {
+            source,
+            let id,
+            let count,
+        }
+
+        Ok(Self { id, count })
+    }
+}
+
+impl StaticSize for ContainerSlot {
+    fn static_size() -> usize {
+        Id::static_size() + u32::static_size()
+    }
+}
+
+impl AsBytes for BlockEntity {
+    fn as_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Container(container) => compose! {
+                std::iter::once(0),
+                container.slots.len().as_bytes(),
+                container.slots.iter().flat_map(AsBytes::as_bytes),
+            }
+            .collect(),
+
+            Self::Sign(sign) => compose! {
+                std::iter::once(1),
+                sign.text.as_bytes().to_vec().as_bytes(),
+            }
+            .collect(),
+
+            Self::Spawner(spawner) => compose! {
+                std::iter::once(2),
+                spawner.delay.as_bytes(),
+                spawner.timer.as_bytes(),
+                spawner.n_pending.as_bytes(),
+            }
+            .collect(),
+
+            Self::Fluid(fluid) => vec![3, fluid.level],
+
+            Self::Crop(crop) => vec![4, crop.stage],
+        }
+    }
+}
+
+impl FromBytes for BlockEntity {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        let mut reader = ByteReader::new(source);
+        let variant: u8 = reader.read()?;
+
+        match variant {
+            0 => {
+                let len: usize = reader.read()?;
+                let mut slots = Vec::with_capacity(len);
+
+                for _ in 0..len {
+                    slots.push(reader.read()?);
+                }
+
+                Ok(Self::Container(Container { slots }))
+            }
+
+            1 => {
+                let bytes: Vec<u8> = reader.read()?;
+                let text = String::from_utf8(bytes)
+                    .map_err(|err| ReinterpretError::Conversion(err.to_string()))?;
+
+                Ok(Self::Sign(Sign { text }))
+            }
+
+            2 => {
+                let spawner = Spawner {
+                    delay: reader.read()?,
+                    timer: reader.read()?,
+                    n_pending: reader.read()?,
+                };
+
+                // Zero delay would request infinitely many spawns.
+                if !(spawner.delay.is_finite() && spawner.delay > 0.0 && spawner.timer.is_finite())
+                {
+                    return Err(ReinterpretError::Conversion(format!(
+                        "invalid spawner delay ({}) or timer ({})",
+                        spawner.delay, spawner.timer,
+                    )));
+                }
+
+                Ok(Self::Spawner(spawner))
+            }
+
+            3 => Ok(Self::Fluid(Fluid {
+                level: reader.read()?,
+            })),
+
+            4 => Ok(Self::Crop(Crop {
+                stage: reader.read()?,
+            })),
+
+            _ => Err(ReinterpretError::Conversion(format!(
+                "conversion of too large byte ({variant}) to BlockEntity"
+            ))),
+        }
+    }
+}
+
+impl DynamicSize for BlockEntity {
+    fn dynamic_size(&self) -> usize {
+        u8::static_size()
+            + match self {
+                Self::Container(container) => {
+                    usize::static_size()
+                        + container
+                            .slots
+                            .iter()
+                            .map(DynamicSize::dynamic_size)
+                            .sum::<usize>()
+                }
+                Self::Sign(sign) => usize::static_size() + sign.text.len(),
+                Self::Spawner(_) => 2 * f32::static_size() + u32::static_size(),
+                Self::Fluid(_) | Self::Crop(_) => u8::static_size(),
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reinterpret_container() {
+        let mut container = Container::default();
+        container.slots[3] = Some(ContainerSlot { id: 2, count: 64 });
+        container.slots[17] = Some(ContainerSlot { id: 4, count: 1 });
+
+        let before = BlockEntity::Container(container);
+        let after = BlockEntity::from_bytes(&before.as_bytes()).unwrap();
+
+        assert_eq!(before, after);
+        assert_eq!(before.dynamic_size(), before.as_bytes().len());
+    }
+
+    #[test]
+    fn reinterpret_block_entities() {
+        let before = BlockEntities::from([
+            (
+                Int3::new(1, 2, 3),
+                BlockEntity::Sign(Sign {
+                    text: "Hello, terrain!".into(),
+                }),
+            ),
+            (
+                Int3::new(0, 63, 7),
+                BlockEntity::Spawner(Spawner::default()),
+            ),
+            (Int3::new(5, 5, 5), BlockEntityKind::Container.instantiate()),
+            (Int3::new(6, 0, 2), BlockEntity::Fluid(Fluid { level: 3 })),
+            (Int3::new(9, 1, 2), BlockEntity::Crop(Crop { stage: 2 })),
+        ]);
+
+        let after = BlockEntities::from_bytes(&before.as_bytes()).unwrap();
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn spawner_requests_spawns() {
+        let mut spawner = Spawner {
+            delay: 1.0,
+            timer: 1.0,
+            n_pending: 0,
+        };
+
+        spawner.tick(0.5);
+        assert_eq!(spawner.n_pending, 0);
+
+        spawner.tick(2.0);
+        assert_eq!(spawner.n_pending, 2);
+    }
+
+    #[test]
+    fn spawner_requests_all_missed_spawns() {
+        let mut spawner = Spawner {
+            delay: 2.0,
+            timer: 1.0,
+            n_pending: 0,
+        };
+
+        spawner.tick(0.5);
+        assert_eq!(spawner.n_pending, 0);
+
+        // Timer goes to -4.5, which is 3 spawns late.
+        spawner.tick(5.0);
+        assert_eq!(spawner.n_pending, 3);
+        assert_eq!(spawner.timer, 1.5);
+    }
+
+    #[test]
+    fn spawners_with_invalid_delay_are_rejected() {
+        for delay in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let spawner = BlockEntity::Spawner(Spawner {
+                delay,
+                ..Default::default()
+            });
+
+            assert!(BlockEntity::from_bytes(&spawner.as_bytes()).is_err());
+        }
+    }
+}
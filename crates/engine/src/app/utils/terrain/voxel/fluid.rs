@@ -0,0 +1,443 @@
+//!
+//! Water and lava flowing by cellular rules.
+//!
+
+use {
+    super::voxel_data::{Id, data::*},
+    crate::prelude::*,
+};
+
+/// Kind of fluid voxel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum FluidKind {
+    Water,
+    Lava,
+}
+
+impl FluidKind {
+    /// Gives fluid kind of voxel with `id` if it is a fluid.
+    pub fn of(id: Id) -> Option<Self> {
+        VOXEL_DATA.get(id as usize)?.fluid
+    }
+
+    /// Gives [id][Id] of voxel made of this fluid.
+    pub fn id(self) -> Id {
+        match self {
+            Self::Water => WATER_VOXEL_DATA.id,
+            Self::Lava => LAVA_VOXEL_DATA.id,
+        }
+    }
+
+    /// Level lost with each step aside.
+    pub const fn decay(self) -> u8 {
+        match self {
+            Self::Water => 1,
+            Self::Lava => 2,
+        }
+    }
+
+    /// Ticks between updates of the fluid.
+    pub const fn delay(self) -> u64 {
+        match self {
+            Self::Water => 2,
+            Self::Lava => 6,
+        }
+    }
+}
+
+/// Level of fluid in a voxel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fluid {
+    pub level: u8,
+}
+
+impl Default for Fluid {
+    fn default() -> Self {
+        Self::SOURCE
+    }
+}
+
+impl Fluid {
+    /// Level of source voxels, they never dry out.
+    pub const SOURCE_LEVEL: u8 = 8;
+
+    /// Level of fluid falling down.
+    pub const FALLING_LEVEL: u8 = 7;
+
+    pub const SOURCE: Self = Self {
+        level: Self::SOURCE_LEVEL,
+    };
+
+    pub const fn is_source(self) -> bool {
+        self.level == Self::SOURCE_LEVEL
+    }
+}
+
+/// Space where fluids flow.
+pub trait FluidSpace {
+    /// Gives [id][Id] of voxel at `pos` or [`None`] if `pos` is out of the space.
+    fn get_id(&self, pos: Int3) -> Option<Id>;
+
+    /// Gives level of fluid voxel at `pos`.
+    fn get_level(&self, pos: Int3) -> u8;
+
+    /// Sets voxel at `pos` to `id`. `level` is used by fluids only.
+    fn set(&mut self, pos: Int3, id: Id, level: u8);
+
+    /// Updates outside of active regions are postponed.
+    fn is_active(&self, _pos: Int3) -> bool {
+        true
+    }
+}
+
+const UP: Int3 = veci!(0, 1, 0);
+
+const ASIDE: [Int3; 4] = [
+    veci!(1, 0, 0),
+    veci!(-1, 0, 0),
+    veci!(0, 0, 1),
+    veci!(0, 0, -1),
+];
+
+fn is_kind(space: &impl FluidSpace, pos: Int3, kind: FluidKind) -> bool {
+    space.get_id(pos).and_then(FluidKind::of) == Some(kind)
+}
+
+/// Level that flowing fluid at `pos` gets from its neighbors.
+fn flow_level(space: &impl FluidSpace, pos: Int3, kind: FluidKind) -> u8 {
+    if is_kind(space, pos + UP, kind) {
+        return Fluid::FALLING_LEVEL;
+    }
+
+    let mut n_sources = 0;
+    let mut max_level = 0;
+
+    for offset in ASIDE {
+        if is_kind(space, pos + offset, kind) {
+            let level = space.get_level(pos + offset);
+            n_sources += (level == Fluid::SOURCE_LEVEL) as usize;
+            max_level = max_level.max(level);
+        }
+    }
+
+    // Water between two sources becomes a source if it lies on something.
+    let is_supported = match space.get_id(pos - UP) {
+        Some(id) if id == AIR_VOXEL_DATA.id => false,
+        Some(id) if FluidKind::of(id).is_some() => space.get_level(pos - UP) == Fluid::SOURCE_LEVEL,
+        Some(_) => true,
+        None => false,
+    };
+
+    if kind == FluidKind::Water && 2 <= n_sources && is_supported {
+        return Fluid::SOURCE_LEVEL;
+    }
+
+    max_level.saturating_sub(kind.decay())
+}
+
+/// Updates fluid at `pos`. Gives positions that have changed.
+pub fn update(space: &mut impl FluidSpace, pos: Int3) -> Vec<Int3> {
+    let Some(kind) = space.get_id(pos).and_then(FluidKind::of) else {
+        return vec![];
+    };
+
+    let old_level = space.get_level(pos);
+
+    // Lava hardens when touches water.
+    if kind == FluidKind::Lava
+        && SpaceIter::adj_iter(pos).any(|adj| is_kind(space, adj, FluidKind::Water))
+    {
+        let id = match old_level {
+            Fluid::SOURCE_LEVEL => OBSIDIAN_VOXEL_DATA.id,
+            _ => STONE_VOXEL_DATA.id,
+        };
+
+        space.set(pos, id, 0);
+        return vec![pos];
+    }
+
+    let mut changed = vec![];
+
+    let level = match old_level {
+        Fluid::SOURCE_LEVEL => old_level,
+        _ => flow_level(space, pos, kind),
+    };
+
+    if level != old_level {
+        changed.push(pos);
+
+        if level == 0 {
+            space.set(pos, AIR_VOXEL_DATA.id, 0);
+            return changed;
+        }
+
+        space.set(pos, kind.id(), level);
+    }
+
+    let below = pos - UP;
+    match space.get_id(below) {
+        Some(id) if id == AIR_VOXEL_DATA.id => {
+            space.set(below, kind.id(), Fluid::FALLING_LEVEL);
+            changed.push(below);
+        }
+
+        // Falling into the same fluid.
+        Some(id) if FluidKind::of(id) == Some(kind) => (),
+
+        _ => {
+            let next_level = level.saturating_sub(kind.decay());
+            if next_level == 0 {
+                return changed;
+            }
+
+            for offset in ASIDE {
+                let adj = pos + offset;
+
+                match space.get_id(adj) {
+                    Some(id) if id == AIR_VOXEL_DATA.id => {
+                        space.set(adj, kind.id(), next_level);
+                        changed.push(adj);
+                    }
+                    Some(id)
+                        if FluidKind::of(id) == Some(kind) && space.get_level(adj) < next_level =>
+                    {
+                        space.set(adj, kind.id(), next_level);
+                        changed.push(adj);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::terrain::{
+            chunk::{light::Light, schedule::UpdateQueue},
+            voxel::{
+                block_entity::BlockEntity,
+                world_tick::{self, WorldSpace},
+            },
+        },
+    };
+
+    /// Box of air with solid floor at `y = 0`.
+    #[derive(Debug, Default)]
+    struct TestSpace {
+        voxels: HashMap<Int3, (Id, u8)>,
+        sizes: Int3,
+    }
+
+    impl TestSpace {
+        fn new(sizes: Int3) -> Self {
+            let mut result = Self {
+                voxels: HashMap::new(),
+                sizes,
+            };
+
+            for x in 0..sizes.x {
+                for z in 0..sizes.z {
+                    result.set(Int3::new(x, 0, z), STONE_VOXEL_DATA.id, 0);
+                }
+            }
+
+            result
+        }
+
+        fn id(&self, pos: Int3) -> Id {
+            self.get_id(pos).unwrap()
+        }
+
+        fn count(&self, kind: FluidKind) -> usize {
+            self.voxels
+                .values()
+                .filter(|&&(id, _)| FluidKind::of(id) == Some(kind))
+                .count()
+        }
+    }
+
+    impl FluidSpace for TestSpace {
+        fn get_id(&self, pos: Int3) -> Option<Id> {
+            let is_inside = 0 <= pos.x
+                && 0 <= pos.y
+                && 0 <= pos.z
+                && pos.x < self.sizes.x
+                && pos.y < self.sizes.y
+                && pos.z < self.sizes.z;
+
+            is_inside.then(|| {
+                self.voxels
+                    .get(&pos)
+                    .map_or(AIR_VOXEL_DATA.id, |&(id, _)| id)
+            })
+        }
+
+        fn get_level(&self, pos: Int3) -> u8 {
+            self.voxels.get(&pos).map_or(0, |&(_, level)| level)
+        }
+
+        fn set(&mut self, pos: Int3, id: Id, level: u8) {
+            match id == AIR_VOXEL_DATA.id {
+                true => drop(self.voxels.remove(&pos)),
+                false => drop(self.voxels.insert(pos, (id, level))),
+            }
+        }
+    }
+
+    impl WorldSpace for TestSpace {
+        fn get_light(&self, _pos: Int3) -> Light {
+            Light::SKY
+        }
+
+        fn get_block_entity(&self, _pos: Int3) -> Option<BlockEntity> {
+            None
+        }
+
+        fn set_block_entity(&mut self, _pos: Int3, _block_entity: BlockEntity) {}
+    }
+
+    fn place(space: &mut TestSpace, queue: &mut UpdateQueue, pos: Int3, id: Id) {
+        space.set(pos, id, Fluid::SOURCE_LEVEL);
+        world_tick::schedule_near(space, queue, [pos]);
+    }
+
+    fn run(space: &mut TestSpace, queue: &mut UpdateQueue, n_ticks: usize) {
+        for _ in 0..n_ticks {
+            world_tick::tick(space, queue);
+        }
+    }
+
+    #[test]
+    fn water_falls_and_spreads() {
+        let mut space = TestSpace::new(Int3::new(32, 8, 32));
+        let mut queue = UpdateQueue::new();
+
+        let source = Int3::new(16, 5, 16);
+        place(&mut space, &mut queue, source, WATER_VOXEL_DATA.id);
+        run(&mut space, &mut queue, 200);
+
+        assert!(queue.is_empty());
+        for y in 1..5 {
+            assert_eq!(space.id(Int3::new(16, y, 16)), WATER_VOXEL_DATA.id);
+        }
+
+        // Fluid spreads by levels from falling level on the floor.
+        let floor = Int3::new(16, 1, 16);
+        assert_eq!(space.get_level(floor), Fluid::FALLING_LEVEL);
+        assert_eq!(space.get_level(floor + Int3::new(3, 0, 0)), 4);
+        assert_eq!(space.get_level(floor + Int3::new(2, 0, 4)), 1);
+        assert_eq!(
+            space.id(floor + Int3::new(7, 0, 0)),
+            AIR_VOXEL_DATA.id,
+            "water should not flow further than its level",
+        );
+    }
+
+    #[test]
+    fn lava_flows_shorter_than_water() {
+        let mut space = TestSpace::new(Int3::new(32, 4, 32));
+        let mut queue = UpdateQueue::new();
+
+        place(
+            &mut space,
+            &mut queue,
+            Int3::new(16, 1, 16),
+            LAVA_VOXEL_DATA.id,
+        );
+        run(&mut space, &mut queue, 200);
+
+        assert_eq!(space.count(FluidKind::Lava), 1 + 4 + 8 + 12);
+    }
+
+    #[test]
+    fn flow_dries_out_without_source() {
+        let mut space = TestSpace::new(Int3::new(16, 4, 16));
+        let mut queue = UpdateQueue::new();
+
+        let source = Int3::new(8, 1, 8);
+        place(&mut space, &mut queue, source, WATER_VOXEL_DATA.id);
+        run(&mut space, &mut queue, 100);
+        assert!(space.count(FluidKind::Water) > 1);
+
+        space.set(source, AIR_VOXEL_DATA.id, 0);
+        world_tick::schedule_near(&space, &mut queue, [source]);
+        run(&mut space, &mut queue, 200);
+
+        assert_eq!(space.count(FluidKind::Water), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn water_between_sources_becomes_source() {
+        let mut space = TestSpace::new(Int3::new(8, 4, 8));
+        let mut queue = UpdateQueue::new();
+
+        place(
+            &mut space,
+            &mut queue,
+            Int3::new(2, 1, 4),
+            WATER_VOXEL_DATA.id,
+        );
+        place(
+            &mut space,
+            &mut queue,
+            Int3::new(4, 1, 4),
+            WATER_VOXEL_DATA.id,
+        );
+        run(&mut space, &mut queue, 100);
+
+        assert_eq!(space.get_level(Int3::new(3, 1, 4)), Fluid::SOURCE_LEVEL);
+    }
+
+    #[test]
+    fn lava_hardens_near_water() {
+        let mut space = TestSpace::new(Int3::new(16, 4, 16));
+        let mut queue = UpdateQueue::new();
+
+        let lava = Int3::new(4, 1, 8);
+        place(&mut space, &mut queue, lava, LAVA_VOXEL_DATA.id);
+        run(&mut space, &mut queue, 100);
+
+        place(&mut space, &mut queue, lava + UP, WATER_VOXEL_DATA.id);
+        run(&mut space, &mut queue, 200);
+
+        assert_eq!(space.id(lava), OBSIDIAN_VOXEL_DATA.id);
+        assert!(
+            (5..8).any(|x| space.id(Int3::new(x, 1, 8)) == STONE_VOXEL_DATA.id),
+            "flowing lava should turn into stone",
+        );
+    }
+
+    #[test]
+    fn simulation_is_deterministic() {
+        let simulate = || {
+            let mut space = TestSpace::new(Int3::new(24, 8, 24));
+            let mut queue = UpdateQueue::new();
+
+            place(
+                &mut space,
+                &mut queue,
+                Int3::new(6, 6, 6),
+                WATER_VOXEL_DATA.id,
+            );
+            place(
+                &mut space,
+                &mut queue,
+                Int3::new(14, 3, 12),
+                LAVA_VOXEL_DATA.id,
+            );
+            run(&mut space, &mut queue, 37);
+
+            let mut voxels = space.voxels.into_iter().collect_vec();
+            voxels.sort_by_key(|&(pos, _)| (pos.x, pos.y, pos.z));
+            (voxels, queue)
+        };
+
+        assert_eq!(simulate(), simulate());
+    }
+}
@@ -62,22 +62,44 @@ pub fn spawn_control_window(ui: &imgui::Ui) {
         });
 
         if ui.button("Build") {
-            let mut noise_vals = NOISE_VALS.write();
-            let _ = mem::replace(
-                &mut *noise_vals,
-                Noise2d::new(
-                    SEED.load(Relaxed),
-                    (Chunk::SIZES * USize3::from(*GENERATOR_SIZES.lock().unwrap())).xz(),
-                    FREQUENCY.load(Relaxed),
-                    LACUNARITY.load(Relaxed),
-                    N_OCTAVES.load(Relaxed),
-                    PERSISTENCE.load(Relaxed),
-                ),
-            );
+            rebuild();
         }
     });
 }
 
+/// Rebuilds noise map with current settings and generator sizes.
+pub fn rebuild() {
+    let noise_vals = Noise2d::new(
+        SEED.load(Relaxed),
+        (Chunk::SIZES * USize3::from(*GENERATOR_SIZES.lock().unwrap())).xz(),
+        FREQUENCY.load(Relaxed),
+        LACUNARITY.load(Relaxed),
+        N_OCTAVES.load(Relaxed),
+        PERSISTENCE.load(Relaxed),
+    );
+
+    let _ = mem::replace(&mut *NOISE_VALS.write(), noise_vals);
+}
+
+pub fn seed() -> u32 {
+    SEED.load(Relaxed)
+}
+
+/// Sets noise seed. Takes effect after [`rebuild`].
+pub fn set_seed(seed: u32) {
+    SEED.store(seed, Relaxed);
+}
+
+/// Sets noise frequency. Takes effect after [`rebuild`].
+pub fn set_frequency(frequency: f32) {
+    FREQUENCY.store(frequency, Relaxed);
+}
+
+/// Sets number of noise octaves. Takes effect after [`rebuild`].
+pub fn set_n_octaves(n_octaves: usize) {
+    N_OCTAVES.store(n_octaves, Relaxed);
+}
+
 pub fn perlin(pos: Int3, chunk_array_sizes: USize3) -> i32 {
     let coord_idx = ChunkArray::voxel_pos_to_coord_idx(pos, chunk_array_sizes)
         .expect("failed to convert voxel pos to coord idx");
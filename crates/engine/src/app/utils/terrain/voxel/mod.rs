@@ -1,10 +1,16 @@
 pub mod atlas;
+pub mod block_entity;
+pub mod fluid;
 pub mod generator;
 pub mod voxel_data;
+pub mod world_tick;
 
 use crate::{
     prelude::*,
-    terrain::chunk::mesh::{FullVertex, LowVertex},
+    terrain::chunk::{
+        light::Light,
+        mesh::{FullVertex, LowVertex},
+    },
 };
 use voxel_data::{Id, VoxelData, data::*};
 
@@ -30,6 +36,12 @@ impl Voxel {
     pub fn is_air(&self) -> bool {
         self.data.id == AIR_VOXEL_DATA.id
     }
+
+    /// Gives position of voxel containing world position `pos`.
+    pub fn pos_of(pos: vec3) -> Int3 {
+        let pos = (pos / Self::SIZE).as_array().map(f32::round);
+        Int3::from(vec3::from(pos))
+    }
 }
 
 pub fn is_id_valid(id: Id) -> bool {
@@ -37,6 +49,26 @@ pub fn is_id_valid(id: Id) -> bool {
     (0..VOXEL_DATA.len()).contains(&id)
 }
 
+/// Gives [id][Id] of voxel type named `name` ignoring case.
+pub fn id_by_name(name: &str) -> Option<Id> {
+    VOXEL_DATA
+        .iter()
+        .find(|data| data.name.eq_ignore_ascii_case(name))
+        .map(|data| data.id)
+}
+
+/// Spawns combo box to choose voxel type. Returns `true` if `id` has changed.
+pub fn id_combo(ui: &imgui::Ui, label: &str, id: &mut Id) -> bool {
+    let mut idx = *id as usize;
+
+    let is_changed = ui.combo(label, &mut idx, VOXEL_DATA, |data| data.name.into());
+    if is_changed {
+        *id = idx as Id;
+    }
+
+    is_changed
+}
+
 /// Generalization of voxel details.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LoweredVoxel {
@@ -102,10 +134,11 @@ pub mod shape {
         cfg::terrain::{BACK_IDX, BOTTOM_IDX, FRONT_IDX, LEFT_IDX, RIGHT_IDX, TOP_IDX},
     };
 
-    #[derive(Debug)]
+    #[derive(Clone, Copy, Debug)]
     pub struct CubeDetailed<'c> {
         data: &'c VoxelData,
         half_size: f32,
+        light: Light,
     }
 
     #[derive(Debug)]
@@ -119,16 +152,22 @@ pub mod shape {
             Self {
                 data,
                 half_size: Voxel::SIZE * 0.5,
+                light: Light::SKY,
             }
         }
 
         /// Edit default size.
-        #[allow(dead_code)]
         pub fn size(mut self, new_size: f32) -> Self {
             self.half_size = new_size * 0.5;
             self
         }
 
+        /// Edit light baked into vertices. Default is [`Light::SKY`].
+        pub fn light(mut self, light: Light) -> Self {
+            self.light = light;
+            self
+        }
+
         pub fn by_offset<const N: usize>(
             &self,
             offset: Int3,
@@ -153,12 +192,15 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for front face */
-            let uv = UV::new(self.data.textures.front);
+            /* UVs, texture layer and material for front face */
+            let uv = UV::layer();
+            let layer = self.data.textures.front;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = FRONT_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (
@@ -168,16 +210,25 @@ pub mod shape {
                 ),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (
@@ -187,16 +238,25 @@ pub mod shape {
                 ),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
         }
 
@@ -206,42 +266,63 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for back face */
-            let uv = UV::new(self.data.textures.back);
+            /* UVs, texture layer and material for back face */
+            let uv = UV::layer();
+            let layer = self.data.textures.back;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = BACK_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
         }
 
@@ -251,42 +332,63 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for top face */
-            let uv = UV::new(self.data.textures.top);
+            /* UVs, texture layer and material for top face */
+            let uv = UV::layer();
+            let layer = self.data.textures.top;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = TOP_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
         }
 
@@ -296,12 +398,15 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for bottom face */
-            let uv = UV::new(self.data.textures.bottom);
+            /* UVs, texture layer and material for bottom face */
+            let uv = UV::layer();
+            let layer = self.data.textures.bottom;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = BOTTOM_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (
@@ -311,16 +416,25 @@ pub mod shape {
                 ),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (
@@ -330,16 +444,25 @@ pub mod shape {
                 ),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (-self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             });
         }
 
@@ -349,37 +472,55 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for left face */
-            let uv = UV::new(self.data.textures.left);
+            /* UVs, texture layer and material for left face */
+            let uv = UV::layer();
+            let layer = self.data.textures.left;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = LEFT_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 0 (uv.x_lo, uv.y_lo)
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 1 (uv.x_lo, uv.y_hi)
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 2 (uv.x_hi, uv.y_hi)
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 0
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, -self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 2
             vertices.push(FullVertex {
                 position: (
@@ -389,6 +530,9 @@ pub mod shape {
                 ),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // 3 (uv.x_hi, uv.y_lo)
         }
 
@@ -398,42 +542,63 @@ pub mod shape {
             position: vec3,
             vertices: &mut SmallVec<[FullVertex; N]>,
         ) {
-            /* UVs for right face */
-            let uv = UV::new(self.data.textures.right);
+            /* UVs, texture layer and material for right face */
+            let uv = UV::layer();
+            let layer = self.data.textures.right;
+            let material = self.data.id;
 
             /* Shortcuts */
             let (x, y, z) = position.as_tuple();
             let face_idx = RIGHT_IDX as u8;
+            let light = self.light.into_raw();
 
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // lolo (uv.x_lo, uv.y_lo)
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // hihi
             vertices.push(FullVertex {
                 position: (self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // lohi (uv.x_lo, uv.y_hi)
             vertices.push(FullVertex {
                 position: (self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.lo.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // lolo (uv.x_lo, uv.y_lo)
             vertices.push(FullVertex {
                 position: (-self.half_size + x, -self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.hi.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // hilo
             vertices.push(FullVertex {
                 position: (-self.half_size + x, self.half_size + y, self.half_size + z),
                 tex_coords: (uv.hi.x, uv.lo.y),
                 face_idx,
+                layer,
+                material,
+                light,
             }); // hihi
         }
 
@@ -1,4 +1,5 @@
 use {
+    super::{block_entity::BlockEntityKind, fluid::FluidKind, world_tick::Behavior},
     crate::app::utils::cfg::terrain::voxel_types::VOXEL_DATA as CFG_VOXEL_DATA,
     math_linear::prelude::*,
 };
@@ -14,6 +15,21 @@ pub struct VoxelData {
 
     pub textures: TextureSides,
     pub avarage_color: Color,
+
+    /// Surface of the voxel for physically based shading.
+    pub material: Material,
+
+    /// Kind of [block entity][super::block_entity::BlockEntity] created with the voxel.
+    pub block_entity: Option<BlockEntityKind>,
+
+    /// Block light level emitted by the voxel, see [`Light`][crate::terrain::chunk::light::Light].
+    pub emission: u8,
+
+    /// Kind of [fluid][super::fluid] the voxel is made of.
+    pub fluid: Option<FluidKind>,
+
+    /// How the voxel changes on [world ticks][super::world_tick].
+    pub behavior: Option<Behavior>,
 }
 
 /// Represents textured sides of the voxel.
@@ -65,6 +81,59 @@ impl TextureSides {
     }
 }
 
+/// Surface properties of the voxel used by physically based shading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Microfacet roughness from `0.0` (mirror) to `1.0`.
+    pub roughness: f32,
+    /// `1.0` for metals, `0.0` for dielectrics.
+    pub metallic: f32,
+    /// Brightness of emitted light relative to the albedo.
+    pub emissive: f32,
+    /// Depth of parallax mapping in voxels, `0.0` disables it.
+    /// Height of the surface is taken from the albedo brightness.
+    pub parallax_depth: f32,
+}
+
+impl Material {
+    /// Rough dielectric, most voxels are made of it.
+    pub const DEFAULT: Self = Self::rough(0.9);
+
+    /// Constructs dielectric with `roughness`.
+    pub const fn rough(roughness: f32) -> Self {
+        Self {
+            roughness,
+            metallic: 0.0,
+            emissive: 0.0,
+            parallax_depth: 0.0,
+        }
+    }
+
+    /// Edit default metalness.
+    pub const fn metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    /// Edit default emission.
+    pub const fn emissive(mut self, emissive: f32) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    /// Edit default parallax depth.
+    pub const fn parallax(mut self, depth: f32) -> Self {
+        self.parallax_depth = depth;
+        self
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 pub mod data {
     use super::*;
 
@@ -75,4 +144,16 @@ pub mod data {
     pub const STONE_VOXEL_DATA: &VoxelData = &VOXEL_DATA[2];
     pub const GRASS_VOXEL_DATA: &VoxelData = &VOXEL_DATA[3];
     pub const DIRT_VOXEL_DATA: &VoxelData = &VOXEL_DATA[4];
+    pub const CHEST_VOXEL_DATA: &VoxelData = &VOXEL_DATA[5];
+    pub const SIGN_VOXEL_DATA: &VoxelData = &VOXEL_DATA[6];
+    pub const SPAWNER_VOXEL_DATA: &VoxelData = &VOXEL_DATA[7];
+    pub const TORCH_VOXEL_DATA: &VoxelData = &VOXEL_DATA[8];
+    pub const LAVA_VOXEL_DATA: &VoxelData = &VOXEL_DATA[9];
+    pub const WATER_VOXEL_DATA: &VoxelData = &VOXEL_DATA[10];
+    pub const OBSIDIAN_VOXEL_DATA: &VoxelData = &VOXEL_DATA[11];
+    pub const SAND_VOXEL_DATA: &VoxelData = &VOXEL_DATA[12];
+    pub const GRAVEL_VOXEL_DATA: &VoxelData = &VOXEL_DATA[13];
+    pub const LEAVES_VOXEL_DATA: &VoxelData = &VOXEL_DATA[14];
+    pub const CROP_VOXEL_DATA: &VoxelData = &VOXEL_DATA[15];
+    pub const WHEAT_VOXEL_DATA: &VoxelData = &VOXEL_DATA[16];
 }
@@ -0,0 +1,472 @@
+//!
+//! World ticks: scheduled voxel updates and random ticks.
+//!
+
+use {
+    super::{
+        block_entity::{BlockEntity, Crop},
+        fluid::{self, FluidKind, FluidSpace},
+        voxel_data::{Id, data::*},
+    },
+    crate::{
+        prelude::*,
+        terrain::chunk::{
+            light::Light,
+            schedule::{Priority, UpdateQueue},
+        },
+    },
+    rand::Rng,
+    std::collections::VecDeque,
+};
+
+/// How voxel changes on world ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Behavior {
+    /// Falls down through air and fluids, e.g. sand.
+    Falling,
+    /// Spreads onto lit dirt and dies under other voxels, e.g. grass.
+    Spreading,
+    /// Decays far from logs, e.g. leaves.
+    Decaying,
+    /// Grows in light and breaks without soil, e.g. crops.
+    Growing,
+}
+
+impl Behavior {
+    /// Gives behavior of voxel with `id`.
+    pub fn of(id: Id) -> Option<Self> {
+        VOXEL_DATA.get(id as usize)?.behavior
+    }
+}
+
+/// Space updated by world ticks.
+pub trait WorldSpace: FluidSpace {
+    /// Gives [light][Light] of voxel at `pos`.
+    fn get_light(&self, pos: Int3) -> Light;
+
+    fn get_block_entity(&self, pos: Int3) -> Option<BlockEntity>;
+
+    fn set_block_entity(&mut self, pos: Int3, block_entity: BlockEntity);
+}
+
+/// Maximal number of scheduled updates in one tick.
+pub const MAX_UPDATES_PER_TICK: usize = 4096;
+
+/// Ticks before postponed update is retried.
+pub const INACTIVE_DELAY: u64 = 20;
+
+/// Ticks between steps of falling voxels.
+pub const FALLING_DELAY: u64 = 2;
+
+/// Ticks before leaves check their support after a neighbor change.
+pub const DECAY_DELAY: u64 = 10;
+
+/// Leaves this far from logs decay.
+pub const LEAF_SUPPORT_DISTANCE: usize = 4;
+
+/// Minimal light level for grass to spread and crops to grow.
+pub const MIN_GROWTH_LIGHT: u8 = 9;
+
+const UP: Int3 = veci!(0, 1, 0);
+
+/// Delay and [priority][Priority] of update of voxel with `id`
+/// or [`None`] if it is not affected by neighbor changes.
+fn update_schedule(id: Id) -> Option<(u64, Priority)> {
+    if let Some(kind) = FluidKind::of(id) {
+        return Some((kind.delay(), 1));
+    }
+
+    match Behavior::of(id)? {
+        Behavior::Falling => Some((FALLING_DELAY, 0)),
+        Behavior::Decaying => Some((DECAY_DELAY, 2)),
+        Behavior::Growing => Some((1, 2)),
+        Behavior::Spreading => None,
+    }
+}
+
+fn is_air(space: &impl WorldSpace, pos: Int3) -> bool {
+    space.get_id(pos) == Some(AIR_VOXEL_DATA.id)
+}
+
+fn is_soil(space: &impl WorldSpace, pos: Int3) -> bool {
+    space
+        .get_id(pos)
+        .is_some_and(|id| id == DIRT_VOXEL_DATA.id || id == GRASS_VOXEL_DATA.id)
+}
+
+fn light_level(space: &impl WorldSpace, pos: Int3) -> u8 {
+    let light = space.get_light(pos);
+    light.sky().max(light.block())
+}
+
+/// Schedules updates of voxels at `positions` and next to them.
+pub fn schedule_near(
+    space: &impl WorldSpace,
+    queue: &mut UpdateQueue,
+    positions: impl IntoIterator<Item = Int3>,
+) {
+    for pos in positions {
+        for pos in std::iter::once(pos).chain(SpaceIter::adj_iter(pos)) {
+            if let Some((delay, priority)) = space.get_id(pos).and_then(update_schedule) {
+                queue.schedule(pos, delay, priority);
+            }
+        }
+    }
+}
+
+/// Runs one tick of updates scheduled in `queue`.
+pub fn tick(space: &mut impl WorldSpace, queue: &mut UpdateQueue) {
+    for pos in queue.advance(MAX_UPDATES_PER_TICK) {
+        if !space.is_active(pos) {
+            let priority = space
+                .get_id(pos)
+                .and_then(update_schedule)
+                .map_or(0, |s| s.1);
+            queue.schedule(pos, INACTIVE_DELAY, priority);
+            continue;
+        }
+
+        let changed = update(space, pos);
+        schedule_near(space, queue, changed);
+    }
+}
+
+/// Updates voxel at `pos` after its neighbor has changed. Gives positions that have changed.
+pub fn update(space: &mut impl WorldSpace, pos: Int3) -> Vec<Int3> {
+    let Some(id) = space.get_id(pos) else {
+        return vec![];
+    };
+
+    if FluidKind::of(id).is_some() {
+        return fluid::update(space, pos);
+    }
+
+    match Behavior::of(id) {
+        Some(Behavior::Falling) => fall(space, pos, id),
+        Some(Behavior::Decaying) => decay(space, pos),
+        Some(Behavior::Growing) if !is_soil(space, pos - UP) => {
+            space.set(pos, AIR_VOXEL_DATA.id, 0);
+            vec![pos]
+        }
+        _ => vec![],
+    }
+}
+
+/// Randomly ticks voxel at `pos`. Gives positions that have changed.
+pub fn random_tick(space: &mut impl WorldSpace, pos: Int3, rng: &mut impl Rng) -> Vec<Int3> {
+    let Some(id) = space.get_id(pos) else {
+        return vec![];
+    };
+
+    match Behavior::of(id) {
+        Some(Behavior::Spreading) => spread(space, pos, id, rng),
+        Some(Behavior::Decaying) => decay(space, pos),
+        Some(Behavior::Growing) => grow(space, pos),
+        _ => vec![],
+    }
+}
+
+/// Moves voxel one step down if there's air or fluid below.
+fn fall(space: &mut impl WorldSpace, pos: Int3, id: Id) -> Vec<Int3> {
+    let below = pos - UP;
+
+    match space.get_id(below) {
+        Some(below_id) if below_id == AIR_VOXEL_DATA.id || FluidKind::of(below_id).is_some() => {
+            space.set(below, id, 0);
+            space.set(pos, AIR_VOXEL_DATA.id, 0);
+            vec![pos, below]
+        }
+        _ => vec![],
+    }
+}
+
+/// Grass turns into dirt under other voxels and spreads onto lit dirt nearby.
+fn spread(space: &mut impl WorldSpace, pos: Int3, id: Id, rng: &mut impl Rng) -> Vec<Int3> {
+    // Voxel above may be not loaded yet, grass stays then.
+    if space
+        .get_id(pos + UP)
+        .is_some_and(|id| id != AIR_VOXEL_DATA.id)
+    {
+        space.set(pos, DIRT_VOXEL_DATA.id, 0);
+        return vec![pos];
+    }
+
+    let target = pos
+        + Int3::new(
+            rng.gen_range(-1..=1),
+            rng.gen_range(-3..=1),
+            rng.gen_range(-1..=1),
+        );
+
+    let is_lit_dirt = space.get_id(target) == Some(DIRT_VOXEL_DATA.id)
+        && is_air(space, target + UP)
+        && MIN_GROWTH_LIGHT <= light_level(space, target + UP);
+
+    if !is_lit_dirt {
+        return vec![];
+    }
+
+    space.set(target, id, 0);
+    vec![target]
+}
+
+/// Leaves disappear if there's no log connected through leaves nearby.
+fn decay(space: &mut impl WorldSpace, pos: Int3) -> Vec<Int3> {
+    let mut visited = HashSet::from([pos]);
+    let mut queue = VecDeque::from([(pos, 0)]);
+
+    while let Some((cur, distance)) = queue.pop_front() {
+        for adj in SpaceIter::adj_iter(cur) {
+            let Some(id) = space.get_id(adj) else {
+                continue;
+            };
+
+            if id == LOG_VOXEL_DATA.id {
+                return vec![];
+            }
+
+            let is_leaves = Behavior::of(id) == Some(Behavior::Decaying);
+            if is_leaves && distance + 1 < LEAF_SUPPORT_DISTANCE && visited.insert(adj) {
+                queue.push_back((adj, distance + 1));
+            }
+        }
+    }
+
+    space.set(pos, AIR_VOXEL_DATA.id, 0);
+    vec![pos]
+}
+
+/// Crops grow in light till they are ripe.
+fn grow(space: &mut impl WorldSpace, pos: Int3) -> Vec<Int3> {
+    let Some(BlockEntity::Crop(crop)) = space.get_block_entity(pos) else {
+        return vec![];
+    };
+
+    if light_level(space, pos) < MIN_GROWTH_LIGHT {
+        return vec![];
+    }
+
+    let stage = crop.stage + 1;
+    if stage < Crop::RIPE_STAGE {
+        space.set_block_entity(pos, BlockEntity::Crop(Crop { stage }));
+        return vec![];
+    }
+
+    space.set(pos, WHEAT_VOXEL_DATA.id, 0);
+    vec![pos]
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::terrain::voxel::{block_entity::BlockEntities, fluid::Fluid},
+        rand::{SeedableRng, rngs::StdRng},
+    };
+
+    /// Lit box of air with dirt floor at `y = 0`.
+    #[derive(Debug, Default)]
+    struct TestSpace {
+        voxels: HashMap<Int3, Id>,
+        levels: HashMap<Int3, u8>,
+        block_entities: BlockEntities,
+        dark: HashSet<Int3>,
+        sizes: Int3,
+    }
+
+    impl TestSpace {
+        fn new(sizes: Int3) -> Self {
+            let mut result = Self {
+                sizes,
+                ..Default::default()
+            };
+
+            for x in 0..sizes.x {
+                for z in 0..sizes.z {
+                    result.set(Int3::new(x, 0, z), DIRT_VOXEL_DATA.id, 0);
+                }
+            }
+
+            result
+        }
+
+        fn id(&self, pos: Int3) -> Id {
+            self.get_id(pos).unwrap()
+        }
+
+        fn count(&self, id: Id) -> usize {
+            self.voxels.values().filter(|&&other| other == id).count()
+        }
+    }
+
+    impl FluidSpace for TestSpace {
+        fn get_id(&self, pos: Int3) -> Option<Id> {
+            let is_inside = 0 <= pos.x
+                && 0 <= pos.y
+                && 0 <= pos.z
+                && pos.x < self.sizes.x
+                && pos.y < self.sizes.y
+                && pos.z < self.sizes.z;
+
+            is_inside.then(|| self.voxels.get(&pos).copied().unwrap_or_default())
+        }
+
+        fn get_level(&self, pos: Int3) -> u8 {
+            self.levels
+                .get(&pos)
+                .copied()
+                .unwrap_or(Fluid::SOURCE_LEVEL)
+        }
+
+        fn set(&mut self, pos: Int3, id: Id, level: u8) {
+            self.voxels.insert(pos, id);
+            self.levels.insert(pos, level);
+            self.block_entities.remove(&pos);
+
+            if let Some(kind) = VOXEL_DATA[id as usize].block_entity {
+                self.block_entities.insert(pos, kind.instantiate());
+            }
+        }
+    }
+
+    impl WorldSpace for TestSpace {
+        fn get_light(&self, pos: Int3) -> Light {
+            match self.dark.contains(&pos) {
+                true => Light::DARK,
+                false => Light::SKY,
+            }
+        }
+
+        fn get_block_entity(&self, pos: Int3) -> Option<BlockEntity> {
+            self.block_entities.get(&pos).cloned()
+        }
+
+        fn set_block_entity(&mut self, pos: Int3, block_entity: BlockEntity) {
+            self.block_entities.insert(pos, block_entity);
+        }
+    }
+
+    fn run(space: &mut TestSpace, queue: &mut UpdateQueue, n_ticks: usize) {
+        for _ in 0..n_ticks {
+            tick(space, queue);
+        }
+    }
+
+    fn random_ticks(space: &mut TestSpace, n_ticks: usize) {
+        let mut rng = StdRng::seed_from_u64(39);
+        let sizes = space.sizes;
+
+        for _ in 0..n_ticks {
+            let pos = Int3::new(
+                rng.gen_range(0..sizes.x),
+                rng.gen_range(0..sizes.y),
+                rng.gen_range(0..sizes.z),
+            );
+
+            random_tick(space, pos, &mut rng);
+        }
+    }
+
+    #[test]
+    fn sand_falls_through_air_and_water() {
+        let mut space = TestSpace::new(Int3::new(4, 12, 4));
+        let mut queue = UpdateQueue::new();
+
+        let water = Int3::new(1, 1, 1);
+        space.set(water, WATER_VOXEL_DATA.id, Fluid::SOURCE_LEVEL);
+
+        let sand = Int3::new(1, 10, 1);
+        space.set(sand, SAND_VOXEL_DATA.id, 0);
+        space.set(sand + UP, GRAVEL_VOXEL_DATA.id, 0);
+        schedule_near(&space, &mut queue, [sand]);
+        run(&mut space, &mut queue, 100);
+
+        assert_eq!(space.id(water), SAND_VOXEL_DATA.id);
+        assert_eq!(space.id(water + UP), GRAVEL_VOXEL_DATA.id);
+        assert_eq!(space.id(sand), AIR_VOXEL_DATA.id);
+    }
+
+    #[test]
+    fn grass_spreads_onto_lit_dirt() {
+        let mut space = TestSpace::new(Int3::new(8, 3, 8));
+        space.set(Int3::new(4, 0, 4), GRASS_VOXEL_DATA.id, 0);
+
+        // Covered dirt stays dirt.
+        let covered = Int3::new(0, 0, 0);
+        space.set(covered + UP, STONE_VOXEL_DATA.id, 0);
+
+        // So does dirt in darkness.
+        let dark = Int3::new(7, 0, 7);
+        space.dark.insert(dark + UP);
+
+        random_ticks(&mut space, 20_000);
+
+        assert_eq!(space.count(GRASS_VOXEL_DATA.id), 8 * 8 - 2);
+        assert_eq!(space.id(covered), DIRT_VOXEL_DATA.id);
+        assert_eq!(space.id(dark), DIRT_VOXEL_DATA.id);
+    }
+
+    #[test]
+    fn grass_stays_under_missing_voxel() {
+        let mut space = TestSpace::new(Int3::new(1, 1, 1));
+        space.set(Int3::ZERO, GRASS_VOXEL_DATA.id, 0);
+
+        random_ticks(&mut space, 100);
+
+        assert_eq!(space.id(Int3::ZERO), GRASS_VOXEL_DATA.id);
+    }
+
+    #[test]
+    fn leaves_decay_without_log() {
+        let mut space = TestSpace::new(Int3::new(12, 8, 12));
+        let mut queue = UpdateQueue::new();
+
+        let log = Int3::new(6, 1, 6);
+        space.set(log, LOG_VOXEL_DATA.id, 0);
+
+        // Line of leaves going from the log.
+        for x in 7..12 {
+            space.set(Int3::new(x, 1, 6), LEAVES_VOXEL_DATA.id, 0);
+        }
+
+        random_ticks(&mut space, 50_000);
+        assert_eq!(space.count(LEAVES_VOXEL_DATA.id), LEAF_SUPPORT_DISTANCE);
+
+        space.set(log, AIR_VOXEL_DATA.id, 0);
+        schedule_near(&space, &mut queue, [log]);
+        run(&mut space, &mut queue, 100);
+        random_ticks(&mut space, 50_000);
+
+        assert_eq!(space.count(LEAVES_VOXEL_DATA.id), 0);
+    }
+
+    #[test]
+    fn crops_grow_in_light_and_break_without_soil() {
+        let mut space = TestSpace::new(Int3::new(4, 4, 4));
+        let mut queue = UpdateQueue::new();
+
+        let lit = Int3::new(1, 1, 1);
+        let dark = Int3::new(2, 1, 2);
+        space.set(lit, CROP_VOXEL_DATA.id, 0);
+        space.set(dark, CROP_VOXEL_DATA.id, 0);
+        space.dark.insert(dark);
+
+        let mut rng = StdRng::seed_from_u64(39);
+        for _ in 0..Crop::RIPE_STAGE {
+            random_tick(&mut space, lit, &mut rng);
+            random_tick(&mut space, dark, &mut rng);
+        }
+
+        assert_eq!(space.id(lit), WHEAT_VOXEL_DATA.id);
+        assert_eq!(
+            space.get_block_entity(dark),
+            Some(BlockEntity::Crop(Crop::default())),
+        );
+
+        space.set(dark - UP, AIR_VOXEL_DATA.id, 0);
+        schedule_near(&space, &mut queue, [dark - UP]);
+        run(&mut space, &mut queue, 10);
+
+        assert_eq!(space.id(dark), AIR_VOXEL_DATA.id);
+    }
+}
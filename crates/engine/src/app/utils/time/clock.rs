@@ -0,0 +1,306 @@
+//!
+//! World clock driving the day/night cycle.
+//!
+
+use {
+    crate::{app::utils::reinterpreter::ReinterpretError, prelude::*},
+    std::f32::consts::TAU,
+};
+
+/// Colors of the world at some time of day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DayColors {
+    pub sky: Color,
+    pub fog: Color,
+    pub ambient: Color,
+}
+
+impl DayColors {
+    /// Linear interpolation between `self` and `other`.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let lerp = |from: Color, to: Color| from * (1.0 - t) + to * t;
+
+        Self {
+            sky: lerp(self.sky, other.sky),
+            fog: lerp(self.fog, other.fog),
+            ambient: lerp(self.ambient, other.ambient),
+        }
+    }
+}
+
+/// Time of day with its colors. `0.0` is midnight, `0.5` is noon.
+const KEYFRAMES: [(f32, DayColors); 7] = [
+    (0.0, NIGHT),
+    (0.2, NIGHT),
+    (0.27, DAWN),
+    (0.35, DAY),
+    (0.65, DAY),
+    (0.73, DAWN),
+    (0.8, NIGHT),
+];
+
+const NIGHT: DayColors = DayColors {
+    sky: Color::new(0.01, 0.01, 0.03),
+    fog: Color::new(0.02, 0.02, 0.05),
+    ambient: Color::new(0.02, 0.02, 0.04),
+};
+
+const DAWN: DayColors = DayColors {
+    sky: Color::new(0.85, 0.45, 0.25),
+    fog: Color::new(0.80, 0.55, 0.40),
+    ambient: Color::new(0.05, 0.035, 0.03),
+};
+
+const DAY: DayColors = DayColors {
+    sky: Color::new(0.45, 0.65, 0.95),
+    fog: Color::new(0.70, 0.80, 0.95),
+    ambient: Color::new(0.05, 0.05, 0.05),
+};
+
+/// Counts time of day, rotates sun and moon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldClock {
+    /// Fraction of the day passed, in `[0, 1)`.
+    pub time: f32,
+    /// Duration of the whole day in seconds.
+    pub day_length: f32,
+    pub is_paused: bool,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self {
+            time: Self::MORNING,
+            day_length: Self::DEFAULT_DAY_LENGTH,
+            is_paused: false,
+        }
+    }
+}
+
+impl WorldClock {
+    pub const DEFAULT_DAY_LENGTH: f32 = 20.0 * 60.0;
+    pub const MIN_DAY_LENGTH: f32 = 10.0;
+    pub const MAX_DAY_LENGTH: f32 = 3.0 * 60.0 * 60.0;
+
+    pub const MIDNIGHT: f32 = 0.0;
+    pub const MORNING: f32 = 0.3;
+    pub const NOON: f32 = 0.5;
+    pub const EVENING: f32 = 0.75;
+
+    /// Tilt of sun path from the zenith.
+    const ORBIT_TILT: f32 = 0.3;
+
+    /// Advances clock by `dt` seconds if it is not paused.
+    pub fn update(&mut self, dt: f32) {
+        if !self.is_paused {
+            self.time = (self.time + dt / self.day_length).rem_euclid(1.0);
+        }
+    }
+
+    /// Sets time of day, wraps it into `[0, 1)`.
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+
+    /// Direction from the world to the sun.
+    pub fn to_sun(&self) -> vec3 {
+        let angle = (self.time - 0.25) * TAU;
+
+        vec3::new(
+            f32::cos(angle),
+            f32::sin(angle) * f32::cos(Self::ORBIT_TILT),
+            f32::sin(angle) * f32::sin(Self::ORBIT_TILT),
+        )
+    }
+
+    /// Direction of sun light.
+    pub fn sun_dir(&self) -> vec3 {
+        -self.to_sun()
+    }
+
+    /// Direction of moon light. Moon is opposite to the sun.
+    pub fn moon_dir(&self) -> vec3 {
+        self.to_sun()
+    }
+
+    /// Brightness of the sun, zero while it is below the horizon.
+    pub fn sun_intensity(&self) -> f32 {
+        (4.0 * self.to_sun().y).clamp(0.0, 1.0)
+    }
+
+    /// Brightness of the moon, zero while it is below the horizon.
+    pub fn moon_intensity(&self) -> f32 {
+        const MAX_INTENSITY: f32 = 0.15;
+        MAX_INTENSITY * (-4.0 * self.to_sun().y).clamp(0.0, 1.0)
+    }
+
+    /// Sky, fog and ambient colors interpolated between keyframes.
+    pub fn colors(&self) -> DayColors {
+        let next_idx = KEYFRAMES
+            .iter()
+            .position(|&(time, _)| self.time < time)
+            .unwrap_or(KEYFRAMES.len());
+
+        let (from_time, from) = KEYFRAMES[next_idx - 1];
+        let (to_time, to) = match KEYFRAMES.get(next_idx) {
+            Some(&keyframe) => keyframe,
+            None => (1.0 + KEYFRAMES[0].0, KEYFRAMES[0].1),
+        };
+
+        from.lerp(to, (self.time - from_time) / (to_time - from_time))
+    }
+
+    /// Time of day as `hh:mm`.
+    pub fn time_string(&self) -> String {
+        let minutes = (self.time * 24.0 * 60.0) as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    pub fn spawn_control_window(&mut self, ui: &imgui::Ui) {
+        use crate::app::utils::graphics::ui::imgui_constructor::make_window;
+
+        make_window(ui, "Time").build(|| {
+            ui.text(format!("Time of day: {}", self.time_string()));
+
+            let pause_label = if self.is_paused { "Resume" } else { "Pause" };
+            if ui.button(pause_label) {
+                self.is_paused = !self.is_paused;
+            }
+
+            let mut time = self.time;
+            if ui.slider("Time", 0.0, 1.0, &mut time) {
+                self.set_time(time);
+            }
+
+            for (name, time) in [
+                ("Morning", Self::MORNING),
+                ("Noon", Self::NOON),
+                ("Evening", Self::EVENING),
+                ("Midnight", Self::MIDNIGHT),
+            ] {
+                if ui.button(name) {
+                    self.set_time(time);
+                }
+                ui.same_line();
+            }
+            ui.new_line();
+
+            ui.slider(
+                "Day length (s)",
+                Self::MIN_DAY_LENGTH,
+                Self::MAX_DAY_LENGTH,
+                &mut self.day_length,
+            );
+
+            let sun_angle = (self.time - 0.25) * TAU;
+            ui.text(format!("Sun angle: {:.1}°", sun_angle.to_degrees()));
+        });
+    }
+}
+
+impl AsBytes for WorldClock {
+    fn as_bytes(&self) -> Vec<u8> {
+        compose! {
+            self.time.as_bytes(),
+            self.day_length.as_bytes(),
+            self.is_paused.as_bytes(),
+        }
+        .collect()
+    }
+}
+
+impl FromBytes for WorldClock {
+    fn from_bytes(source: &[u8]) -> Result<Self, ReinterpretError> {
+        let mut reader = ByteReader::new(source);
+
+        let time: f32 = reader.read()?;
+        let day_length: f32 = reader.read()?;
+        let is_paused = reader.read()?;
+
+        Ok(Self {
+            time: time.rem_euclid(1.0),
+            day_length: day_length.clamp(Self::MIN_DAY_LENGTH, Self::MAX_DAY_LENGTH),
+            is_paused,
+        })
+    }
+}
+
+impl StaticSize for WorldClock {
+    fn static_size() -> usize {
+        2 * f32::static_size() + bool::static_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(lhs: DayColors, rhs: DayColors) -> bool {
+        [
+            (lhs.sky, rhs.sky),
+            (lhs.fog, rhs.fog),
+            (lhs.ambient, rhs.ambient),
+        ]
+        .into_iter()
+        .all(|(lhs, rhs)| (lhs - rhs).len() < 1e-5)
+    }
+
+    #[test]
+    fn time_wraps_around_day() {
+        let mut clock = WorldClock {
+            time: 0.9,
+            day_length: 100.0,
+            is_paused: false,
+        };
+
+        clock.update(20.0);
+        assert!((clock.time - 0.1).abs() < 1e-5);
+
+        clock.is_paused = true;
+        clock.update(20.0);
+        assert!((clock.time - 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sun_is_up_at_noon_and_down_at_midnight() {
+        let mut clock = WorldClock::default();
+
+        clock.set_time(WorldClock::NOON);
+        assert!(clock.to_sun().y > 0.9);
+        assert_eq!(clock.sun_intensity(), 1.0);
+        assert_eq!(clock.moon_intensity(), 0.0);
+
+        clock.set_time(WorldClock::MIDNIGHT);
+        assert!(clock.sun_dir().y > 0.9);
+        assert_eq!(clock.sun_intensity(), 0.0);
+        assert!(clock.moon_intensity() > 0.0);
+    }
+
+    #[test]
+    fn colors_are_interpolated_between_keyframes() {
+        let mut clock = WorldClock::default();
+
+        clock.set_time(WorldClock::NOON);
+        assert!(approx_eq(clock.colors(), DAY));
+
+        clock.set_time(0.95);
+        assert!(approx_eq(clock.colors(), NIGHT));
+
+        clock.set_time(0.31);
+        let colors = clock.colors();
+        assert!(DAWN.sky.b < colors.sky.b && colors.sky.b < DAY.sky.b);
+    }
+
+    #[test]
+    fn reinterpret_clock() {
+        let before = WorldClock {
+            time: 0.42,
+            day_length: 600.0,
+            is_paused: true,
+        };
+
+        let bytes = before.as_bytes();
+        assert_eq!(bytes.len(), WorldClock::static_size());
+        assert_eq!(WorldClock::from_bytes(&bytes).unwrap(), before);
+    }
+}
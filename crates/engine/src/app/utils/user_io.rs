@@ -8,7 +8,7 @@ use {
 };
 
 use glium::winit::dpi::PhysicalPosition;
-use glium::winit::event::{ElementState, Event, MouseButton, WindowEvent};
+use glium::winit::event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent};
 use glium::winit::window::{CursorGrabMode, Window};
 use winit::keyboard::PhysicalKey;
 
@@ -112,6 +112,7 @@ pub mod mouse {
     pub(super) static DY: AtomicF32 = AtomicF32::new(0.0);
     pub(super) static X: AtomicF32 = AtomicF32::new(0.0);
     pub(super) static Y: AtomicF32 = AtomicF32::new(0.0);
+    pub(super) static WHEEL_DY: AtomicF32 = AtomicF32::new(0.0);
     pub(super) static IS_ON_WINDOW: AtomicBool = AtomicBool::new(false);
     pub(super) static IS_GRABBED: AtomicBool = AtomicBool::new(false);
 
@@ -128,6 +129,11 @@ pub mod mouse {
         DY.load(Relaxed)
     }
 
+    /// Gives wheel scroll in lines since last update. Positive is scrolling up.
+    pub fn get_wheel_dy() -> f32 {
+        WHEEL_DY.load(Relaxed)
+    }
+
     pub fn press(button: MouseButton) {
         INPUTS.write().unwrap().insert(button);
     }
@@ -175,6 +181,22 @@ pub mod mouse {
     }
 
     pub fn handle_event(event: &Event<()>, _window: &Window) {
+        /* Touchpads scroll by pixels */
+        const PIXELS_PER_LINE: f32 = 32.0;
+
+        if let Event::WindowEvent {
+            event: WindowEvent::MouseWheel { delta, .. },
+            ..
+        } = event
+        {
+            let dy = match delta {
+                MouseScrollDelta::LineDelta(_, dy) => *dy,
+                MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+            };
+
+            WHEEL_DY.fetch_add(dy, AcqRel);
+        }
+
         if let Event::WindowEvent {
             event: WindowEvent::CursorMoved { position, .. },
             ..
@@ -213,6 +235,7 @@ pub mod mouse {
 
         DX.store(0.0, Release);
         DY.store(0.0, Release);
+        WHEEL_DY.store(0.0, Release);
 
         /* If cursor grabbed then not change mouse position and put cursor on center */
         if IS_GRABBED.load(Relaxed) {
@@ -1,7 +1,7 @@
 use {crate::math::ray::space_3d::Line, crate::prelude::*};
 
 /// Represents axis aligned bounding box
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Aabb {
     lo: vec3,
     hi: vec3,
@@ -22,6 +22,34 @@ impl Aabb {
         }
     }
 
+    /// Gives minimal corner.
+    pub const fn lo(self) -> vec3 {
+        self.lo
+    }
+
+    /// Gives maximal corner.
+    pub const fn hi(self) -> vec3 {
+        self.hi
+    }
+
+    /// Gives AABB moved by `offset`.
+    pub fn translated(self, offset: vec3) -> Self {
+        Self {
+            lo: self.lo + offset,
+            hi: self.hi + offset,
+        }
+    }
+
+    /// Checks if two AABBs overlap with non-zero volume.
+    pub fn intersects(self, other: Self) -> bool {
+        self.lo.x < other.hi.x
+            && other.lo.x < self.hi.x
+            && self.lo.y < other.hi.y
+            && other.lo.y < self.hi.y
+            && self.lo.z < other.hi.z
+            && other.lo.z < self.hi.z
+    }
+
     /// Represents AABB as corner vertex array.
     pub const fn as_vertex_array(self) -> [vec3; 8] {
         [
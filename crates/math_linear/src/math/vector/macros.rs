@@ -764,6 +764,7 @@ macro_rules! vector_macro {
     };
 }
 
+#[allow(unused_imports)]
 pub(crate) use vector_macro;
 
 use super::{Float2, Float3, Int2, Int3, UInt2, UInt3, USize2, USize3};